@@ -0,0 +1,16 @@
+use std::env;
+
+// generates fec.h from the exported functions in src/lib.rs into OUT_DIR,
+// the checked-in include/fec.h is compared against it in the tests
+fn main() {
+    let crate_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
+    let out_dir = env::var("OUT_DIR").unwrap();
+    println!("cargo:rerun-if-changed=src/lib.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    cbindgen::Builder::new()
+        .with_crate(&crate_dir)
+        .with_config(cbindgen::Config::from_file(format!("{crate_dir}/cbindgen.toml")).unwrap())
+        .generate()
+        .expect("generate C header")
+        .write_to_file(format!("{out_dir}/fec.h"));
+}
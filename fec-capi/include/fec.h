@@ -0,0 +1,114 @@
+/*
+ * C API for fec-parser.
+ *
+ * Ownership:
+ * - FecFiling handles come from fec_filing_open_path/fec_filing_open_buffer
+ *   and are freed with fec_filing_free.
+ * - FecRow handles come from fec_filing_next_row and are freed with
+ *   fec_row_free. Rows stay valid after their filing is freed.
+ * - Strings returned by fec_filing_* and fec_row_* functions are owned by
+ *   the filing or row they came from, and are valid until it is freed.
+ *   Don't free them.
+ * - Error messages written to a `char **error` argument are owned by the
+ *   caller and freed with fec_string_free.
+ * - Lookups by name or index return NULL for unknown columns and out of
+ *   range indexes.
+ * - fec_filing_next_row returns FEC_STATUS_OK with a row, FEC_STATUS_DONE
+ *   after the last row, or FEC_STATUS_ERROR, after which reading can
+ *   continue. Errors are also kept in the filing's diagnostics, along with
+ *   spec problems once fec_filing_enable_validation is called.
+ * - Handles aren't thread safe, but different handles can be used from
+ *   different threads.
+ */
+
+
+#ifndef FEC_H
+#define FEC_H
+
+/* Generated by cbindgen from fec-capi/src/lib.rs, do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef enum FecSeverity {
+  FEC_SEVERITY_ERROR = 0,
+  FEC_SEVERITY_WARNING = 1,
+} FecSeverity;
+
+typedef enum FecStatus {
+  FEC_STATUS_OK = 0,
+  FEC_STATUS_DONE = 1,
+  FEC_STATUS_ERROR = -1,
+} FecStatus;
+
+typedef struct FecFiling FecFiling;
+
+typedef struct FecRow FecRow;
+
+typedef struct FecDiagnostic {
+  uint64_t line;
+  enum FecSeverity severity;
+  const char *row_type;
+  const char *column;
+  const char *message;
+} FecDiagnostic;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+struct FecFiling *fec_filing_open_path(const char *path, char **error);
+
+struct FecFiling *fec_filing_open_buffer(const uint8_t *data,
+                                         size_t len,
+                                         const char *filing_id,
+                                         char **error);
+
+void fec_filing_free(struct FecFiling *filing);
+
+const char *fec_filing_id(const struct FecFiling *filing);
+
+const char *fec_filing_header(const struct FecFiling *filing, const char *field);
+
+const char *fec_filing_form_type(const struct FecFiling *filing);
+
+const char *fec_filing_cover(const struct FecFiling *filing, const char *column);
+
+size_t fec_filing_cover_count(const struct FecFiling *filing);
+
+const char *fec_filing_cover_column(const struct FecFiling *filing, size_t idx);
+
+const char *fec_filing_cover_value(const struct FecFiling *filing, size_t idx);
+
+void fec_filing_enable_validation(struct FecFiling *filing);
+
+enum FecStatus fec_filing_next_row(struct FecFiling *filing, struct FecRow **out, char **error);
+
+size_t fec_filing_diagnostic_count(const struct FecFiling *filing);
+
+const struct FecDiagnostic *fec_filing_diagnostic(const struct FecFiling *filing, size_t idx);
+
+void fec_row_free(struct FecRow *row);
+
+const char *fec_row_type(const struct FecRow *row);
+
+uint64_t fec_row_line(const struct FecRow *row);
+
+size_t fec_row_field_count(const struct FecRow *row);
+
+const char *fec_row_field(const struct FecRow *row, size_t idx);
+
+const char *fec_row_column(const struct FecRow *row, size_t idx);
+
+const char *fec_row_get(const struct FecRow *row, const char *column);
+
+void fec_string_free(char *value);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* FEC_H */
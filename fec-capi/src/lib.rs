@@ -0,0 +1,518 @@
+// C API for fec-parser. The functions are `unsafe` since they take raw
+// pointers from C callers, the rules for those pointers are documented in
+// include/fec.h.
+#![allow(clippy::missing_safety_doc)]
+
+use fec_parser::{
+    validation::spec::{Severity, SpecValidator},
+    Filing, FilingRow,
+};
+use std::{
+    ffi::{c_char, CStr, CString},
+    fs::File,
+    io::{Cursor, Read},
+    panic::{catch_unwind, AssertUnwindSafe},
+    path::Path,
+    ptr,
+};
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FecStatus {
+    Ok = 0,
+    Done = 1,
+    Error = -1,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FecSeverity {
+    Error = 0,
+    Warning = 1,
+}
+
+// A problem found while reading a filing. Strings are owned by the filing,
+// `column` is NULL when the problem isn't about a single field and `line`
+// is 0 when unknown.
+#[repr(C)]
+pub struct FecDiagnostic {
+    pub line: u64,
+    pub severity: FecSeverity,
+    pub row_type: *const c_char,
+    pub column: *const c_char,
+    pub message: *const c_char,
+}
+
+struct Diagnostic {
+    _row_type: CString,
+    _column: Option<CString>,
+    _message: CString,
+    // points into the CStrings above, which don't move with the struct
+    view: FecDiagnostic,
+}
+
+impl Diagnostic {
+    fn new(
+        line: u64,
+        severity: FecSeverity,
+        row_type: &str,
+        column: Option<&str>,
+        message: &str,
+    ) -> Self {
+        let row_type = cstring(row_type);
+        let column = column.map(cstring);
+        let message = cstring(message);
+        let view = FecDiagnostic {
+            line,
+            severity,
+            row_type: row_type.as_ptr(),
+            column: column.as_ref().map_or(ptr::null(), |v| v.as_ptr()),
+            message: message.as_ptr(),
+        };
+        Self {
+            _row_type: row_type,
+            _column: column,
+            _message: message,
+            view,
+        }
+    }
+}
+
+pub struct FecFiling {
+    filing: Filing<Box<dyn Read>>,
+    filing_id: CString,
+    header: Vec<(&'static str, CString)>,
+    cover_form_type: CString,
+    cover: Vec<(CString, CString)>,
+    validator: Option<SpecValidator>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+pub struct FecRow {
+    row_type: CString,
+    line: u64,
+    fields: Vec<CString>,
+    // NULL for fields past the known columns of the row's version
+    columns: Vec<Option<CString>>,
+}
+
+// interior NULs can't be represented in C strings, so they're dropped
+fn cstring(value: &str) -> CString {
+    CString::new(value.replace('\0', "")).unwrap()
+}
+
+fn set_error(error: *mut *mut c_char, message: &str) {
+    if !error.is_null() {
+        unsafe { *error = cstring(message).into_raw() };
+    }
+}
+
+unsafe fn str_arg<'a>(value: *const c_char) -> Option<&'a str> {
+    if value.is_null() {
+        return None;
+    }
+    CStr::from_ptr(value).to_str().ok()
+}
+
+fn open(
+    rdr: Box<dyn Read>,
+    filing_id: String,
+    source_length: Option<usize>,
+    error: *mut *mut c_char,
+) -> *mut FecFiling {
+    let filing = catch_unwind(AssertUnwindSafe(|| {
+        Filing::from_reader(rdr, filing_id, source_length)
+    }));
+    let filing = match filing {
+        Ok(Ok(filing)) => filing,
+        Ok(Err(e)) => {
+            set_error(error, &e.to_string());
+            return ptr::null_mut();
+        }
+        Err(_) => {
+            set_error(
+                error,
+                "Could not read the filing's header and cover records",
+            );
+            return ptr::null_mut();
+        }
+    };
+    let header = &filing.header;
+    let header = vec![
+        ("record_type", cstring(&header.record_type)),
+        ("ef_type", cstring(&header.ef_type)),
+        ("fec_version", cstring(&header.fec_version)),
+        ("soft_name", cstring(&header.soft_name)),
+        ("soft_ver", cstring(&header.soft_ver)),
+        (
+            "report_id",
+            cstring(header.report_id.as_deref().unwrap_or("")),
+        ),
+        (
+            "report_number",
+            cstring(header.report_number.as_deref().unwrap_or("")),
+        ),
+        ("comment", cstring(header.comment.as_deref().unwrap_or(""))),
+    ];
+    let cover = filing
+        .cover
+        .iter_named()
+        .map(|(column, value)| (cstring(column), cstring(value)))
+        .collect();
+    Box::into_raw(Box::new(FecFiling {
+        filing_id: cstring(&filing.filing_id),
+        cover_form_type: cstring(&filing.cover.form_type),
+        header,
+        cover,
+        filing,
+        validator: None,
+        diagnostics: vec![],
+    }))
+}
+
+// Opens a .fec file. The filing ID is the file name without its extension.
+// Returns NULL on failure, with `*error` set to a message if `error` isn't
+// NULL.
+#[no_mangle]
+pub unsafe extern "C" fn fec_filing_open_path(
+    path: *const c_char,
+    error: *mut *mut c_char,
+) -> *mut FecFiling {
+    let Some(path) = str_arg(path) else {
+        set_error(error, "path must be a UTF-8 string");
+        return ptr::null_mut();
+    };
+    let path = Path::new(path);
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) => {
+            set_error(error, &format!("Error opening {}: {e}", path.display()));
+            return ptr::null_mut();
+        }
+    };
+    let filing_id = path
+        .file_stem()
+        .map(|v| v.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let source_length = file.metadata().map(|v| v.len() as usize).ok();
+    open(Box::new(file), filing_id, source_length, error)
+}
+
+// Opens a filing from memory. The buffer is copied, so it can be freed as
+// soon as this returns. `filing_id` may be NULL.
+#[no_mangle]
+pub unsafe extern "C" fn fec_filing_open_buffer(
+    data: *const u8,
+    len: usize,
+    filing_id: *const c_char,
+    error: *mut *mut c_char,
+) -> *mut FecFiling {
+    if data.is_null() && len > 0 {
+        set_error(error, "data is NULL");
+        return ptr::null_mut();
+    }
+    let data = if len == 0 {
+        vec![]
+    } else {
+        std::slice::from_raw_parts(data, len).to_vec()
+    };
+    let filing_id = str_arg(filing_id).unwrap_or("").to_owned();
+    open(Box::new(Cursor::new(data)), filing_id, Some(len), error)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn fec_filing_free(filing: *mut FecFiling) {
+    if !filing.is_null() {
+        drop(Box::from_raw(filing));
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn fec_filing_id(filing: *const FecFiling) -> *const c_char {
+    (&*filing).filing_id.as_ptr()
+}
+
+// ex "fec_version", "soft_name", "report_id". NULL for unknown fields,
+// empty for fields the header left out.
+#[no_mangle]
+pub unsafe extern "C" fn fec_filing_header(
+    filing: *const FecFiling,
+    field: *const c_char,
+) -> *const c_char {
+    let Some(field) = str_arg(field) else {
+        return ptr::null();
+    };
+    (&*filing)
+        .header
+        .iter()
+        .find(|(name, _)| *name == field)
+        .map_or(ptr::null(), |(_, value)| value.as_ptr())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn fec_filing_form_type(filing: *const FecFiling) -> *const c_char {
+    (&*filing).cover_form_type.as_ptr()
+}
+
+// a cover record field by column name, ex "filer_committee_id_number"
+#[no_mangle]
+pub unsafe extern "C" fn fec_filing_cover(
+    filing: *const FecFiling,
+    column: *const c_char,
+) -> *const c_char {
+    let Some(column) = str_arg(column) else {
+        return ptr::null();
+    };
+    (&*filing)
+        .cover
+        .iter()
+        .find(|(name, _)| name.to_bytes() == column.as_bytes())
+        .map_or(ptr::null(), |(_, value)| value.as_ptr())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn fec_filing_cover_count(filing: *const FecFiling) -> usize {
+    (&*filing).cover.len()
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn fec_filing_cover_column(
+    filing: *const FecFiling,
+    idx: usize,
+) -> *const c_char {
+    (&*filing)
+        .cover
+        .get(idx)
+        .map_or(ptr::null(), |(column, _)| column.as_ptr())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn fec_filing_cover_value(
+    filing: *const FecFiling,
+    idx: usize,
+) -> *const c_char {
+    (&*filing)
+        .cover
+        .get(idx)
+        .map_or(ptr::null(), |(_, value)| value.as_ptr())
+}
+
+// Checks every row read from now on against the FEC spec, adding any
+// problems to the filing's diagnostics. Off by default, since it's slower.
+#[no_mangle]
+pub unsafe extern "C" fn fec_filing_enable_validation(filing: *mut FecFiling) {
+    let filing = &mut *filing;
+    if filing.validator.is_none() {
+        filing.validator = Some(SpecValidator::new(&filing.filing.header.fec_version));
+    }
+}
+
+fn severity(severity: Severity) -> FecSeverity {
+    match severity {
+        Severity::Error => FecSeverity::Error,
+        Severity::Warning => FecSeverity::Warning,
+    }
+}
+
+fn row(filing: &mut FecFiling, row: FilingRow) -> FecRow {
+    let line = row.record.position().map_or(0, |p| p.line());
+    if let Some(validator) = &mut filing.validator {
+        for finding in validator.validate_record(Some(line), &row.record) {
+            filing.diagnostics.push(Diagnostic::new(
+                line,
+                severity(finding.severity()),
+                &finding.row_type,
+                finding.column.as_deref(),
+                &finding.message,
+            ));
+        }
+    }
+    let columns = row.columns();
+    FecRow {
+        row_type: cstring(&row.row_type),
+        line,
+        fields: row.record.iter().map(cstring).collect(),
+        columns: (0..row.record.len())
+            .map(|idx| columns.get(idx).map(|column| cstring(column)))
+            .collect(),
+    }
+}
+
+// Reads the next row into `*row`, which must be freed with fec_row_free.
+// Returns FEC_STATUS_DONE after the last row. On FEC_STATUS_ERROR the error
+// is also added to the diagnostics, and reading can continue.
+#[no_mangle]
+pub unsafe extern "C" fn fec_filing_next_row(
+    filing: *mut FecFiling,
+    out: *mut *mut FecRow,
+    error: *mut *mut c_char,
+) -> FecStatus {
+    let filing = &mut *filing;
+    if !out.is_null() {
+        *out = ptr::null_mut();
+    }
+    let next = catch_unwind(AssertUnwindSafe(|| filing.filing.next_row()));
+    let message = match next {
+        Ok(None) => return FecStatus::Done,
+        Ok(Some(Ok(r))) => {
+            let r = row(filing, r);
+            if !out.is_null() {
+                *out = Box::into_raw(Box::new(r));
+            }
+            return FecStatus::Ok;
+        }
+        Ok(Some(Err(e))) => e.to_string(),
+        Err(_) => "Unexpected error reading the next row".to_owned(),
+    };
+    filing
+        .diagnostics
+        .push(Diagnostic::new(0, FecSeverity::Error, "", None, &message));
+    set_error(error, &message);
+    FecStatus::Error
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn fec_filing_diagnostic_count(filing: *const FecFiling) -> usize {
+    (&*filing).diagnostics.len()
+}
+
+// NULL when out of range. Valid until the filing is freed.
+#[no_mangle]
+pub unsafe extern "C" fn fec_filing_diagnostic(
+    filing: *const FecFiling,
+    idx: usize,
+) -> *const FecDiagnostic {
+    (&*filing)
+        .diagnostics
+        .get(idx)
+        .map_or(ptr::null(), |diagnostic| &diagnostic.view)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn fec_row_free(row: *mut FecRow) {
+    if !row.is_null() {
+        drop(Box::from_raw(row));
+    }
+}
+
+// ex "SA11AI", with any '/' removed
+#[no_mangle]
+pub unsafe extern "C" fn fec_row_type(row: *const FecRow) -> *const c_char {
+    (&*row).row_type.as_ptr()
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn fec_row_line(row: *const FecRow) -> u64 {
+    (&*row).line
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn fec_row_field_count(row: *const FecRow) -> usize {
+    (&*row).fields.len()
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn fec_row_field(row: *const FecRow, idx: usize) -> *const c_char {
+    (&*row).fields.get(idx).map_or(ptr::null(), |v| v.as_ptr())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn fec_row_column(row: *const FecRow, idx: usize) -> *const c_char {
+    (&*row)
+        .columns
+        .get(idx)
+        .and_then(|v| v.as_ref())
+        .map_or(ptr::null(), |v| v.as_ptr())
+}
+
+// a field by column name, ex "contribution_amount"
+#[no_mangle]
+pub unsafe extern "C" fn fec_row_get(row: *const FecRow, column: *const c_char) -> *const c_char {
+    let Some(column) = str_arg(column) else {
+        return ptr::null();
+    };
+    let row = &*row;
+    row.columns
+        .iter()
+        .position(|v| {
+            v.as_ref()
+                .is_some_and(|v| v.to_bytes() == column.as_bytes())
+        })
+        .and_then(|idx| row.fields.get(idx))
+        .map_or(ptr::null(), |v| v.as_ptr())
+}
+
+// frees error messages
+#[no_mangle]
+pub unsafe extern "C" fn fec_string_free(value: *mut c_char) {
+    if !value.is_null() {
+        drop(CString::from_raw(value));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_filing() {
+        let filing = [
+            "HDR\u{1c}FEC\u{1c}8.4\u{1c}libfec\u{1c}0.1\u{1c}\u{1c}\u{1c}",
+            "F3XN\u{1c}C00000001\u{1c}Test PAC",
+            "SA11AI\u{1c}C00000001\u{1c}SA.1\u{1c}\u{1c}\u{1c}IND\u{1c}\u{1c}Doe\u{1c}Jane",
+        ]
+        .join("\n");
+        unsafe {
+            let mut error = ptr::null_mut();
+            let filing =
+                fec_filing_open_buffer(filing.as_ptr(), filing.len(), c"123".as_ptr(), &mut error);
+            assert!(!filing.is_null());
+            assert_eq!(CStr::from_ptr(fec_filing_id(filing)), c"123");
+            assert_eq!(
+                CStr::from_ptr(fec_filing_header(filing, c"fec_version".as_ptr())),
+                c"8.4"
+            );
+            assert_eq!(
+                CStr::from_ptr(fec_filing_cover(filing, c"committee_name".as_ptr())),
+                c"Test PAC"
+            );
+
+            let mut row = ptr::null_mut();
+            assert_eq!(
+                fec_filing_next_row(filing, &mut row, ptr::null_mut()),
+                FecStatus::Ok
+            );
+            assert_eq!(CStr::from_ptr(fec_row_type(row)), c"SA11AI");
+            assert_eq!(CStr::from_ptr(fec_row_column(row, 2)), c"transaction_id");
+            assert_eq!(
+                CStr::from_ptr(fec_row_get(row, c"contributor_last_name".as_ptr())),
+                c"Doe"
+            );
+            fec_row_free(row);
+            assert_eq!(
+                fec_filing_next_row(filing, &mut row, ptr::null_mut()),
+                FecStatus::Done
+            );
+            fec_filing_free(filing);
+
+            let filing =
+                fec_filing_open_buffer(c"nope".as_ptr().cast(), 4, ptr::null(), &mut error);
+            assert!(filing.is_null());
+            assert!(!error.is_null());
+            fec_string_free(error);
+        }
+    }
+
+    // include/fec.h is checked in for C users, copy the generated header
+    // over it after changing the API
+    #[test]
+    fn header_is_up_to_date() {
+        let generated = include_str!(concat!(env!("OUT_DIR"), "/fec.h"));
+        let checked_in = include_str!("../include/fec.h");
+        assert!(
+            generated == checked_in,
+            "include/fec.h is out of date, update it with: cp {}/fec.h fec-capi/include/fec.h",
+            env!("OUT_DIR")
+        );
+    }
+}
@@ -0,0 +1,210 @@
+use colored::Colorize;
+use fec_parser::diff::{diff_covers, FieldChange, RowDiff, TransactionDiff};
+use serde_json::Value;
+use std::{
+    error::Error,
+    io::{self, Write},
+};
+use tabled::{builder::Builder as TableBuilder, settings::Style as TableStyle};
+
+use crate::sourcer::FilingSourcer;
+
+pub(crate) enum CmdDiffFormat {
+    Human,
+    Json,
+    Csv,
+}
+
+fn change_kind(diff: &RowDiff) -> &'static str {
+    match diff {
+        RowDiff::Added(_) => "added",
+        RowDiff::Removed(_) => "removed",
+        RowDiff::Changed { .. } => "changed",
+    }
+}
+
+fn changes_json(changes: &[FieldChange]) -> Value {
+    Value::Array(
+        changes
+            .iter()
+            .map(|change| {
+                serde_json::json!({
+                    "column": change.column,
+                    "old": change.old,
+                    "new": change.new,
+                })
+            })
+            .collect(),
+    )
+}
+
+fn diff_json(diff: &RowDiff) -> Value {
+    let row = diff.row();
+    let mut value = serde_json::json!({
+        "change": change_kind(diff),
+        "row_type": row.row_type,
+        "transaction_id": diff.transaction_id(),
+    });
+    match diff {
+        RowDiff::Changed { changes, .. } => {
+            value["changes"] = changes_json(changes);
+        }
+        RowDiff::Added(row) | RowDiff::Removed(row) => {
+            value["row"] = row
+                .iter_named()
+                .map(|(column, value)| (column.to_owned(), Value::from(value)))
+                .collect::<serde_json::Map<_, _>>()
+                .into();
+        }
+    }
+    value
+}
+
+fn print_changes_table(title: &str, changes: &[FieldChange]) {
+    if changes.is_empty() {
+        return;
+    }
+    println!("{}", title.bold());
+    let mut tbl = TableBuilder::new();
+    tbl.push_record(["Column", "Old", "New"]);
+    for change in changes {
+        tbl.push_record([
+            change.column.clone(),
+            change.old.clone().unwrap_or_default(),
+            change.new.clone().unwrap_or_default(),
+        ]);
+    }
+    println!("{}", tbl.build().with(TableStyle::modern_rounded()));
+}
+
+fn print_human_diff(diff: &RowDiff) {
+    let row = diff.row();
+    let label = format!(
+        "{} {}",
+        row.row_type,
+        diff.transaction_id().unwrap_or("(no transaction ID)")
+    );
+    match diff {
+        RowDiff::Added(_) => println!("{}", format!("+ {label}").green()),
+        RowDiff::Removed(_) => println!("{}", format!("- {label}").red()),
+        RowDiff::Changed { changes, .. } => {
+            println!("{}", format!("~ {label}").yellow());
+            for change in changes {
+                println!(
+                    "    {}: {} -> {}",
+                    change.column,
+                    change.old.as_deref().unwrap_or("(none)"),
+                    change.new.as_deref().unwrap_or("(none)"),
+                );
+            }
+        }
+    }
+}
+
+pub fn cmd_diff(old: &str, new: &str, format: CmdDiffFormat) -> Result<(), Box<dyn Error>> {
+    let filing_sourcer = FilingSourcer::new();
+    let mut old = filing_sourcer.resolve(old);
+    let mut new = filing_sourcer.resolve(new);
+    let (cover, summary) = diff_covers(&old.cover, &new.cover);
+    let old_id = old.filing_id.clone();
+    let new_id = new.filing_id.clone();
+    let new_form_type = new.cover.form_type.clone();
+    let diffs = TransactionDiff::new(&mut old, &mut new);
+
+    match format {
+        CmdDiffFormat::Human => {
+            println!("{}", format!("FEC-{old_id} -> FEC-{new_id}").bold());
+            print_changes_table("Cover", &cover);
+            print_changes_table("Summary", &summary);
+            let (mut added, mut removed, mut changed) = (0, 0, 0);
+            for diff in diffs {
+                let diff = diff?;
+                match diff {
+                    RowDiff::Added(_) => added += 1,
+                    RowDiff::Removed(_) => removed += 1,
+                    RowDiff::Changed { .. } => changed += 1,
+                }
+                print_human_diff(&diff);
+            }
+            println!("{added} added, {removed} removed, {changed} changed transactions");
+        }
+        // written piece by piece, so the transactions are never all in memory
+        CmdDiffFormat::Json => {
+            let mut stdout = io::stdout().lock();
+            write!(
+                stdout,
+                "{{\"old\":{},\"new\":{},\"cover\":{},\"summary\":{},\"transactions\":[",
+                Value::from(old_id.as_str()),
+                Value::from(new_id.as_str()),
+                changes_json(&cover),
+                changes_json(&summary)
+            )?;
+            for (idx, diff) in diffs.enumerate() {
+                if idx > 0 {
+                    write!(stdout, ",")?;
+                }
+                write!(stdout, "{}", diff_json(&diff?))?;
+            }
+            writeln!(stdout, "]}}")?;
+        }
+        CmdDiffFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(io::stdout().lock());
+            writer.write_record([
+                "section",
+                "row_type",
+                "transaction_id",
+                "change",
+                "column",
+                "old",
+                "new",
+            ])?;
+            for (section, changes) in [("cover", &cover), ("summary", &summary)] {
+                for change in changes {
+                    writer.write_record([
+                        section,
+                        &new_form_type,
+                        "",
+                        "changed",
+                        &change.column,
+                        change.old.as_deref().unwrap_or(""),
+                        change.new.as_deref().unwrap_or(""),
+                    ])?;
+                }
+            }
+            for diff in diffs {
+                let diff = diff?;
+                let row_type = diff.row().row_type.clone();
+                let transaction_id = diff.transaction_id().unwrap_or("").to_owned();
+                let kind = change_kind(&diff);
+                match &diff {
+                    RowDiff::Changed { changes, .. } => {
+                        for change in changes {
+                            writer.write_record([
+                                "transactions",
+                                &row_type,
+                                &transaction_id,
+                                kind,
+                                &change.column,
+                                change.old.as_deref().unwrap_or(""),
+                                change.new.as_deref().unwrap_or(""),
+                            ])?;
+                        }
+                    }
+                    RowDiff::Added(_) | RowDiff::Removed(_) => {
+                        writer.write_record([
+                            "transactions",
+                            &row_type,
+                            &transaction_id,
+                            kind,
+                            "",
+                            "",
+                            "",
+                        ])?;
+                    }
+                }
+            }
+            writer.flush()?;
+        }
+    }
+    Ok(())
+}
@@ -1,6 +1,12 @@
 use fec_parser::{
-    mappings::{DATE_COLUMNS, FLOAT_COLUMNS},
-    try_format_fec_date, Filing,
+    amendments::AmendmentChains,
+    canonical::{CanonicalMapping, Schedule, CANONICAL_VERSION, SCHEDULES},
+    codes::{code_label, CODED_COLUMNS},
+    descriptions::column_description,
+    mappings::{column_names_for_field, column_type, ColumnType},
+    summary::FinancialSummary,
+    transactions::{TextMemo, TransactionColumns, TransactionLink, TRANSACTION_ID_COLUMNS},
+    try_format_fec_date, Filing, FilingHeader, FilingRow,
 };
 use indicatif::{HumanDuration, MultiProgress, ProgressBar, ProgressStyle};
 use rusqlite::{
@@ -9,7 +15,8 @@ use rusqlite::{
     Connection, Statement, ToSql, Transaction,
 };
 use std::{
-    collections::HashMap,
+    borrow::Cow,
+    collections::{HashMap, HashSet},
     error::Error,
     io::Read,
     time::{Duration, Instant},
@@ -24,15 +31,9 @@ pub enum CmdExportError {
     SqliteError(String, #[source] rusqlite::Error),
 }
 
-#[derive(Clone, Copy)]
-enum FieldFormat {
-    Text,
-    Float,
-    Date,
-}
-
 #[derive(Clone)]
 enum FieldValue {
+    Null,
     Text(String),
     Float(f64),
     Date(String),
@@ -41,6 +42,7 @@ enum FieldValue {
 impl ToSql for FieldValue {
     fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
         match self {
+            FieldValue::Null => Ok(ToSqlOutput::Owned(Value::Null)),
             FieldValue::Text(v) => Ok(ToSqlOutput::Owned(Value::Text(v.to_owned()))),
             FieldValue::Date(v) => Ok(ToSqlOutput::Owned(Value::Text(v.to_owned()))),
             FieldValue::Float(v) => Ok(ToSqlOutput::Owned(Value::Real(*v))),
@@ -50,15 +52,225 @@ impl ToSql for FieldValue {
 
 struct Entry<'a> {
     statement: Statement<'a>,
-    field_formats: Vec<FieldFormat>,
+    field_formats: Vec<ColumnType>,
+    transaction_columns: TransactionColumns,
+    transaction_id_column: Option<String>,
+    label_columns: Vec<(usize, &'static str)>,
+    first_rowid: Option<i64>,
+}
+
+fn insert_transaction_link(
+    statement: &mut Statement,
+    filing_id: &str,
+    link: &TransactionLink,
+) -> Result<(), rusqlite::Error> {
+    if let Some(ref parent_transaction_id) = link.parent_transaction_id {
+        statement.execute(rusqlite::params![
+            filing_id,
+            &link.row_type,
+            &link.transaction_id,
+            parent_transaction_id,
+            &link.parent_sched_name,
+        ])?;
+    }
+    Ok(())
+}
+
+// TEXT records usually come after the transaction they describe, so memos are
+// collected while streaming and applied to the exported rows afterwards. Only
+// rows inserted for the current filing (rowid >= first_rowid) are searched.
+// Tables from before memos were exported get memo_text from `ensure_columns`.
+fn apply_text_memo(
+    tx: &Transaction,
+    table: &str,
+    transaction_id_column: &str,
+    first_rowid: i64,
+    filing_id: &str,
+    memo: &TextMemo,
+) -> Result<usize, rusqlite::Error> {
+    let sql = format!(
+        "UPDATE [libfec_{table}] SET memo_text = coalesce(memo_text || char(10), '') || ? WHERE rowid >= ? AND filing_id = ? AND [{transaction_id_column}] = ?"
+    );
+    tx.prepare_cached(&sql)?.execute(rusqlite::params![
+        &memo.text,
+        first_rowid,
+        filing_id,
+        &memo.target_transaction_id,
+    ])
+}
+
+fn export_financial_summary(
+    tx: &Transaction,
+    filing_id: &str,
+    summary: &FinancialSummary,
+) -> Result<(), rusqlite::Error> {
+    let lines = summary.lines();
+    let mut sql = String::from("CREATE TABLE IF NOT EXISTS libfec_financial_summaries(\n  ");
+    sql += "filing_id text primary key references libfec_filings(filing_id),\n  ";
+    sql += "form text";
+    for (name, _) in &lines {
+        sql += &format!(",\n  {name}_period float,\n  {name}_cycle_to_date float");
+    }
+    sql += "\n)";
+    tx.execute(&sql, [])?;
+
+    let mut values = vec![
+        FieldValue::Text(filing_id.to_owned()),
+        FieldValue::Text(format!("{:?}", summary.form)),
+    ];
+    for (_, amount) in lines {
+        for value in [amount.period, amount.cycle_to_date] {
+            values.push(value.map_or(FieldValue::Null, FieldValue::Float));
+        }
+    }
+    let sql = format!(
+        "INSERT INTO libfec_financial_summaries VALUES ({})",
+        vec!["?"; values.len()].join(",")
+    );
+    tx.execute(&sql, params_from_iter(values))?;
+    Ok(())
+}
+
+// Coded columns (ex entity_type) that get an extra decoded `_label` column,
+// as (index into the record, column name)
+fn label_columns(column_names: &'static [String]) -> Vec<(usize, &'static str)> {
+    column_names
+        .iter()
+        .enumerate()
+        .filter(|(_, name)| CODED_COLUMNS.contains(&name.as_str()))
+        .map(|(idx, name)| (idx, name.as_str()))
+        .collect()
+}
+
+// The columns of an itemization table in insert order, with their types:
+// filing_id, the optional back reference and memo columns, the mapped columns
+// and the decoded labels
+fn insert_columns(
+    column_names: &[String],
+    parent_transaction_id: bool,
+    memo_text: bool,
+    label_columns: &[&str],
+) -> Vec<(String, &'static str)> {
+    let mut columns = vec![("filing_id".to_owned(), "text")];
+    if parent_transaction_id {
+        columns.push(("parent_transaction_id".to_owned(), "text"));
+    }
+    if memo_text {
+        columns.push(("memo_text".to_owned(), "text"));
+    }
+    columns.extend(
+        column_names
+            .iter()
+            .map(|name| (name.to_owned(), column_type(name).as_str())),
+    );
+    columns.extend(
+        label_columns
+            .iter()
+            .map(|name| (format!("{name}_label"), "text")),
+    );
+    columns
+}
+
+// Tables created by older versions of libfec, by an export without --labels
+// or for another version of the form type can be missing some of the columns,
+// which get added at the end
+fn ensure_columns(
+    tx: &Transaction,
+    table: &str,
+    columns: &[(String, &str)],
+) -> Result<(), rusqlite::Error> {
+    let existing: HashSet<String> = tx
+        .prepare("SELECT lower(name) FROM pragma_table_info(?1)")?
+        .query_map([format!("libfec_{table}")], |row| row.get(0))?
+        .collect::<Result<_, _>>()?;
+    for (name, column_type) in columns {
+        if !existing.contains(&name.to_lowercase()) {
+            tx.execute(
+                &format!("ALTER TABLE [libfec_{table}] ADD COLUMN [{name}] {column_type}"),
+                [],
+            )?;
+        }
+    }
+    Ok(())
+}
+
+// Inserts list their columns, so they don't depend on the order of the
+// columns in an existing table
+fn insert_sql(table: &str, columns: &[(String, &str)]) -> String {
+    format!(
+        "INSERT INTO [libfec_{table}]({}) VALUES ({})",
+        columns
+            .iter()
+            .map(|(name, _)| format!("[{name}]"))
+            .collect::<Vec<_>>()
+            .join(","),
+        vec!["?"; columns.len()].join(",")
+    )
+}
+
+// The per form type table of itemizations, with optional columns for the
+// back reference, the TEXT memos attached to each transaction and decoded
+// labels of coded columns
+pub(crate) fn create_table_sql(
+    table: &str,
+    column_names: &[String],
+    parent_transaction_id: bool,
+    memo_text: bool,
+    label_columns: &[&str],
+) -> String {
+    let mut columns_defs: Vec<(String, Option<String>)> = column_names
+        .iter()
+        .map(|name| {
+            (
+                format!("{} {}", name, column_type(name).as_str()),
+                column_description(name).map(|description| description.to_owned()),
+            )
+        })
+        .collect();
+    for name in label_columns {
+        columns_defs.push((
+            format!("{name}_label text"),
+            Some(format!("Decoded label of {name}")),
+        ));
+    }
+
+    let mut sql = String::from("CREATE TABLE IF NOT EXISTS [libfec_");
+    sql += table;
+    sql += "](\n  ";
+    sql += "filing_id text references libfec_filings(filing_id),\n  ";
+    if parent_transaction_id {
+        sql += "parent_transaction_id text,\n  ";
+    }
+    if memo_text {
+        sql += "memo_text text,\n  ";
+    }
+    // descriptions are kept as SQL comments, so they show up in the schema
+    let last = columns_defs.len().saturating_sub(1);
+    let columns_defs: Vec<String> = columns_defs
+        .into_iter()
+        .enumerate()
+        .map(|(idx, (definition, description))| {
+            let separator = if idx < last { "," } else { "" };
+            match description {
+                Some(description) => format!("{definition}{separator} -- {description}"),
+                None => format!("{definition}{separator}"),
+            }
+        })
+        .collect();
+    sql += columns_defs.join("\n  ").as_str();
+    sql += "\n)";
+    sql
 }
 
 fn export_itemizations_by_form_type<R: Read>(
     mut filing: Filing<R>,
     tx: &mut Transaction,
     pb: &ProgressBar,
+    labels: bool,
 ) -> Result<(), rusqlite::Error> {
     let mut stmt_map: HashMap<String, Entry> = HashMap::new();
+    let mut link_statement = tx.prepare(INSERT_TRANSACTION_LINK_SQL)?;
+    let mut memos = vec![];
     while let Some(r) = filing.next_row() {
         let r = r.unwrap();
         pb.set_position(r.record.position().unwrap().byte());
@@ -66,55 +278,43 @@ fn export_itemizations_by_form_type<R: Read>(
         let entry = match stmt_map.get_mut(&r.row_type) {
             Some(stmt) => stmt,
             None => {
+                // lookup with the raw form type, 'SC/10' would match the SC1
+                // mappings once the '/' is stripped
                 let column_names = fec_parser::mappings::column_names_for_field(
-                    &r.row_type,
+                    r.record.get(0).unwrap_or(&r.row_type),
                     &filing.header.fec_version,
                 )
                 .unwrap();
 
-                let column_types: Vec<FieldFormat> = column_names
-                    .iter()
-                    .map(|c| {
-                        if DATE_COLUMNS.contains(c) {
-                            FieldFormat::Date
-                        } else if FLOAT_COLUMNS.contains(c) {
-                            FieldFormat::Float
-                        } else {
-                            FieldFormat::Text
-                        }
-                    })
-                    .collect();
-
-                let columns_defs: Vec<String> = column_names
-                    .iter()
-                    .zip(column_types.clone())
-                    .map(|(name, format)| {
-                        format!(
-                            "{} {}",
-                            name,
-                            match format {
-                                FieldFormat::Date => "date",
-                                FieldFormat::Text => "text",
-                                FieldFormat::Float => "float",
-                            }
-                        )
-                    })
-                    .collect();
-
-                let mut sql = String::from("CREATE TABLE IF NOT EXISTS [libfec_");
-                sql += r.row_type.as_ref();
-                sql += "](\n  ";
-                sql += "filing_id text references libfec_filings(filing_id),\n  ";
-                sql += columns_defs.join(",\n  ").as_str();
-                sql += "\n)";
-
-                tx.execute(&sql, [])?;
-
-                let sql = format!(
-                    "INSERT INTO libfec_{} VALUES ({})",
-                    r.row_type,
-                    vec!["?"; column_names.len() + 1].join(",")
+                let column_types: Vec<ColumnType> =
+                    column_names.iter().map(|c| column_type(c)).collect();
+
+                let transaction_columns = TransactionColumns::new(column_names);
+                let label_columns = if labels {
+                    label_columns(column_names)
+                } else {
+                    vec![]
+                };
+                let label_names: Vec<&str> = label_columns.iter().map(|(_, name)| *name).collect();
+
+                tx.execute(
+                    &create_table_sql(
+                        &r.row_type,
+                        column_names,
+                        transaction_columns.has_back_reference(),
+                        transaction_columns.has_transaction_id(),
+                        &label_names,
+                    ),
+                    [],
+                )?;
+                let columns = insert_columns(
+                    column_names,
+                    transaction_columns.has_back_reference(),
+                    transaction_columns.has_transaction_id(),
+                    &label_names,
                 );
+                ensure_columns(tx, &r.row_type, &columns)?;
+                let sql = insert_sql(&r.row_type, &columns);
 
                 let statement = tx.prepare(&sql)?;
                 stmt_map.insert(
@@ -122,6 +322,14 @@ fn export_itemizations_by_form_type<R: Read>(
                     Entry {
                         statement,
                         field_formats: column_types,
+                        transaction_columns,
+                        // TEXT tables don't have a memo_text column to update
+                        transaction_id_column: transaction_columns
+                            .transaction_id_column(column_names)
+                            .filter(|_| transaction_columns.has_transaction_id())
+                            .map(|column| column.to_owned()),
+                        label_columns,
+                        first_rowid: None,
                     },
                 );
 
@@ -136,12 +344,12 @@ fn export_itemizations_by_form_type<R: Read>(
             .iter()
             .enumerate()
             .map(|(idx, field)| match entry.field_formats.get(idx) {
-                Some(FieldFormat::Text) => FieldValue::Text(field.to_owned()),
-                Some(FieldFormat::Date) => match field.len() {
+                Some(ColumnType::Text) => FieldValue::Text(field.to_owned()),
+                Some(ColumnType::Date) => match field.len() {
                     8 => FieldValue::Date(try_format_fec_date(field)),
                     _ => FieldValue::Text(field.to_owned()),
                 },
-                Some(FieldFormat::Float) => match field.parse::<f64>() {
+                Some(ColumnType::Float) => match field.parse::<f64>() {
                     Ok(value) => FieldValue::Float(value),
                     Err(_) => FieldValue::Text(field.to_owned()),
                 },
@@ -150,25 +358,96 @@ fn export_itemizations_by_form_type<R: Read>(
             .collect();
 
         vals.insert(0, FieldValue::Text(filing.filing_id.clone()));
-        if vals.len() == entry.statement.parameter_count() + 1 {
+        if entry.transaction_columns.has_back_reference() {
+            let link = entry.transaction_columns.link(&r);
+            let parent_transaction_id = link
+                .as_ref()
+                .and_then(|link| link.parent_transaction_id.clone());
+            vals.insert(
+                1,
+                parent_transaction_id.map_or(FieldValue::Null, FieldValue::Text),
+            );
+            if let Some(ref link) = link {
+                insert_transaction_link(&mut link_statement, &filing.filing_id, link)?;
+            }
+        }
+        if entry.transaction_columns.has_transaction_id() {
+            let idx = if entry.transaction_columns.has_back_reference() {
+                2
+            } else {
+                1
+            };
+            vals.insert(idx, FieldValue::Null);
+        }
+        if let Some(memo) = entry.transaction_columns.memo(&r) {
+            memos.push(memo);
+        }
+        // label columns come after every mapped column
+        let parameter_count = entry.statement.parameter_count() - entry.label_columns.len();
+        if vals.len() == parameter_count + 1 {
             vals.pop();
         }
 
-        while vals.len() < entry.statement.parameter_count() {
+        while vals.len() < parameter_count {
             vals.push(FieldValue::Text("".to_owned()));
         }
-        if vals.len() > entry.statement.parameter_count() {
+        if vals.len() > parameter_count {
             pb.println(format!(
                 "Warning too long at {}:{}, {} vs {}!",
                 filing.filing_id,
                 r.record.position().map(|p| p.line()).unwrap_or(0),
                 vals.len(),
-                entry.statement.parameter_count()
+                parameter_count
             ));
-            vals.truncate(entry.statement.parameter_count());
+            vals.truncate(parameter_count);
         }
+        vals.extend(entry.label_columns.iter().map(|(idx, name)| {
+            r.record
+                .get(*idx)
+                .and_then(|value| code_label(name, value))
+                .map_or(FieldValue::Null, FieldValue::Text)
+        }));
         entry.statement.execute(params_from_iter(vals))?;
         entry.statement.clear_bindings();
+        if entry.first_rowid.is_none() {
+            entry.first_rowid = Some(tx.last_insert_rowid());
+        }
+    }
+
+    for memo in &memos {
+        if memo.target_transaction_id.is_none() {
+            continue;
+        }
+        // try the schedule the memo references first, then every other
+        // table that has transaction IDs
+        let target = memo
+            .target_sched_name
+            .as_ref()
+            .map(|sched| sched.replace('/', ""));
+        let mut candidates: Vec<_> = stmt_map
+            .iter()
+            .filter_map(|(row_type, entry)| {
+                Some((
+                    row_type,
+                    entry.transaction_id_column.as_ref()?,
+                    entry.first_rowid?,
+                ))
+            })
+            .collect();
+        candidates.sort_by_key(|(row_type, _, _)| Some(row_type.as_str()) != target.as_deref());
+        for (row_type, transaction_id_column, first_rowid) in candidates {
+            let updated = apply_text_memo(
+                tx,
+                row_type,
+                transaction_id_column,
+                first_rowid,
+                &filing.filing_id,
+                memo,
+            )?;
+            if updated > 0 {
+                break;
+            }
+        }
     }
     Ok(())
 }
@@ -176,100 +455,134 @@ fn export_schedule_a<R: Read>(
     mut filing: Filing<R>,
     tx: &mut Transaction,
     pb: &ProgressBar,
+    labels: bool,
 ) -> Result<(), rusqlite::Error> {
-    let column_names = fec_parser::mappings::column_names_for_field("SA", "8.4").unwrap();
-
-    let column_types: Vec<FieldFormat> = column_names
-        .iter()
-        .map(|c| {
-            if DATE_COLUMNS.contains(c) {
-                FieldFormat::Date
-            } else if FLOAT_COLUMNS.contains(c) {
-                FieldFormat::Float
-            } else {
-                FieldFormat::Text
-            }
-        })
-        .collect();
-
-    let columns_defs: Vec<String> = column_names
-        .iter()
-        .zip(column_types.clone())
-        .map(|(name, format)| {
-            format!(
-                "{} {}",
-                name,
-                match format {
-                    FieldFormat::Date => "date",
-                    FieldFormat::Text => "text",
-                    FieldFormat::Float => "float",
-                }
-            )
-        })
-        .collect();
+    // rows from every version are mapped onto the canonical 8.4 layout
+    let column_names = Schedule::A.columns();
 
-    let mut sql = String::from("CREATE TABLE IF NOT EXISTS [libfec_");
-    sql += "schedule_a";
-    sql += "](\n  ";
-    sql += "filing_id text references libfec_filings(filing_id),\n  ";
-    sql += columns_defs.join(",\n  ").as_str();
-    sql += "\n)";
+    let column_types: Vec<ColumnType> = column_names.iter().map(|c| column_type(c)).collect();
 
-    tx.execute(&sql, [])?;
+    let transaction_columns = TransactionColumns::new(column_names);
+    let label_columns = if labels {
+        label_columns(column_names)
+    } else {
+        vec![]
+    };
 
-    let sql = format!(
-        "INSERT INTO libfec_{} VALUES ({})",
-        "schedule_a",
-        vec!["?"; column_names.len() + 1].join(",")
-    );
+    let label_names: Vec<&str> = label_columns.iter().map(|(_, name)| *name).collect();
+    tx.execute(
+        &create_table_sql(
+            Schedule::A.table_name(),
+            column_names,
+            true,
+            true,
+            &label_names,
+        ),
+        [],
+    )?;
+    let columns = insert_columns(column_names, true, true, &label_names);
+    ensure_columns(tx, Schedule::A.table_name(), &columns)?;
+    let sql = insert_sql(Schedule::A.table_name(), &columns);
 
     let mut statement = tx.prepare(&sql)?;
+    let mut link_statement = tx.prepare(INSERT_TRANSACTION_LINK_SQL)?;
+    let text_columns = TransactionColumns::for_field("TEXT", &filing.header.fec_version);
+    let name_delim = filing.header.name_delim.clone().unwrap_or_default();
+    // the mapping and transaction columns of each raw form type in the filing
+    let mut mappings: HashMap<String, (CanonicalMapping, TransactionColumns)> = HashMap::new();
+    let mut memos = vec![];
+    let mut first_rowid = None;
 
     while let Some(r) = filing.next_row() {
         let r = r.unwrap();
         pb.set_position(r.record.position().unwrap().byte());
-        if !r.row_type.starts_with("SA") {
+        if r.row_type == "TEXT" {
+            memos.extend(text_columns.memo(&r));
             continue;
         }
-
-        let mut vals: Vec<FieldValue> = r
-            .record
-            .iter()
-            .enumerate()
-            .map(|(idx, field)| match column_types.get(idx) {
-                Some(FieldFormat::Text) => FieldValue::Text(field.to_owned()),
-                Some(FieldFormat::Date) => match field.len() {
-                    8 => FieldValue::Date(try_format_fec_date(field)),
-                    _ => FieldValue::Text(field.to_owned()),
-                },
-                Some(FieldFormat::Float) => match field.parse::<f64>() {
-                    Ok(value) => FieldValue::Float(value),
-                    Err(_) => FieldValue::Text(field.to_owned()),
-                },
-                None => FieldValue::Text(field.to_owned()),
-            })
-            .collect();
-
-        vals.insert(0, FieldValue::Text(filing.filing_id.clone()));
-        if vals.len() == statement.parameter_count() + 1 {
-            vals.pop();
+        let form_type = r.record.get(0).unwrap_or(&r.row_type);
+        if Schedule::from_row_type(form_type) != Some(Schedule::A) {
+            continue;
         }
-
-        while vals.len() < statement.parameter_count() {
-            vals.push(FieldValue::Text("".to_owned()));
+        if !mappings.contains_key(form_type) {
+            mappings.insert(
+                form_type.to_owned(),
+                (
+                    CanonicalMapping::new(Schedule::A, r.columns()).with_name_delim(&name_delim),
+                    TransactionColumns::new(r.columns()),
+                ),
+            );
         }
-        if vals.len() > statement.parameter_count() {
+        let (mapping, row_transaction_columns) = &mappings[form_type];
+
+        if r.record.len() > r.columns().len() + 1 {
             pb.println(format!(
                 "Warning too long at {}:{}, {} vs {}!",
                 filing.filing_id,
                 r.record.position().map(|p| p.line()).unwrap_or(0),
-                vals.len(),
-                statement.parameter_count()
+                r.record.len(),
+                r.columns().len()
             ));
-            vals.truncate(statement.parameter_count());
         }
+
+        let values = mapping.map(&r.record);
+        let mut vals: Vec<FieldValue> = vec![
+            FieldValue::Text(filing.filing_id.clone()),
+            FieldValue::Null,
+            FieldValue::Null,
+        ];
+        vals.extend(
+            values
+                .iter()
+                .zip(column_types.iter())
+                .map(|(field, column_type)| match column_type {
+                    ColumnType::Text => FieldValue::Text(field.to_string()),
+                    ColumnType::Date => match field.len() {
+                        8 => FieldValue::Date(try_format_fec_date(field)),
+                        _ => FieldValue::Text(field.to_string()),
+                    },
+                    ColumnType::Float => match field.parse::<f64>() {
+                        Ok(value) => FieldValue::Float(value),
+                        Err(_) => FieldValue::Text(field.to_string()),
+                    },
+                }),
+        );
+
+        let link = row_transaction_columns.link(&r);
+        if let Some(ref link) = link {
+            vals[1] = link
+                .parent_transaction_id
+                .clone()
+                .map_or(FieldValue::Null, FieldValue::Text);
+            insert_transaction_link(&mut link_statement, &filing.filing_id, link)?;
+        }
+        vals.extend(label_columns.iter().map(|(idx, name)| {
+            code_label(name, &values[*idx]).map_or(FieldValue::Null, FieldValue::Text)
+        }));
         statement.execute(params_from_iter(vals))?;
         statement.clear_bindings();
+        if first_rowid.is_none() {
+            first_rowid = Some(tx.last_insert_rowid());
+        }
+    }
+
+    if let (Some(first_rowid), Some(transaction_id_column)) = (
+        first_rowid,
+        transaction_columns.transaction_id_column(column_names),
+    ) {
+        for memo in memos
+            .iter()
+            .filter(|memo| memo.target_transaction_id.is_some())
+        {
+            apply_text_memo(
+                tx,
+                Schedule::A.table_name(),
+                transaction_id_column,
+                first_rowid,
+                &filing.filing_id,
+                memo,
+            )?;
+        }
     }
     Ok(())
 }
@@ -299,24 +612,375 @@ const CREATE_FILINGS_SQL: &str = r#"
     filer_name TEXT NOT NULL,
     report_code TEXT NOT NULL,
     coverage_from_date TEXT NOT NULL,
-    coverage_through_date TEXT NOT NULL
+    coverage_through_date TEXT NOT NULL,
+    superseded_by TEXT
   )
 "#;
 
 const INSERT_FILING_SQL: &str = r#"
-  INSERT INTO libfec_filings VALUES(?,?,?,?,?,?,?,?,?,?,?,?,?)
+  INSERT INTO libfec_filings VALUES(?,?,?,?,?,?,?,?,?,?,?,?,?,NULL)
+"#;
+
+const CREATE_TRANSACTION_LINKS_SQL: &str = r#"
+  CREATE TABLE IF NOT EXISTS libfec_transaction_links(
+    filing_id TEXT NOT NULL REFERENCES libfec_filings(filing_id),
+    row_type TEXT NOT NULL,
+    transaction_id TEXT NOT NULL,
+    parent_transaction_id TEXT NOT NULL,
+    parent_sched_name TEXT
+  )
+"#;
+
+const INSERT_TRANSACTION_LINK_SQL: &str = r#"
+  INSERT INTO libfec_transaction_links VALUES(?,?,?,?,?)
 "#;
 
 pub enum CmdExportTarget {
     ByFormType,
     ScheduleA,
+    // every consolidated schedule, not supported for SQLite yet
+    Schedules,
+}
+
+impl CmdExportTarget {
+    fn schedules(&self) -> &'static [Schedule] {
+        match self {
+            Self::ByFormType => &[],
+            Self::ScheduleA => &[Schedule::A],
+            Self::Schedules => SCHEDULES,
+        }
+    }
+}
+
+pub(crate) struct TargetRow<'r> {
+    pub table: &'r str,
+    pub columns: &'static [String],
+    pub values: Vec<Cow<'r, str>>,
+}
+
+// The table, columns and values of each row of a filing for a target, for
+// the exports other than SQLite. Rows outside of the target are skipped.
+pub(crate) struct TargetRows<'a> {
+    target: &'a CmdExportTarget,
+    // the canonical mapping of each raw form type in the filing
+    mappings: HashMap<String, CanonicalMapping>,
+    name_delim: String,
+    fec_version: String,
+    // whether each table's rows have the columns of the latest version
+    latest: HashMap<String, bool>,
+}
+
+impl<'a> TargetRows<'a> {
+    pub(crate) fn new(target: &'a CmdExportTarget, header: &FilingHeader) -> Self {
+        Self {
+            target,
+            mappings: HashMap::new(),
+            name_delim: header.name_delim.clone().unwrap_or_default(),
+            fec_version: header.fec_version.clone(),
+            latest: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn map<'r>(&mut self, r: &'r FilingRow) -> Option<TargetRow<'r>> {
+        if let CmdExportTarget::ByFormType = self.target {
+            return Some(TargetRow {
+                table: &r.row_type,
+                columns: r.columns(),
+                values: r.record.iter().map(Cow::Borrowed).collect(),
+            });
+        }
+        let form_type = r.record.get(0).unwrap_or(&r.row_type);
+        let schedule = Schedule::from_row_type(form_type)
+            .filter(|schedule| self.target.schedules().contains(schedule))?;
+        let mapping = self
+            .mappings
+            .entry(form_type.to_owned())
+            .or_insert_with(|| {
+                CanonicalMapping::new(schedule, r.columns()).with_name_delim(&self.name_delim)
+            });
+        Some(TargetRow {
+            table: schedule.table_name(),
+            columns: schedule.columns(),
+            values: mapping.map(&r.record),
+        })
+    }
+
+    // The table or file of a row for the exports that split form types by
+    // version. Rows with other columns than the form type's latest version,
+    // ex SA11AI from a 6.4 filing, get the version as a suffix, ex
+    // "SA11AI_v6_4", so where rows go doesn't depend on the order of the
+    // filings. Schedule targets always have the latest columns.
+    pub(crate) fn versioned_table<'r>(&mut self, row: &TargetRow<'r>) -> Cow<'r, str> {
+        let latest = match self.latest.get(row.table) {
+            Some(latest) => *latest,
+            None => {
+                let latest = row
+                    .values
+                    .first()
+                    .and_then(|form_type| column_names_for_field(form_type, CANONICAL_VERSION))
+                    .is_some_and(|columns| columns[..] == row.columns[..]);
+                self.latest.insert(row.table.to_owned(), latest);
+                latest
+            }
+        };
+        if latest {
+            return Cow::Borrowed(row.table);
+        }
+        let version: String = self
+            .fec_version
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        Cow::Owned(format!("{}_v{version}", row.table))
+    }
+}
+
+// for the Postgres and DuckDB exports, which create tables with any column names
+pub(crate) fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+// databases from older versions of libfec don't have the superseded_by column
+fn ensure_superseded_by_column(tx: &Transaction) -> Result<(), rusqlite::Error> {
+    let exists: bool = tx.query_row(
+        "SELECT count(*) > 0 FROM pragma_table_info('libfec_filings') WHERE name = 'superseded_by'",
+        [],
+        |row| row.get(0),
+    )?;
+    if !exists {
+        tx.execute(
+            "ALTER TABLE libfec_filings ADD COLUMN superseded_by TEXT",
+            [],
+        )?;
+    }
+    Ok(())
+}
+
+fn load_amendment_chains(tx: &Transaction) -> Result<AmendmentChains, rusqlite::Error> {
+    let mut chains = AmendmentChains::new();
+    let mut stmt = tx.prepare("SELECT filing_id, report_id, report_number FROM libfec_filings")?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let filing_id: String = row.get(0)?;
+        let report_id: Option<String> = row.get(1)?;
+        let report_number: Option<String> = row.get(2)?;
+        chains.insert(&filing_id, report_id.as_deref(), report_number.as_deref());
+    }
+    Ok(chains)
+}
+
+// Points every filing in libfec_filings at the latest amendment of its
+// report, returning the superseded filings
+fn mark_superseded(tx: &Transaction) -> Result<Vec<String>, rusqlite::Error> {
+    let chains = load_amendment_chains(tx)?;
+    let mut superseded = vec![];
+    let mut stmt = tx.prepare("UPDATE libfec_filings SET superseded_by = ? WHERE filing_id = ?")?;
+    for chain in chains.chains() {
+        let latest = chain.last().copied();
+        for filing_id in chain {
+            let superseded_by = latest.filter(|latest| *latest != filing_id);
+            stmt.execute(rusqlite::params![superseded_by, filing_id])?;
+            if superseded_by.is_some() {
+                superseded.push(filing_id.to_owned());
+            }
+        }
+    }
+    Ok(superseded)
+}
+
+// removes the rows of superseded filings from every table but libfec_filings
+fn delete_superseded_rows(tx: &Transaction, superseded: &[String]) -> Result<(), rusqlite::Error> {
+    let tables: Vec<String> = tx
+        .prepare(
+            "SELECT m.name FROM sqlite_master m
+              WHERE m.type = 'table'
+                AND m.name LIKE 'libfec_%'
+                AND m.name != 'libfec_filings'
+                AND EXISTS (
+                  SELECT 1 FROM pragma_table_info(m.name) p WHERE p.name = 'filing_id'
+                )",
+        )?
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<_, _>>()?;
+    for table in tables {
+        let mut stmt = tx.prepare(&format!("DELETE FROM [{table}] WHERE filing_id = ?"))?;
+        for filing_id in superseded {
+            stmt.execute([filing_id])?;
+        }
+    }
+    Ok(())
+}
+
+// The per form type tables, with their transaction ID column and whether
+// they have the amended_cd column of pre-6.0 filings
+fn itemization_tables(
+    tx: &Transaction,
+) -> Result<Vec<(String, Option<String>, bool)>, rusqlite::Error> {
+    let transaction_id_columns = TRANSACTION_ID_COLUMNS
+        .iter()
+        .map(|column| format!("'{column}'"))
+        .collect::<Vec<_>>()
+        .join(",");
+    tx.prepare(&format!(
+        "SELECT m.name,
+           (SELECT p.name FROM pragma_table_info(m.name) p
+             WHERE p.name IN ({transaction_id_columns}) LIMIT 1),
+           EXISTS (SELECT 1 FROM pragma_table_info(m.name) p WHERE p.name = 'amended_cd')
+          FROM sqlite_master m
+         WHERE m.type = 'table'
+           AND m.name LIKE 'libfec_%'
+           AND m.name NOT IN ('libfec_filings', 'libfec_transaction_links',
+                              'libfec_financial_summaries', 'libfec_{}')
+           AND EXISTS (
+             SELECT 1 FROM pragma_table_info(m.name) p WHERE p.name = 'filing_id'
+           )",
+        Schedule::A.table_name()
+    ))?
+    .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+    .collect()
+}
+
+// Applies the amendments of every report with a filing in `filing_ids`,
+// oldest first, to the rows already exported, and moves the effective rows
+// under the latest filing. Follows the rules of `TransactionMerger`, in SQL
+// so no filing has to be kept in memory, and every row stays in the table
+// of its own version. Returns the number of merged reports.
+fn merge_amendment_chains(
+    tx: &Transaction,
+    chains: &AmendmentChains,
+    filing_ids: &[String],
+) -> Result<usize, rusqlite::Error> {
+    let tables = itemization_tables(tx)?;
+    tx.execute(
+        "CREATE TEMP TABLE IF NOT EXISTS libfec_merge_ids(transaction_id TEXT PRIMARY KEY)",
+        [],
+    )?;
+    let filing_ids: HashSet<&str> = filing_ids.iter().map(|id| id.as_str()).collect();
+    let mut merged = 0;
+    for chain in chains.chains() {
+        if !chain.iter().any(|id| filing_ids.contains(id)) {
+            continue;
+        }
+        let in_list = |ids: &[&str]| vec!["?"; ids.len()].join(",");
+        for (idx, filing_id) in chain.iter().enumerate().skip(1) {
+            let earlier = &chain[..idx];
+            let mut incremental = false;
+            for (table, _, _) in tables.iter().filter(|(_, _, amended_cd)| *amended_cd) {
+                incremental |= tx.query_row(
+                    &format!(
+                        "SELECT EXISTS (SELECT 1 FROM [{table}] WHERE filing_id = ? AND trim(amended_cd) != '')"
+                    ),
+                    [filing_id],
+                    |row| row.get::<_, bool>(0),
+                )?;
+            }
+
+            // complete copies of the report replace every earlier row
+            if !incremental {
+                for table in tables
+                    .iter()
+                    .map(|(table, _, _)| table.as_str())
+                    .chain(["libfec_transaction_links"])
+                {
+                    tx.execute(
+                        &format!(
+                            "DELETE FROM [{table}] WHERE filing_id IN ({})",
+                            in_list(earlier)
+                        ),
+                        params_from_iter(earlier),
+                    )?;
+                }
+                continue;
+            }
+
+            // otherwise its transactions replace the earlier rows with the
+            // same IDs, and the ones marked D are deleted with them
+            tx.execute("DELETE FROM libfec_merge_ids", [])?;
+            for (table, transaction_id, _) in &tables {
+                let Some(transaction_id) = transaction_id else {
+                    continue;
+                };
+                tx.execute(
+                    &format!(
+                        "INSERT OR IGNORE INTO libfec_merge_ids
+                         SELECT trim([{transaction_id}]) FROM [{table}]
+                          WHERE filing_id = ? AND trim([{transaction_id}]) != ''"
+                    ),
+                    [filing_id],
+                )?;
+            }
+            for (table, transaction_id) in tables
+                .iter()
+                .filter_map(|(table, transaction_id, _)| {
+                    Some((table.as_str(), transaction_id.as_deref()?))
+                })
+                .chain([("libfec_transaction_links", "transaction_id")])
+            {
+                tx.execute(
+                    &format!(
+                        "DELETE FROM [{table}] WHERE filing_id IN ({})
+                           AND trim([{transaction_id}]) IN (SELECT transaction_id FROM libfec_merge_ids)",
+                        in_list(earlier)
+                    ),
+                    params_from_iter(earlier),
+                )?;
+            }
+            for (table, transaction_id, _) in tables.iter().filter(|(_, _, amended_cd)| *amended_cd)
+            {
+                let deleted = "upper(trim(amended_cd)) = 'D'";
+                if let Some(transaction_id) = transaction_id {
+                    let row_type = table.trim_start_matches("libfec_");
+                    tx.execute(
+                        &format!(
+                            "DELETE FROM libfec_transaction_links
+                              WHERE filing_id = ?1 AND row_type = ?2 AND transaction_id IN (
+                                SELECT trim([{transaction_id}]) FROM [{table}]
+                                 WHERE filing_id = ?1 AND {deleted}
+                              )"
+                        ),
+                        [filing_id, &row_type],
+                    )?;
+                }
+                tx.execute(
+                    &format!("DELETE FROM [{table}] WHERE filing_id = ? AND {deleted}"),
+                    [filing_id],
+                )?;
+            }
+        }
+
+        let (latest, earlier) = chain.split_last().expect("chains have 2+ filings");
+        for table in tables
+            .iter()
+            .map(|(table, _, _)| table.as_str())
+            .chain(["libfec_transaction_links"])
+        {
+            tx.execute(
+                &format!(
+                    "UPDATE [{table}] SET filing_id = ? WHERE filing_id IN ({})",
+                    in_list(earlier)
+                ),
+                params_from_iter(std::iter::once(latest).chain(earlier)),
+            )?;
+        }
+        merged += 1;
+    }
+    Ok(merged)
 }
 
 pub fn cmd_export(
     filings: Vec<String>,
     db: &str,
     target: CmdExportTarget,
+    labels: bool,
+    latest_only: bool,
+    merge_amendments: bool,
 ) -> Result<(), Box<dyn Error>> {
+    if merge_amendments && latest_only {
+        return Err("--merge-amendments can't be combined with --latest-only".into());
+    }
+    if merge_amendments && !matches!(target, CmdExportTarget::ByFormType) {
+        return Err("--merge-amendments is only supported with --target form-type".into());
+    }
     let filing_sourcer = FilingSourcer::new();
     let t0 = Instant::now();
     let mut db = Connection::open(db).map_err(|e| {
@@ -325,6 +989,13 @@ pub fn cmd_export(
 
     let mut tx = db.transaction().unwrap();
     tx.execute(CREATE_FILINGS_SQL, []).unwrap();
+    ensure_superseded_by_column(&tx)?;
+    tx.execute(CREATE_TRANSACTION_LINKS_SQL, []).unwrap();
+    // amendments seen so far, to skip the itemizations of filings that are
+    // already known to be superseded
+    let mut chains = load_amendment_chains(&tx)?;
+    // filings of this run, whose reports get merged with --merge-amendments
+    let mut exported = vec![];
     let mb = MultiProgress::new();
     let pb_files = if filings.len() > 1 {
         let pb_files = mb.add(ProgressBar::new(filings.len() as u64));
@@ -376,14 +1047,42 @@ pub fn cmd_export(
             ],
         )
         .unwrap();
+        chains.insert(
+            &filing.filing_id,
+            filing.header.report_id.as_deref(),
+            filing.header.report_number.as_deref(),
+        );
+        if latest_only && chains.superseded_by(&filing_id).is_some() {
+            if let Some(pb_files) = &pb_files {
+                pb_files.inc(1);
+            }
+            continue;
+        }
+        if let Some(summary) = filing.cover.financial_summary() {
+            export_financial_summary(&tx, &filing.filing_id, &summary).map_err(|e| {
+                CmdExportError::SqliteError(
+                    format!("Error inserting financial summary for {filing_id}"),
+                    e,
+                )
+            })?;
+        }
+        exported.push(filing_id.clone());
         match target {
             CmdExportTarget::ByFormType => {
-                export_itemizations_by_form_type(filing, &mut tx, &pb_file).map_err(|e| {
-                    CmdExportError::SqliteError(format!("Error inserting filing {filing_id}"), e)
-                })?;
+                export_itemizations_by_form_type(filing, &mut tx, &pb_file, labels).map_err(
+                    |e| {
+                        CmdExportError::SqliteError(
+                            format!("Error inserting filing {filing_id}"),
+                            e,
+                        )
+                    },
+                )?;
             }
             CmdExportTarget::ScheduleA => {
-                export_schedule_a(filing, &mut tx, &pb_file).unwrap();
+                export_schedule_a(filing, &mut tx, &pb_file, labels).unwrap();
+            }
+            CmdExportTarget::Schedules => {
+                return Err("--target schedules is not supported for SQLite exports".into());
             }
         }
 
@@ -391,10 +1090,29 @@ pub fn cmd_export(
             pb_files.inc(1);
         }
     }
+    let merged = if merge_amendments {
+        merge_amendment_chains(&tx, &chains, &exported)
+            .map_err(|e| CmdExportError::SqliteError("Error merging amendments".to_owned(), e))?
+    } else {
+        0
+    };
+    let superseded = mark_superseded(&tx)?;
+    if latest_only {
+        delete_superseded_rows(&tx, &superseded)?;
+    }
     tx.commit().unwrap();
     if let Some(pb_files) = &pb_files {
         pb_files.finish_and_clear();
     }
+    if merged > 0 {
+        println!("Merged the amendments of {merged} reports");
+    }
+    if latest_only && !superseded.is_empty() {
+        println!(
+            "Kept only the latest amendments, left out {} superseded filings",
+            superseded.len()
+        );
+    }
 
     println!(
         "Finished {} files in {}",
@@ -404,3 +1122,304 @@ pub fn cmd_export(
     println!("{:?}", db.path());
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::filing;
+    use rusqlite::Connection;
+    use std::io::Cursor;
+
+    fn insert_filing(tx: &Transaction, filing: &Filing<Cursor<Vec<u8>>>) {
+        let header = &filing.header;
+        tx.execute(
+            INSERT_FILING_SQL,
+            rusqlite::params![
+                &filing.filing_id,
+                &header.fec_version,
+                &header.soft_name,
+                &header.soft_ver,
+                &header.report_id,
+                &header.report_number,
+                &header.comment,
+                &filing.cover.form_type,
+                &filing.cover.filer_id,
+                &filing.cover.filer_name,
+                "Q1",
+                "",
+                "",
+            ],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn merges_amendment_chains() {
+        let mut db = Connection::open_in_memory().unwrap();
+        let mut tx = db.transaction().unwrap();
+        tx.execute(CREATE_FILINGS_SQL, []).unwrap();
+        tx.execute(CREATE_TRANSACTION_LINKS_SQL, []).unwrap();
+        let sa = |transaction_id, amount, amended_cd| {
+            vec![
+                ("form_type", "SA11A1"),
+                ("transaction_id", transaction_id),
+                ("contribution_amount", amount),
+                ("amended_cd", amended_cd),
+            ]
+        };
+        let mut chains = AmendmentChains::new();
+        let export = |tx: &mut Transaction,
+                      chains: &mut AmendmentChains,
+                      filing: Filing<Cursor<Vec<u8>>>| {
+            insert_filing(tx, &filing);
+            chains.insert(
+                &filing.filing_id,
+                filing.header.report_id.as_deref(),
+                filing.header.report_number.as_deref(),
+            );
+            export_itemizations_by_form_type(filing, tx, &ProgressBar::hidden(), false).unwrap();
+        };
+        let rows = |tx: &Transaction, table: &str| -> Vec<(String, String, f64)> {
+            tx.prepare(&format!(
+                "SELECT filing_id, transaction_id, contribution_amount FROM {table} ORDER BY 2"
+            ))
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap()
+            .map(|row| row.unwrap())
+            .collect()
+        };
+
+        let original = [
+            sa("SA.1", "10", ""),
+            sa("SA.2", "20", ""),
+            sa("SA.3", "30", ""),
+        ];
+        export(&mut tx, &mut chains, filing("1", "5.3", "", &original));
+        let amendment = [
+            sa("SA.1", "10", "D"),
+            sa("SA.2", "25", "C"),
+            sa("SA.4", "40", "A"),
+        ];
+        export(
+            &mut tx,
+            &mut chains,
+            filing("2", "5.3", "FEC-1", &amendment),
+        );
+        let merged = merge_amendment_chains(&tx, &chains, &["1".to_owned(), "2".to_owned()]);
+        assert_eq!(merged.unwrap(), 1);
+        assert_eq!(
+            rows(&tx, "libfec_SA11A1"),
+            vec![
+                ("2".to_owned(), "SA.2".to_owned(), 25.0),
+                ("2".to_owned(), "SA.3".to_owned(), 30.0),
+                ("2".to_owned(), "SA.4".to_owned(), 40.0),
+            ]
+        );
+
+        // a later amendment without amended_cd replaces the report, and its
+        // rows stay in the tables of their own version
+        let replacement = [vec![
+            ("form_type", "SA11AI"),
+            ("transaction_id", "SA.5"),
+            ("contribution_amount", "50"),
+        ]];
+        export(
+            &mut tx,
+            &mut chains,
+            filing("3", "8.4", "FEC-1", &replacement),
+        );
+        let merged = merge_amendment_chains(&tx, &chains, &["3".to_owned()]);
+        assert_eq!(merged.unwrap(), 1);
+        assert!(rows(&tx, "libfec_SA11A1").is_empty());
+        assert_eq!(
+            rows(&tx, "libfec_SA11AI"),
+            vec![("3".to_owned(), "SA.5".to_owned(), 50.0)]
+        );
+    }
+
+    #[test]
+    fn adds_label_columns() {
+        let mut db = Connection::open_in_memory().unwrap();
+        let tx = db.transaction().unwrap();
+        let column_names = fec_parser::mappings::column_names_for_field("SA11AI", "8.4").unwrap();
+        let label_columns = label_columns(column_names);
+        let table_columns = |tx: &Transaction| -> Vec<String> {
+            let mut stmt = tx
+                .prepare("SELECT name FROM pragma_table_info('libfec_SA11AI')")
+                .unwrap();
+            let names = stmt.query_map([], |row| row.get(0)).unwrap();
+            names.map(|name| name.unwrap()).collect()
+        };
+
+        tx.execute(
+            &create_table_sql("SA11AI", column_names, true, true, &[]),
+            [],
+        )
+        .unwrap();
+        let unlabeled = table_columns(&tx);
+        let label_names: Vec<&str> = label_columns.iter().map(|(_, name)| *name).collect();
+        let columns = insert_columns(column_names, true, true, &label_names);
+        ensure_columns(&tx, "SA11AI", &columns).unwrap();
+        ensure_columns(&tx, "SA11AI", &columns).unwrap();
+        let labeled = table_columns(&tx);
+
+        assert!(!label_columns.is_empty());
+        assert_eq!(labeled.len(), unlabeled.len() + label_columns.len());
+        assert_eq!(labeled[..unlabeled.len()], unlabeled[..]);
+        assert_eq!(
+            labeled.last().unwrap(),
+            &format!("{}_label", label_columns.last().unwrap().1)
+        );
+    }
+
+    // tables from before parent_transaction_id was exported get it added
+    #[test]
+    fn adds_parent_transaction_id_to_old_tables() {
+        let mut db = Connection::open_in_memory().unwrap();
+        let mut tx = db.transaction().unwrap();
+        tx.execute(CREATE_FILINGS_SQL, []).unwrap();
+        tx.execute(CREATE_TRANSACTION_LINKS_SQL, []).unwrap();
+        let column_names = fec_parser::mappings::column_names_for_field("SA11AI", "8.4").unwrap();
+        tx.execute(
+            &create_table_sql("SA11AI", column_names, false, false, &[]),
+            [],
+        )
+        .unwrap();
+
+        let records = [vec![
+            ("form_type", "SA11AI"),
+            ("transaction_id", "SA.2"),
+            ("back_reference_tran_id_number", "SA.1"),
+            ("contribution_amount", "10"),
+        ]];
+        let filing = filing("1", "8.4", "", &records);
+        insert_filing(&tx, &filing);
+        export_itemizations_by_form_type(filing, &mut tx, &ProgressBar::hidden(), false).unwrap();
+        let row: (String, String, String, f64) = tx
+            .query_row(
+                "SELECT filing_id, transaction_id, parent_transaction_id, contribution_amount FROM libfec_SA11AI",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )
+            .unwrap();
+        assert_eq!(
+            row,
+            ("1".to_owned(), "SA.2".to_owned(), "SA.1".to_owned(), 10.0)
+        );
+    }
+
+    // tables from before TEXT memos were attached get memo_text added, for
+    // both targets
+    #[test]
+    fn attaches_memos_in_old_tables() {
+        let mut db = Connection::open_in_memory().unwrap();
+        let mut tx = db.transaction().unwrap();
+        tx.execute(CREATE_FILINGS_SQL, []).unwrap();
+        tx.execute(CREATE_TRANSACTION_LINKS_SQL, []).unwrap();
+        let column_names = fec_parser::mappings::column_names_for_field("SA11AI", "8.4").unwrap();
+        for (table, column_names) in [
+            ("SA11AI", column_names.as_slice()),
+            (Schedule::A.table_name(), Schedule::A.columns()),
+        ] {
+            tx.execute(&create_table_sql(table, column_names, true, false, &[]), [])
+                .unwrap();
+        }
+
+        let records = [
+            vec![
+                ("form_type", "SA11AI"),
+                ("transaction_id", "SA.1"),
+                ("contribution_amount", "10"),
+            ],
+            vec![
+                ("rec_type", "TEXT"),
+                ("transaction_id_number", "TEXT.1"),
+                ("back_reference_tran_id_number", "SA.1"),
+                ("back_reference_sched_form_name", "SA11AI"),
+                ("text", "Earmarked"),
+            ],
+        ];
+        let by_form_type = filing("1", "8.4", "", &records);
+        insert_filing(&tx, &by_form_type);
+        export_itemizations_by_form_type(by_form_type, &mut tx, &ProgressBar::hidden(), false)
+            .unwrap();
+        let schedule_a = filing("2", "8.4", "", &records);
+        insert_filing(&tx, &schedule_a);
+        export_schedule_a(schedule_a, &mut tx, &ProgressBar::hidden(), false).unwrap();
+
+        for table in ["libfec_SA11AI", "libfec_schedule_a"] {
+            let memo: String = tx
+                .query_row(
+                    &format!("SELECT memo_text FROM {table} WHERE transaction_id = 'SA.1'"),
+                    [],
+                    |row| row.get(0),
+                )
+                .unwrap();
+            assert_eq!(memo, "Earmarked");
+        }
+    }
+
+    // exports with and without --labels can go into the same tables, in
+    // either order
+    #[test]
+    fn mixes_labeled_exports() {
+        let mut db = Connection::open_in_memory().unwrap();
+        let mut tx = db.transaction().unwrap();
+        tx.execute(CREATE_FILINGS_SQL, []).unwrap();
+        tx.execute(CREATE_TRANSACTION_LINKS_SQL, []).unwrap();
+        let sa = |transaction_id| {
+            [vec![
+                ("form_type", "SA11AI"),
+                ("entity_type", "IND"),
+                ("transaction_id", transaction_id),
+                ("contribution_amount", "10"),
+            ]]
+        };
+        for (filing_id, labels) in [("1", true), ("2", false), ("3", true)] {
+            let filing = filing(filing_id, "8.4", "", &sa(filing_id));
+            insert_filing(&tx, &filing);
+            export_itemizations_by_form_type(filing, &mut tx, &ProgressBar::hidden(), labels)
+                .unwrap();
+        }
+
+        let rows: Vec<(String, String, Option<String>)> = tx
+            .prepare(
+                "SELECT filing_id, entity_type, entity_type_label FROM libfec_SA11AI ORDER BY 1",
+            )
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap()
+            .map(|row| row.unwrap())
+            .collect();
+        let individual = Some("Individual".to_owned());
+        assert_eq!(
+            rows,
+            vec![
+                ("1".to_owned(), "IND".to_owned(), individual.clone()),
+                ("2".to_owned(), "IND".to_owned(), None),
+                ("3".to_owned(), "IND".to_owned(), individual),
+            ]
+        );
+
+        // and the other way around, into a table created without labels
+        let mut db = Connection::open_in_memory().unwrap();
+        let mut tx = db.transaction().unwrap();
+        tx.execute(CREATE_FILINGS_SQL, []).unwrap();
+        tx.execute(CREATE_TRANSACTION_LINKS_SQL, []).unwrap();
+        for (filing_id, labels) in [("1", false), ("2", true)] {
+            let filing = filing(filing_id, "8.4", "", &sa(filing_id));
+            insert_filing(&tx, &filing);
+            export_itemizations_by_form_type(filing, &mut tx, &ProgressBar::hidden(), labels)
+                .unwrap();
+        }
+        let labels: Vec<Option<String>> = tx
+            .prepare("SELECT entity_type_label FROM libfec_SA11AI ORDER BY filing_id")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .map(|row| row.unwrap())
+            .collect();
+        assert_eq!(labels, vec![None, Some("Individual".to_owned())]);
+    }
+}
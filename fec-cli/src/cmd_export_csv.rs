@@ -0,0 +1,539 @@
+use fec_parser::{
+    format_amount,
+    mappings::{column_type, ColumnType},
+    parse_amount, FecDate, Filing, FilingRowReadError,
+};
+use flate2::{read::MultiGzDecoder, write::GzEncoder, Compression};
+use indicatif::{HumanDuration, MultiProgress, ProgressBar};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    error::Error,
+    fs::{self, File, OpenOptions},
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+use thiserror::Error;
+
+use crate::{
+    cmd_export::{CmdExportTarget, TargetRows, BAR_FILES_STYLE, BAR_FILE_STYLE},
+    sourcer::FilingSourcer,
+};
+
+#[derive(Error, Debug)]
+pub enum CmdExportCsvError {
+    #[error("`{0}`: {1}")]
+    Io(String, #[source] io::Error),
+    #[error("`{0}`: {1}")]
+    Csv(String, #[source] csv::Error),
+    #[error("Error reading filing {0}: {1}")]
+    Row(String, #[source] FilingRowReadError),
+    #[error("`{0}` already has other columns")]
+    Columns(String),
+}
+
+pub struct CsvOptions {
+    // add to existing files instead of replacing them
+    pub append: bool,
+    pub gzip: bool,
+}
+
+// Dates as YYYY-MM-DD and amounts with two decimals. Values that don't
+// parse are written as they are.
+fn format_value<'a>(column: &str, value: &'a str) -> Cow<'a, str> {
+    match column_type(column) {
+        ColumnType::Date => match FecDate::parse(value.trim()) {
+            Some(date) => Cow::Owned(date.to_string()),
+            None => Cow::Borrowed(value),
+        },
+        ColumnType::Float => match parse_amount(value) {
+            Some(cents) => Cow::Owned(format_amount(cents)),
+            None => Cow::Borrowed(value),
+        },
+        ColumnType::Text => Cow::Borrowed(value),
+    }
+}
+
+enum Output {
+    Plain(BufWriter<File>),
+    Gzip(GzEncoder<BufWriter<File>>),
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::Plain(w) => w.write(buf),
+            Self::Gzip(w) => w.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::Plain(w) => w.flush(),
+            Self::Gzip(w) => w.flush(),
+        }
+    }
+}
+
+impl Output {
+    fn finish(self) -> io::Result<()> {
+        match self {
+            Self::Plain(mut w) => w.flush(),
+            Self::Gzip(w) => w.finish()?.flush(),
+        }
+    }
+}
+
+// the header row of an existing file, if it has one
+fn existing_header(path: &Path, gzip: bool) -> io::Result<Option<Vec<String>>> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let rdr: Box<dyn Read> = if gzip {
+        Box::new(MultiGzDecoder::new(BufReader::new(file)))
+    } else {
+        Box::new(BufReader::new(file))
+    };
+    let mut rdr = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .from_reader(rdr);
+    Ok(rdr
+        .records()
+        .next()
+        .and_then(|record| record.ok())
+        .map(|record| record.iter().map(|v| v.to_owned()).collect()))
+}
+
+struct CsvFile {
+    columns: &'static [String],
+    writer: csv::Writer<Output>,
+    rows: usize,
+}
+
+impl CsvFile {
+    fn close(self) -> Result<usize, io::Error> {
+        let output = self.writer.into_inner().map_err(|e| e.into_error())?;
+        output.finish()?;
+        Ok(self.rows)
+    }
+}
+
+// The open files of an export, keyed by path, which stay open across
+// filings
+struct CsvFiles {
+    out_dir: PathBuf,
+    options: CsvOptions,
+    files: HashMap<PathBuf, CsvFile>,
+    written: Vec<(PathBuf, usize)>,
+}
+
+impl CsvFiles {
+    fn path(&self, name: &str) -> PathBuf {
+        let extension = if self.options.gzip { "csv.gz" } else { "csv" };
+        self.out_dir.join(format!("{name}.{extension}"))
+    }
+
+    fn header(columns: &[String]) -> Vec<&str> {
+        std::iter::once("filing_id")
+            .chain(columns.iter().map(|c| c.as_str()))
+            .collect()
+    }
+
+    fn open(
+        &self,
+        path: &Path,
+        columns: &'static [String],
+        append: bool,
+    ) -> Result<CsvFile, CmdExportCsvError> {
+        let io_error = |e| CmdExportCsvError::Io(format!("Error opening {}", path.display()), e);
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(append)
+            .truncate(!append)
+            .open(path)
+            .map_err(io_error)?;
+        let has_header = append && file.metadata().map_err(io_error)?.len() > 0;
+        let output = if self.options.gzip {
+            Output::Gzip(GzEncoder::new(BufWriter::new(file), Compression::default()))
+        } else {
+            Output::Plain(BufWriter::new(file))
+        };
+        let mut writer = csv::WriterBuilder::new()
+            .flexible(true)
+            .has_headers(false)
+            .from_writer(output);
+        if !has_header {
+            writer.write_record(Self::header(columns)).map_err(|e| {
+                CmdExportCsvError::Csv(format!("Error writing {}", path.display()), e)
+            })?;
+        }
+        Ok(CsvFile {
+            columns,
+            writer,
+            rows: 0,
+        })
+    }
+
+    // The file of a table, which with `--append` can be from an earlier run,
+    // but never one with other columns.
+    fn file(
+        &mut self,
+        name: &str,
+        columns: &'static [String],
+    ) -> Result<&mut CsvFile, CmdExportCsvError> {
+        let path = self.path(name);
+        let columns_error = || CmdExportCsvError::Columns(path.display().to_string());
+        if let Some(file) = self.files.get(&path) {
+            if file.columns != columns {
+                return Err(columns_error());
+            }
+            return Ok(self.files.get_mut(&path).unwrap());
+        }
+        let append = self.options.append;
+        if append {
+            let header = existing_header(&path, self.options.gzip).map_err(|e| {
+                CmdExportCsvError::Io(format!("Error reading {}", path.display()), e)
+            })?;
+            if header.is_some_and(|header| header != Self::header(columns)) {
+                return Err(columns_error());
+            }
+        }
+        let file = self.open(&path, columns, append)?;
+        Ok(self.files.entry(path).or_insert(file))
+    }
+
+    fn close(&mut self) -> Result<(), CmdExportCsvError> {
+        for (path, file) in self.files.drain() {
+            let rows = file.close().map_err(|e| {
+                CmdExportCsvError::Io(format!("Error writing {}", path.display()), e)
+            })?;
+            self.written.push((path, rows));
+        }
+        Ok(())
+    }
+}
+
+fn export_filing<R: Read>(
+    mut filing: Filing<R>,
+    files: &mut CsvFiles,
+    target: &CmdExportTarget,
+    pb: &ProgressBar,
+) -> Result<(), CmdExportCsvError> {
+    let filing_id = filing.filing_id.clone();
+    let mut rows = TargetRows::new(target, &filing.header);
+    while let Some(r) = filing.next_row() {
+        let r = r.map_err(|e| CmdExportCsvError::Row(filing_id.clone(), e))?;
+        if let Some(position) = r.record.position() {
+            pb.set_position(position.byte());
+        }
+        let Some(row) = rows.map(&r) else {
+            continue;
+        };
+        // ex SA11AI.csv, or SA11AI_v6_4.csv for the rows of older versions
+        let file = files.file(&rows.versioned_table(&row), row.columns)?;
+        // fields past the columns are dropped, missing ones are empty
+        let values = row.columns.iter().enumerate().map(|(idx, column)| {
+            format_value(column, row.values.get(idx).map_or("", |v| v.as_ref()))
+        });
+        let record: Vec<Cow<str>> = std::iter::once(Cow::Borrowed(filing_id.as_str()))
+            .chain(values)
+            .collect();
+        file.writer
+            .write_record(record.iter().map(|v| v.as_bytes()))
+            .map_err(|e| CmdExportCsvError::Csv(format!("Error writing filing {filing_id}"), e))?;
+        file.rows += 1;
+    }
+    Ok(())
+}
+
+pub fn cmd_export_csv(
+    filings: Vec<String>,
+    out_dir: &str,
+    target: CmdExportTarget,
+    options: CsvOptions,
+) -> Result<(), Box<dyn Error>> {
+    let filing_sourcer = FilingSourcer::new();
+    let t0 = Instant::now();
+    fs::create_dir_all(out_dir)
+        .map_err(|e| CmdExportCsvError::Io(format!("Error creating {out_dir}"), e))?;
+    let mut files = CsvFiles {
+        out_dir: PathBuf::from(out_dir),
+        options,
+        files: HashMap::new(),
+        written: vec![],
+    };
+    let mb = MultiProgress::new();
+    let pb_files = if filings.len() > 1 {
+        let pb_files = mb.add(ProgressBar::new(filings.len() as u64));
+        pb_files.set_style(BAR_FILES_STYLE.clone());
+        pb_files.enable_steady_tick(Duration::from_millis(100));
+        Some(pb_files)
+    } else {
+        None
+    };
+
+    for filing in &filings {
+        let filing = filing_sourcer.resolve(filing);
+        let pb_file = mb.add(ProgressBar::new(
+            filing.source_length.unwrap_or_default() as u64
+        ));
+        pb_file.set_style(BAR_FILE_STYLE.clone());
+        pb_file.set_message(format!("FEC-{}", filing.filing_id));
+        export_filing(filing, &mut files, &target, &pb_file)?;
+        pb_file.finish_and_clear();
+        if let Some(pb_files) = &pb_files {
+            pb_files.inc(1);
+        }
+    }
+    files.close()?;
+    if let Some(pb_files) = &pb_files {
+        pb_files.finish_and_clear();
+    }
+
+    let rows: usize = files.written.iter().map(|(_, rows)| rows).sum();
+    println!(
+        "Wrote {rows} rows to {} CSV files in {}",
+        files.written.len(),
+        files.out_dir.display()
+    );
+    println!(
+        "Finished {} files in {}",
+        filings.len(),
+        HumanDuration(Instant::now() - t0)
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::filing;
+    use fec_parser::mappings::column_names_for_field;
+    use std::io::Cursor;
+
+    fn files(out_dir: &Path, append: bool, gzip: bool) -> CsvFiles {
+        CsvFiles {
+            out_dir: out_dir.to_owned(),
+            options: CsvOptions { append, gzip },
+            files: HashMap::new(),
+            written: vec![],
+        }
+    }
+
+    fn sa<'a>(transaction_id: &'a str, date: &'a str, amount: &'a str) -> Vec<(&'a str, &'a str)> {
+        vec![
+            ("form_type", "SA11AI"),
+            ("transaction_id", transaction_id),
+            ("contribution_date", date),
+            ("contribution_amount", amount),
+        ]
+    }
+
+    fn export(
+        files: &mut CsvFiles,
+        target: CmdExportTarget,
+        filings: Vec<Filing<Cursor<Vec<u8>>>>,
+    ) -> Vec<(String, usize)> {
+        for filing in filings {
+            export_filing(filing, files, &target, &ProgressBar::hidden()).unwrap();
+        }
+        files.close().unwrap();
+        let mut written: Vec<_> = files
+            .written
+            .drain(..)
+            .map(|(path, rows)| {
+                let name = path.file_name().unwrap().to_str().unwrap().to_owned();
+                (name, rows)
+            })
+            .collect();
+        written.sort();
+        written
+    }
+
+    // the records of a file, header first, with the given columns of each
+    fn read(path: &Path, gzip: bool, columns: &[&str]) -> Vec<Vec<String>> {
+        let file = File::open(path).unwrap();
+        let rdr: Box<dyn Read> = if gzip {
+            Box::new(MultiGzDecoder::new(file))
+        } else {
+            Box::new(file)
+        };
+        let mut rdr = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .from_reader(rdr);
+        let records: Vec<csv::StringRecord> = rdr.records().map(|r| r.unwrap()).collect();
+        let idxs: Vec<usize> = columns
+            .iter()
+            .map(|column| records[0].iter().position(|c| c == *column).unwrap())
+            .collect();
+        records
+            .iter()
+            .map(|record| idxs.iter().map(|idx| record[*idx].to_owned()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn formats_values() {
+        let out_dir = tempfile::tempdir().unwrap();
+        let written = export(
+            &mut files(out_dir.path(), false, false),
+            CmdExportTarget::ByFormType,
+            vec![filing(
+                "1",
+                "8.4",
+                "",
+                &[sa("SA.1", "20240115", "100.5"), sa("SA.2", "2024", "1,000")],
+            )],
+        );
+        assert_eq!(written, vec![("SA11AI.csv".to_owned(), 2)]);
+        assert_eq!(
+            read(
+                &out_dir.path().join("SA11AI.csv"),
+                false,
+                &["filing_id", "contribution_date", "contribution_amount"]
+            ),
+            vec![
+                vec!["filing_id", "contribution_date", "contribution_amount"],
+                vec!["1", "2024-01-15", "100.50"],
+                // values that don't parse are kept
+                vec!["1", "2024", "1,000"],
+            ]
+        );
+    }
+
+    #[test]
+    fn names_files_by_version() {
+        let filings = || {
+            vec![
+                filing("1", "8.4", "", &[sa("SA.1", "20240115", "10")]),
+                filing("2", "6.4", "", &[sa("SA.2", "20210601", "20")]),
+            ]
+        };
+        let expected = vec![
+            ("SA11AI.csv".to_owned(), 1),
+            ("SA11AI_v6_4.csv".to_owned(), 1),
+        ];
+        let out_dir = tempfile::tempdir().unwrap();
+        let mut csv_files = files(out_dir.path(), false, false);
+        assert_eq!(
+            export(&mut csv_files, CmdExportTarget::ByFormType, filings()),
+            expected
+        );
+        // the same files whichever filing comes first
+        let mut reversed = filings();
+        reversed.reverse();
+        assert_eq!(
+            export(&mut csv_files, CmdExportTarget::ByFormType, reversed),
+            expected
+        );
+    }
+
+    #[test]
+    fn appends_to_files_with_the_same_columns() {
+        let out_dir = tempfile::tempdir().unwrap();
+        let columns = column_names_for_field("SA11AI", "8.4").unwrap();
+        let header = CsvFiles::header(columns).join(",") + "\n";
+
+        // an earlier run's file gets no second header
+        fs::write(out_dir.path().join("SA11AI.csv"), &header).unwrap();
+        let mut csv_files = files(out_dir.path(), true, false);
+        csv_files.file("SA11AI", columns).unwrap().rows += 1;
+        csv_files.close().unwrap();
+        assert_eq!(
+            csv_files.written,
+            vec![(out_dir.path().join("SA11AI.csv"), 1)]
+        );
+        assert_eq!(
+            fs::read_to_string(out_dir.path().join("SA11AI.csv")).unwrap(),
+            header
+        );
+
+        // and one with other columns is an error, not appended to
+        fs::write(out_dir.path().join("SA11AI.csv"), "filing_id,other\n").unwrap();
+        let mut csv_files = files(out_dir.path(), true, false);
+        assert!(matches!(
+            csv_files.file("SA11AI", columns),
+            Err(CmdExportCsvError::Columns(_))
+        ));
+    }
+
+    #[test]
+    fn appends_to_gzip_files() {
+        let out_dir = tempfile::tempdir().unwrap();
+        for (filing_id, transaction_id) in [("1", "SA.1"), ("2", "SA.2")] {
+            let written = export(
+                &mut files(out_dir.path(), true, true),
+                CmdExportTarget::ByFormType,
+                vec![filing(
+                    filing_id,
+                    "8.4",
+                    "",
+                    &[sa(transaction_id, "20240115", "10")],
+                )],
+            );
+            assert_eq!(written, vec![("SA11AI.csv.gz".to_owned(), 1)]);
+        }
+        // each run adds a gzip member, read back as one file
+        assert_eq!(
+            read(
+                &out_dir.path().join("SA11AI.csv.gz"),
+                true,
+                &["filing_id", "transaction_id"]
+            ),
+            vec![
+                vec!["filing_id", "transaction_id"],
+                vec!["1", "SA.1"],
+                vec!["2", "SA.2"],
+            ]
+        );
+    }
+
+    #[test]
+    fn exports_schedules() {
+        let out_dir = tempfile::tempdir().unwrap();
+        let written = export(
+            &mut files(out_dir.path(), false, false),
+            CmdExportTarget::Schedules,
+            vec![
+                filing("1", "8.4", "", &[sa("SA.1", "20240115", "10")]),
+                filing(
+                    "2",
+                    "6.4",
+                    "",
+                    &[
+                        sa("SA.2", "20210601", "20"),
+                        vec![
+                            ("form_type", "SB17"),
+                            ("transaction_id_number", "SB.1"),
+                            ("expenditure_amount", "30"),
+                        ],
+                    ],
+                ),
+            ],
+        );
+        // older versions are mapped to the latest columns, so share a file
+        assert_eq!(
+            written,
+            vec![
+                ("schedule_a.csv".to_owned(), 2),
+                ("schedule_b.csv".to_owned(), 1)
+            ]
+        );
+        assert_eq!(
+            read(
+                &out_dir.path().join("schedule_a.csv"),
+                false,
+                &["filing_id", "transaction_id", "contribution_amount"]
+            ),
+            vec![
+                vec!["filing_id", "transaction_id", "contribution_amount"],
+                vec!["1", "SA.1", "10.00"],
+                vec!["2", "SA.2", "20.00"],
+            ]
+        );
+    }
+}
@@ -0,0 +1,479 @@
+use duckdb::{
+    arrow::{error::ArrowError, record_batch::RecordBatch},
+    params, Connection,
+};
+use fec_parser::{
+    amendments::AmendmentChains,
+    arrow::RecordBatchBuilder,
+    mappings::{column_type, ColumnType},
+    FecDate, Filing, FilingRowReadError, AMOUNT_PRECISION, AMOUNT_SCALE,
+};
+use indicatif::{HumanDuration, MultiProgress, ProgressBar};
+use std::{
+    collections::HashMap,
+    error::Error,
+    io::Read,
+    time::{Duration, Instant},
+};
+use thiserror::Error;
+
+use crate::{
+    cmd_export::{quote_ident, CmdExportTarget, TargetRows, BAR_FILES_STYLE, BAR_FILE_STYLE},
+    sourcer::FilingSourcer,
+};
+
+// DuckDB's data chunks hold at most this many rows, so record batches are
+// appended this many rows at a time
+const CHUNK_SIZE: usize = 2048;
+
+#[derive(Error, Debug)]
+pub enum CmdExportDuckdbError {
+    #[error("`{0}`: {1}")]
+    Duckdb(String, #[source] duckdb::Error),
+    #[error("`{0}`: {1}")]
+    Arrow(String, #[source] ArrowError),
+    #[error("Error reading filing {0}: {1}")]
+    Row(String, #[source] FilingRowReadError),
+    #[error("`{0}` already has other columns")]
+    Columns(String),
+}
+
+const CREATE_FILINGS_SQL: &str = r#"
+  CREATE TABLE IF NOT EXISTS libfec_filings(
+    filing_id VARCHAR PRIMARY KEY,
+    fec_version VARCHAR NOT NULL,
+    software_name VARCHAR NOT NULL,
+    software_version VARCHAR NOT NULL,
+    report_id VARCHAR,
+    report_number VARCHAR,
+    comment VARCHAR,
+    cover_record_form_type VARCHAR NOT NULL,
+    filer_id VARCHAR NOT NULL,
+    filer_name VARCHAR NOT NULL,
+    report_code VARCHAR,
+    coverage_from_date DATE,
+    coverage_through_date DATE,
+    superseded_by VARCHAR
+  )
+"#;
+
+fn duckdb_type(column: &str) -> String {
+    match column_type(column) {
+        ColumnType::Date => "DATE".to_owned(),
+        ColumnType::Float => format!("DECIMAL({AMOUNT_PRECISION}, {AMOUNT_SCALE})"),
+        ColumnType::Text => "VARCHAR".to_owned(),
+    }
+}
+
+// The columns of the record batches, which the appender fills in order
+fn create_table_sql(table: &str, columns: &[String]) -> String {
+    let definitions: Vec<String> = columns
+        .iter()
+        .map(|column| format!("{} {}", quote_ident(column), duckdb_type(column)))
+        .collect();
+    format!(
+        "CREATE TABLE {}(\n  filing_id VARCHAR NOT NULL,\n  {}\n)",
+        quote_ident(table),
+        definitions.join(",\n  ")
+    )
+}
+
+// the columns of an existing table, empty if there is no such table
+fn table_columns(conn: &Connection, table: &str) -> Result<Vec<String>, duckdb::Error> {
+    conn.prepare(
+        "SELECT column_name FROM information_schema.columns
+          WHERE table_schema = 'main' AND table_name = ?
+          ORDER BY ordinal_position",
+    )?
+    .query_map([table], |row| row.get(0))?
+    .collect()
+}
+
+fn append(conn: &Connection, table: &str, batch: RecordBatch) -> Result<(), duckdb::Error> {
+    let mut appender = conn.appender(table)?;
+    appender.append_record_batch(batch)?;
+    appender.flush()
+}
+
+struct DuckdbExport {
+    // the columns of each table used so far
+    tables: HashMap<String, &'static [String]>,
+}
+
+impl DuckdbExport {
+    // Rows of a form type with other columns than its latest version go to
+    // a table for their version, see `TargetRows::versioned_table`. A table
+    // from an earlier run with other columns is an error, since the appender
+    // needs the exact columns of the table.
+    fn table(
+        &mut self,
+        conn: &Connection,
+        name: &str,
+        columns: &'static [String],
+    ) -> Result<String, CmdExportDuckdbError> {
+        let table = format!("libfec_{name}");
+        match self.tables.get(&table) {
+            Some(table_columns) if *table_columns == columns => return Ok(table),
+            Some(_) => return Err(CmdExportDuckdbError::Columns(table)),
+            None => {}
+        }
+        let sql_error = |e| CmdExportDuckdbError::Duckdb(format!("Error creating {table}"), e);
+        let existing = table_columns(conn, &table).map_err(sql_error)?;
+        if existing.is_empty() {
+            conn.execute_batch(&create_table_sql(&table, columns))
+                .map_err(sql_error)?;
+        } else if !existing
+            .iter()
+            .map(String::as_str)
+            .eq(std::iter::once("filing_id").chain(columns.iter().map(String::as_str)))
+        {
+            return Err(CmdExportDuckdbError::Columns(table));
+        }
+        self.tables.insert(table.clone(), columns);
+        Ok(table)
+    }
+
+    // Re-running an export replaces the rows of filings that were exported
+    // before, in every libfec table
+    fn delete_filing(&self, conn: &Connection, filing_id: &str) -> Result<(), duckdb::Error> {
+        let tables: Vec<String> = conn
+            .prepare(
+                "SELECT table_name FROM information_schema.columns
+                  WHERE table_schema = 'main'
+                    AND table_name LIKE 'libfec\\_%' ESCAPE '\\'
+                    AND column_name = 'filing_id'",
+            )?
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<_, _>>()?;
+        for table in tables {
+            conn.execute(
+                &format!("DELETE FROM {} WHERE filing_id = ?", quote_ident(&table)),
+                [filing_id],
+            )?;
+        }
+        Ok(())
+    }
+
+    fn export_filing<R: Read>(
+        &mut self,
+        mut filing: Filing<R>,
+        conn: &Connection,
+        target: &CmdExportTarget,
+        pb: &ProgressBar,
+    ) -> Result<(), CmdExportDuckdbError> {
+        let filing_id = filing.filing_id.clone();
+        let sql_error =
+            |e| CmdExportDuckdbError::Duckdb(format!("Error inserting filing {filing_id}"), e);
+        self.delete_filing(conn, &filing_id).map_err(sql_error)?;
+        let date = |value: &Option<String>| {
+            value
+                .as_deref()
+                .and_then(FecDate::parse)
+                .map(|date| date.to_string())
+        };
+        conn.execute(
+            "INSERT INTO libfec_filings VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?::DATE, ?::DATE, NULL)",
+            params![
+                &filing.filing_id,
+                &filing.header.fec_version,
+                &filing.header.soft_name,
+                &filing.header.soft_ver,
+                &filing.header.report_id,
+                &filing.header.report_number,
+                &filing.header.comment,
+                &filing.cover.form_type,
+                &filing.cover.filer_id,
+                &filing.cover.filer_name,
+                &filing.cover.report_code,
+                &date(&filing.cover.coverage_from_date),
+                &date(&filing.cover.coverage_through_date),
+            ],
+        )
+        .map_err(sql_error)?;
+
+        // rows are collected per table, and appended a chunk at a time
+        let mut rows = TargetRows::new(target, &filing.header);
+        let mut builders: HashMap<String, RecordBatchBuilder> = HashMap::new();
+        while let Some(r) = filing.next_row() {
+            let r = r.map_err(|e| CmdExportDuckdbError::Row(filing_id.clone(), e))?;
+            if let Some(position) = r.record.position() {
+                pb.set_position(position.byte());
+            }
+            let Some(row) = rows.map(&r) else {
+                continue;
+            };
+            let table = self.table(conn, &rows.versioned_table(&row), row.columns)?;
+            let builder = builders
+                .entry(table.clone())
+                .or_insert_with(|| RecordBatchBuilder::with_batch_size(row.columns, CHUNK_SIZE));
+            // fields past the columns are dropped, missing ones are null
+            let batch = builder
+                .push_values(&filing_id, row.values.iter().map(|value| value.as_ref()))
+                .map_err(|e| {
+                    CmdExportDuckdbError::Arrow(format!("Error building rows of {table}"), e)
+                })?;
+            if let Some(batch) = batch {
+                append(conn, &table, batch).map_err(|e| {
+                    CmdExportDuckdbError::Duckdb(format!("Error appending to {table}"), e)
+                })?;
+            }
+        }
+        for (table, mut builder) in builders {
+            let batch = builder.finish().map_err(|e| {
+                CmdExportDuckdbError::Arrow(format!("Error building rows of {table}"), e)
+            })?;
+            if let Some(batch) = batch {
+                append(conn, &table, batch).map_err(|e| {
+                    CmdExportDuckdbError::Duckdb(format!("Error appending to {table}"), e)
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    // Points every filing in libfec_filings at the latest amendment of its
+    // report, like the SQLite export
+    fn mark_superseded(&self, conn: &mut Connection) -> Result<(), duckdb::Error> {
+        let mut chains = AmendmentChains::new();
+        let filings: Vec<(String, Option<String>, Option<String>)> = conn
+            .prepare("SELECT filing_id, report_id, report_number FROM libfec_filings")?
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+            .collect::<Result<_, _>>()?;
+        for (filing_id, report_id, report_number) in &filings {
+            chains.insert(filing_id, report_id.as_deref(), report_number.as_deref());
+        }
+        let tx = conn.transaction()?;
+        {
+            let mut statement =
+                tx.prepare("UPDATE libfec_filings SET superseded_by = ? WHERE filing_id = ?")?;
+            for chain in chains.chains() {
+                let latest = chain.last().copied();
+                for filing_id in chain {
+                    let superseded_by = latest.filter(|latest| *latest != filing_id);
+                    statement.execute(params![superseded_by, filing_id])?;
+                }
+            }
+        }
+        tx.commit()
+    }
+}
+
+pub fn cmd_export_duckdb(
+    filings: Vec<String>,
+    path: &str,
+    target: CmdExportTarget,
+) -> Result<(), Box<dyn Error>> {
+    let filing_sourcer = FilingSourcer::new();
+    let t0 = Instant::now();
+    let mut conn = Connection::open(path)
+        .map_err(|e| CmdExportDuckdbError::Duckdb(format!("Error opening {path}"), e))?;
+    conn.execute_batch(CREATE_FILINGS_SQL)
+        .map_err(|e| CmdExportDuckdbError::Duckdb(format!("Error creating tables in {path}"), e))?;
+    let mut export = DuckdbExport {
+        tables: HashMap::new(),
+    };
+
+    let mb = MultiProgress::new();
+    let pb_files = if filings.len() > 1 {
+        let pb_files = mb.add(ProgressBar::new(filings.len() as u64));
+        pb_files.set_style(BAR_FILES_STYLE.clone());
+        pb_files.enable_steady_tick(Duration::from_millis(100));
+        Some(pb_files)
+    } else {
+        None
+    };
+
+    for filing in &filings {
+        let filing = filing_sourcer.resolve(filing);
+        let pb_file = mb.add(ProgressBar::new(
+            filing.source_length.unwrap_or_default() as u64
+        ));
+        pb_file.set_style(BAR_FILE_STYLE.clone());
+        pb_file.set_message(format!("FEC-{}", filing.filing_id));
+        // each filing is exported in its own transaction, so a failed
+        // export never leaves a partial filing behind
+        let filing_id = filing.filing_id.clone();
+        let sql_error =
+            |e| CmdExportDuckdbError::Duckdb(format!("Error inserting filing {filing_id}"), e);
+        let tx = conn.transaction().map_err(sql_error)?;
+        export.export_filing(filing, &tx, &target, &pb_file)?;
+        tx.commit().map_err(sql_error)?;
+        pb_file.finish_and_clear();
+        if let Some(pb_files) = &pb_files {
+            pb_files.inc(1);
+        }
+    }
+    export.mark_superseded(&mut conn).map_err(|e| {
+        CmdExportDuckdbError::Duckdb("Error marking superseded filings".to_owned(), e)
+    })?;
+    if let Some(pb_files) = &pb_files {
+        pb_files.finish_and_clear();
+    }
+
+    println!(
+        "Finished {} files in {}",
+        filings.len(),
+        HumanDuration(Instant::now() - t0)
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::filing;
+
+    fn sa(transaction_id: &str, date: &str, amount: &str) -> Vec<(&'static str, String)> {
+        vec![
+            ("form_type", "SA11AI".to_owned()),
+            ("transaction_id", transaction_id.to_owned()),
+            ("contribution_date", date.to_owned()),
+            ("contribution_amount", amount.to_owned()),
+        ]
+    }
+
+    // filing ID, FEC version, report ID and records
+    type TestFiling<'a> = (&'a str, &'a str, &'a str, Vec<Vec<(&'a str, String)>>);
+
+    // exports the filings in a new run, like the command does
+    fn export(conn: &mut Connection, filings: &[TestFiling]) {
+        conn.execute_batch(CREATE_FILINGS_SQL).unwrap();
+        let mut export = DuckdbExport {
+            tables: HashMap::new(),
+        };
+        for (filing_id, fec_version, report_id, records) in filings {
+            let records: Vec<Vec<(&str, &str)>> = records
+                .iter()
+                .map(|record| record.iter().map(|(k, v)| (*k, v.as_str())).collect())
+                .collect();
+            let tx = conn.transaction().unwrap();
+            export
+                .export_filing(
+                    filing(filing_id, fec_version, report_id, &records),
+                    &tx,
+                    &CmdExportTarget::ByFormType,
+                    &ProgressBar::hidden(),
+                )
+                .unwrap();
+            tx.commit().unwrap();
+        }
+        export.mark_superseded(conn).unwrap();
+    }
+
+    fn query(conn: &Connection, sql: &str) -> Vec<Vec<Option<String>>> {
+        let mut statement = conn.prepare(sql).unwrap();
+        let mut rows = statement.query([]).unwrap();
+        let mut values = vec![];
+        while let Some(row) = rows.next().unwrap() {
+            let columns = row.as_ref().column_count();
+            values.push((0..columns).map(|i| row.get(i).unwrap()).collect());
+        }
+        values
+    }
+
+    fn strings(values: &[&[Option<&str>]]) -> Vec<Vec<Option<String>>> {
+        values
+            .iter()
+            .map(|row| row.iter().map(|v| v.map(str::to_owned)).collect())
+            .collect()
+    }
+
+    #[test]
+    fn types_and_versions() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        let original = (
+            "1",
+            "8.4",
+            "",
+            vec![sa("SA.1", "20240115", "100.5"), sa("SA.2", "", "20")],
+        );
+        export(
+            &mut conn,
+            &[
+                original.clone(),
+                ("2", "6.4", "FEC-1", vec![sa("SA.3", "20240201", "5")]),
+            ],
+        );
+        // re-running the export replaces the rows of the filing
+        export(&mut conn, &[original]);
+
+        assert_eq!(
+            query(
+                &conn,
+                "SELECT filing_id, typeof(contribution_date), contribution_date::VARCHAR,
+                        typeof(contribution_amount), contribution_amount::VARCHAR
+                   FROM libfec_SA11AI ORDER BY transaction_id"
+            ),
+            strings(&[
+                &[
+                    Some("1"),
+                    Some("DATE"),
+                    Some("2024-01-15"),
+                    Some("DECIMAL(14,2)"),
+                    Some("100.50")
+                ],
+                &[
+                    Some("1"),
+                    Some("DATE"),
+                    None,
+                    Some("DECIMAL(14,2)"),
+                    Some("20.00")
+                ],
+            ])
+        );
+        // 6.4 has a contribution_purpose_code column that 8.4 doesn't
+        assert_eq!(
+            query(
+                &conn,
+                "SELECT filing_id, transaction_id FROM libfec_SA11AI_v6_4"
+            ),
+            strings(&[&[Some("2"), Some("SA.3")]])
+        );
+        assert_eq!(
+            query(
+                &conn,
+                "SELECT filing_id, superseded_by FROM libfec_filings ORDER BY filing_id"
+            ),
+            strings(&[&[Some("1"), Some("2")], &[Some("2"), None]])
+        );
+    }
+
+    #[test]
+    fn version_tables_ignore_order() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        export(
+            &mut conn,
+            &[
+                ("2", "6.4", "", vec![sa("SA.3", "20240201", "5")]),
+                ("1", "8.4", "", vec![sa("SA.1", "20240115", "100.5")]),
+            ],
+        );
+        assert_eq!(
+            query(
+                &conn,
+                "SELECT table_name, count(*)::VARCHAR FROM information_schema.columns
+                  WHERE table_name LIKE 'libfec_SA11AI%' GROUP BY 1 ORDER BY 1"
+            ),
+            strings(&[
+                &[Some("libfec_SA11AI"), Some("46")],
+                &[Some("libfec_SA11AI_v6_4"), Some("47")]
+            ])
+        );
+    }
+
+    #[test]
+    fn appends_chunks() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        let records = (0..CHUNK_SIZE * 2 + 1)
+            .map(|i| sa(&format!("SA.{i}"), "20240115", "1"))
+            .collect();
+        export(&mut conn, &[("1", "8.4", "", records)]);
+        assert_eq!(
+            query(
+                &conn,
+                "SELECT count(*)::VARCHAR, sum(contribution_amount)::VARCHAR FROM libfec_SA11AI"
+            ),
+            strings(&[&[Some("4097"), Some("4097.00")]])
+        );
+    }
+}
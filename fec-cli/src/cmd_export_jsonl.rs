@@ -0,0 +1,343 @@
+use fec_parser::{
+    format_amount,
+    mappings::{column_type, ColumnType},
+    parse_amount, FecDate, Filing, FilingRowReadError,
+};
+use indicatif::{HumanDuration, MultiProgress, ProgressBar};
+use serde_json::Value;
+use std::{
+    error::Error,
+    fs::File,
+    io::{self, BufWriter, Read, Write},
+    time::{Duration, Instant},
+};
+use thiserror::Error;
+
+use crate::{
+    cmd_export::{CmdExportTarget, TargetRows, BAR_FILES_STYLE, BAR_FILE_STYLE},
+    sourcer::FilingSourcer,
+};
+
+#[derive(Error, Debug)]
+pub enum CmdExportJsonlError {
+    #[error("`{0}`: {1}")]
+    Io(String, #[source] io::Error),
+    #[error("Error reading filing {0}: {1}")]
+    Row(String, #[source] FilingRowReadError),
+}
+
+// A field of an object. Amounts are kept as cents and written as exact
+// numbers, ex 100.50, which an f64 Value can't always represent.
+enum JsonValue {
+    Value(Value),
+    Amount(i128),
+}
+
+// Dates as YYYY-MM-DD strings, amounts as numbers, empty values as null.
+// Values that don't parse are kept as strings.
+fn typed_value(column: &str, value: &str) -> JsonValue {
+    if value.trim().is_empty() {
+        return JsonValue::Value(Value::Null);
+    }
+    match column_type(column) {
+        ColumnType::Date => match FecDate::parse(value.trim()) {
+            Some(date) => JsonValue::Value(Value::from(date.to_string())),
+            None => JsonValue::Value(Value::from(value)),
+        },
+        ColumnType::Float => match parse_amount(value) {
+            Some(cents) => JsonValue::Amount(cents),
+            None => JsonValue::Value(Value::from(value)),
+        },
+        ColumnType::Text => JsonValue::Value(Value::from(value)),
+    }
+}
+
+// One JSON object per line, written field by field so the keys keep the
+// order of the columns
+fn write_object<'a>(
+    out: &mut impl Write,
+    fields: impl Iterator<Item = (&'a str, JsonValue)>,
+) -> io::Result<()> {
+    out.write_all(b"{")?;
+    for (idx, (key, value)) in fields.enumerate() {
+        if idx > 0 {
+            out.write_all(b",")?;
+        }
+        serde_json::to_writer(&mut *out, key)?;
+        out.write_all(b":")?;
+        match value {
+            JsonValue::Value(value) => serde_json::to_writer(&mut *out, &value)?,
+            JsonValue::Amount(cents) => out.write_all(format_amount(cents).as_bytes())?,
+        }
+    }
+    out.write_all(b"}\n")
+}
+
+fn export_filing<R: Read>(
+    mut filing: Filing<R>,
+    out: &mut impl Write,
+    target: &CmdExportTarget,
+    covers: bool,
+    pb: &ProgressBar,
+) -> Result<(), CmdExportJsonlError> {
+    let filing_id = filing.filing_id.clone();
+    let io_error = |e| CmdExportJsonlError::Io(format!("Error writing filing {filing_id}"), e);
+    if covers {
+        let header = &filing.header;
+        write_object(
+            out,
+            [
+                ("filing_id", Value::from(filing_id.as_str())),
+                ("row_type", Value::from("HDR")),
+                ("record_type", Value::from(header.record_type.as_str())),
+                ("ef_type", Value::from(header.ef_type.as_str())),
+                ("fec_version", Value::from(header.fec_version.as_str())),
+                ("soft_name", Value::from(header.soft_name.as_str())),
+                ("soft_ver", Value::from(header.soft_ver.as_str())),
+                ("report_id", Value::from(header.report_id.clone())),
+                ("report_number", Value::from(header.report_number.clone())),
+                ("comment", Value::from(header.comment.clone())),
+            ]
+            .into_iter()
+            .map(|(key, value)| (key, JsonValue::Value(value))),
+        )
+        .map_err(io_error)?;
+        write_object(
+            out,
+            [
+                ("filing_id", Value::from(filing_id.as_str())),
+                ("row_type", Value::from(filing.cover.form_type.as_str())),
+            ]
+            .into_iter()
+            .map(|(key, value)| (key, JsonValue::Value(value)))
+            .chain(
+                filing
+                    .cover
+                    .iter_named()
+                    .map(|(column, value)| (column, typed_value(column, value))),
+            ),
+        )
+        .map_err(io_error)?;
+    }
+
+    let mut rows = TargetRows::new(target, &filing.header);
+    while let Some(r) = filing.next_row() {
+        let r = r.map_err(|e| CmdExportJsonlError::Row(filing_id.clone(), e))?;
+        if let Some(position) = r.record.position() {
+            pb.set_position(position.byte());
+        }
+        let Some(row) = rows.map(&r) else {
+            continue;
+        };
+        write_object(
+            out,
+            [
+                ("filing_id", Value::from(filing_id.as_str())),
+                ("row_type", Value::from(r.row_type.as_str())),
+            ]
+            .into_iter()
+            .map(|(key, value)| (key, JsonValue::Value(value)))
+            // fields past the columns are dropped, missing ones are null
+            .chain(row.columns.iter().enumerate().map(|(idx, column)| {
+                let value = row.values.get(idx).map_or("", |v| v.as_ref());
+                (column.as_str(), typed_value(column, value))
+            })),
+        )
+        .map_err(io_error)?;
+    }
+    Ok(())
+}
+
+// `output` is a file path, or "-" for stdout
+pub fn cmd_export_jsonl(
+    filings: Vec<String>,
+    output: &str,
+    target: CmdExportTarget,
+    covers: bool,
+) -> Result<(), Box<dyn Error>> {
+    let filing_sourcer = FilingSourcer::new();
+    let t0 = Instant::now();
+    let stdout = output == "-";
+    let mut out: BufWriter<Box<dyn Write>> = BufWriter::new(if stdout {
+        Box::new(io::stdout().lock())
+    } else {
+        Box::new(
+            File::create(output)
+                .map_err(|e| CmdExportJsonlError::Io(format!("Error creating {output}"), e))?,
+        )
+    });
+    let mb = MultiProgress::new();
+    let pb_files = if filings.len() > 1 {
+        let pb_files = mb.add(ProgressBar::new(filings.len() as u64));
+        pb_files.set_style(BAR_FILES_STYLE.clone());
+        pb_files.enable_steady_tick(Duration::from_millis(100));
+        Some(pb_files)
+    } else {
+        None
+    };
+
+    let mut result = Ok(());
+    for filing in &filings {
+        let filing = filing_sourcer.resolve(filing);
+        let pb_file = mb.add(ProgressBar::new(
+            filing.source_length.unwrap_or_default() as u64
+        ));
+        pb_file.set_style(BAR_FILE_STYLE.clone());
+        pb_file.set_message(format!("FEC-{}", filing.filing_id));
+        result = export_filing(filing, &mut out, &target, covers, &pb_file);
+        pb_file.finish_and_clear();
+        if result.is_err() {
+            break;
+        }
+        if let Some(pb_files) = &pb_files {
+            pb_files.inc(1);
+        }
+    }
+    if result.is_ok() {
+        result = out
+            .flush()
+            .map_err(|e| CmdExportJsonlError::Io(format!("Error writing {output}"), e));
+    }
+    if let Some(pb_files) = &pb_files {
+        pb_files.finish_and_clear();
+    }
+    match result {
+        // the reader went away, ex `| head`, which isn't an error
+        Err(CmdExportJsonlError::Io(_, e)) if e.kind() == io::ErrorKind::BrokenPipe => {
+            return Ok(())
+        }
+        result => result?,
+    }
+
+    // stdout is the data itself
+    if !stdout {
+        println!(
+            "Finished {} files in {}",
+            filings.len(),
+            HumanDuration(Instant::now() - t0)
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::filing;
+    use std::io::Cursor;
+
+    fn export(covers: bool) -> Vec<Value> {
+        let filing = filing(
+            "1",
+            "8.4",
+            "",
+            &[vec![
+                ("form_type", "SA11AI"),
+                ("transaction_id", "SA.1"),
+                ("contributor_last_name", "Doe"),
+                ("contribution_date", "20240115"),
+                ("contribution_amount", "999999999999.99"),
+                ("contribution_aggregate", "1,000"),
+            ]],
+        );
+        export_rows(filing, covers)
+    }
+
+    fn export_rows(filing: Filing<Cursor<Vec<u8>>>, covers: bool) -> Vec<Value> {
+        let mut out = vec![];
+        export_filing(
+            filing,
+            &mut out,
+            &CmdExportTarget::ByFormType,
+            covers,
+            &ProgressBar::hidden(),
+        )
+        .unwrap();
+        String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn typed_values() {
+        let mut out = vec![];
+        write_object(
+            &mut out,
+            [
+                (
+                    "contribution_amount",
+                    typed_value("contribution_amount", "0.1"),
+                ),
+                (
+                    "expenditure_amount",
+                    typed_value("expenditure_amount", "-1234.5"),
+                ),
+                (
+                    "contribution_date",
+                    typed_value("contribution_date", "20240115"),
+                ),
+                ("expenditure_date", typed_value("expenditure_date", "2024")),
+                ("memo_code", typed_value("memo_code", " ")),
+            ]
+            .into_iter(),
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "{\"contribution_amount\":0.10,\"expenditure_amount\":-1234.50,\
+             \"contribution_date\":\"2024-01-15\",\"expenditure_date\":\"2024\",\
+             \"memo_code\":null}\n"
+        );
+
+        let rows = export(false);
+        assert_eq!(rows.len(), 1);
+        let row = &rows[0];
+        assert_eq!(row["filing_id"], "1");
+        assert_eq!(row["row_type"], "SA11AI");
+        assert_eq!(row["contributor_last_name"], "Doe");
+        assert_eq!(row["contribution_date"], "2024-01-15");
+        assert_eq!(row["contribution_amount"].as_f64(), Some(999999999999.99));
+        assert_eq!(row["contribution_aggregate"], "1,000");
+        assert_eq!(row["memo_code"], Value::Null);
+    }
+
+    #[test]
+    fn covers() {
+        let rows = export(true);
+        let row_types: Vec<_> = rows.iter().map(|row| row["row_type"].clone()).collect();
+        assert_eq!(row_types, vec!["HDR", "F3XA", "SA11AI"]);
+        assert_eq!(rows[0]["fec_version"], "8.4");
+        assert_eq!(rows[1]["filing_id"], "1");
+        assert_eq!(rows[1]["committee_name"], "Test Committee");
+    }
+
+    #[test]
+    fn short_rows() {
+        // filers often leave off the empty fields at the end of a row
+        let filing = Filing::from_reader(
+            Cursor::new(
+                "HDR\x1cFEC\x1c8.4\x1clibfec\x1c0.1\x1c\x1c1\x1c\n\
+                 F3XA\x1cC00000001\x1cTest Committee\n\
+                 SA11AI\x1cC00000001\x1cSA.1\n"
+                    .as_bytes()
+                    .to_vec(),
+            ),
+            "1".to_owned(),
+            None,
+        )
+        .unwrap();
+        let rows = export_rows(filing, false);
+        let row = rows[0].as_object().unwrap();
+        assert_eq!(row["transaction_id"], "SA.1");
+        assert!(row.contains_key("memo_code"));
+        assert_eq!(row["memo_code"], Value::Null);
+        assert_eq!(
+            row.len(),
+            2 + fec_parser::mappings::column_names_for_field("SA11AI", "8.4")
+                .unwrap()
+                .len()
+        );
+    }
+}
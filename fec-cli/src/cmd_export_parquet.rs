@@ -0,0 +1,467 @@
+use arrow_array::RecordBatch;
+use fec_parser::{arrow::RecordBatchBuilder, Filing, FilingRowReadError};
+use indicatif::{HumanDuration, MultiProgress, ProgressBar};
+use parquet::{
+    arrow::ArrowWriter,
+    basic::{Compression, GzipLevel, ZstdLevel},
+    errors::ParquetError,
+    file::properties::WriterProperties,
+};
+use std::{
+    collections::HashMap,
+    error::Error,
+    fs::{self, File},
+    io::Read,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use thiserror::Error;
+
+use crate::{
+    cmd_export::{CmdExportTarget, TargetRows, BAR_FILES_STYLE, BAR_FILE_STYLE},
+    sourcer::FilingSourcer,
+};
+
+// Hive's name for partitions without a value, which most readers understand
+const NULL_PARTITION: &str = "__HIVE_DEFAULT_PARTITION__";
+
+#[derive(Error, Debug)]
+pub enum CmdExportParquetError {
+    #[error("`{0}`: {1}")]
+    Io(String, #[source] std::io::Error),
+    #[error("`{0}`: {1}")]
+    Parquet(String, #[source] ParquetError),
+    #[error("Error reading filing {0}: {1}")]
+    Row(String, #[source] FilingRowReadError),
+}
+
+pub enum ParquetPartition {
+    None,
+    // filing_id=1813847/
+    FilingId,
+    // cycle=2024/, from the end of the filing's coverage
+    Cycle,
+}
+
+pub struct ParquetOptions {
+    pub compression: Compression,
+    pub row_group_size: usize,
+    pub partition: ParquetPartition,
+}
+
+// "none", "snappy", or "zstd" and "gzip" at their default level or with an
+// explicit one like "zstd(9)"
+pub fn parse_compression(value: &str) -> Result<Compression, ParquetError> {
+    match value {
+        "none" => Ok(Compression::UNCOMPRESSED),
+        "zstd" => Ok(Compression::ZSTD(ZstdLevel::default())),
+        "gzip" => Ok(Compression::GZIP(GzipLevel::default())),
+        value => Compression::from_str(value),
+    }
+}
+
+// the directory of a filing's files, relative to the output directory
+fn partition_dir<R: Read>(partition: &ParquetPartition, filing: &Filing<R>) -> Option<String> {
+    match partition {
+        ParquetPartition::None => None,
+        ParquetPartition::FilingId => Some(format!("filing_id={}", filing.filing_id)),
+        ParquetPartition::Cycle => Some(format!(
+            "cycle={}",
+            election_cycle(filing).map_or(NULL_PARTITION.to_owned(), |c| c.to_string())
+        )),
+    }
+}
+
+// the two-year election cycle the filing's coverage ends in, ex a report
+// through 2023-06-30 is in the 2024 cycle
+fn election_cycle<R: Read>(filing: &Filing<R>) -> Option<u32> {
+    let date = filing
+        .cover
+        .get_date("coverage_through_date")
+        .or_else(|| filing.cover.get_date("coverage_from_date"))?;
+    Some(date.year + date.year % 2)
+}
+
+struct ParquetFile {
+    builder: RecordBatchBuilder,
+    writer: ArrowWriter<File>,
+    // the filing_id column is left out of files partitioned by filing_id
+    projection: Option<Vec<usize>>,
+    rows: usize,
+}
+
+impl ParquetFile {
+    fn write(&mut self, batch: Option<RecordBatch>) -> Result<(), ParquetError> {
+        let Some(batch) = batch else {
+            return Ok(());
+        };
+        self.rows += batch.num_rows();
+        match &self.projection {
+            Some(projection) => self.writer.write(&batch.project(projection)?),
+            None => self.writer.write(&batch),
+        }
+    }
+
+    fn close(mut self) -> Result<usize, ParquetError> {
+        let batch = self.builder.finish()?;
+        self.write(batch)?;
+        self.writer.close()?;
+        Ok(self.rows)
+    }
+}
+
+// The open files of an export, keyed by path. Rows are written out a row
+// group at a time, so only the current row group of each file is in memory.
+struct ParquetFiles {
+    out_dir: PathBuf,
+    properties: WriterProperties,
+    without_filing_id: bool,
+    files: HashMap<PathBuf, ParquetFile>,
+    written: Vec<(PathBuf, usize)>,
+}
+
+impl ParquetFiles {
+    fn new(out_dir: &str, options: &ParquetOptions) -> Self {
+        Self {
+            out_dir: PathBuf::from(out_dir),
+            properties: WriterProperties::builder()
+                .set_compression(options.compression)
+                .set_max_row_group_size(options.row_group_size.max(1))
+                .build(),
+            without_filing_id: matches!(options.partition, ParquetPartition::FilingId),
+            files: HashMap::new(),
+            written: vec![],
+        }
+    }
+
+    fn path(&self, partition: Option<&str>, name: &str) -> PathBuf {
+        let mut path = self.out_dir.clone();
+        if let Some(partition) = partition {
+            path.push(partition);
+        }
+        path.push(format!("{name}.parquet"));
+        path
+    }
+
+    fn open(
+        &mut self,
+        path: &Path,
+        columns: &'static [String],
+    ) -> Result<ParquetFile, CmdExportParquetError> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| {
+                CmdExportParquetError::Io(format!("Error creating {}", dir.display()), e)
+            })?;
+        }
+        let file = File::create(path).map_err(|e| {
+            CmdExportParquetError::Io(format!("Error creating {}", path.display()), e)
+        })?;
+        let builder = RecordBatchBuilder::new(columns);
+        let schema = builder.schema();
+        let projection = self
+            .without_filing_id
+            .then(|| (1..schema.fields().len()).collect::<Vec<_>>());
+        let schema = match &projection {
+            Some(projection) => Arc::new(schema.project(projection).unwrap()),
+            None => schema,
+        };
+        let writer =
+            ArrowWriter::try_new(file, schema, Some(self.properties.clone())).map_err(|e| {
+                CmdExportParquetError::Parquet(format!("Error writing {}", path.display()), e)
+            })?;
+        Ok(ParquetFile {
+            builder,
+            writer,
+            projection,
+            rows: 0,
+        })
+    }
+
+    // `name` has a version suffix for rows with other columns than the
+    // latest version, see `TargetRows::versioned_table`
+    fn push<'a>(
+        &mut self,
+        partition: Option<&str>,
+        name: &str,
+        columns: &'static [String],
+        filing_id: &str,
+        values: impl IntoIterator<Item = &'a str>,
+    ) -> Result<(), CmdExportParquetError> {
+        let path = self.path(partition, name);
+        if !self.files.contains_key(&path) {
+            let file = self.open(&path, columns)?;
+            self.files.insert(path.clone(), file);
+        }
+        let file = self.files.get_mut(&path).unwrap();
+        let result = file
+            .builder
+            .push_values(filing_id, values)
+            .map_err(ParquetError::from)
+            .and_then(|batch| file.write(batch));
+        result.map_err(|e| {
+            CmdExportParquetError::Parquet(format!("Error writing {}", path.display()), e)
+        })
+    }
+
+    fn close(&mut self) -> Result<(), CmdExportParquetError> {
+        for (path, file) in self.files.drain() {
+            let rows = file.close().map_err(|e| {
+                CmdExportParquetError::Parquet(format!("Error writing {}", path.display()), e)
+            })?;
+            self.written.push((path, rows));
+        }
+        Ok(())
+    }
+}
+
+fn export_filing<R: Read>(
+    mut filing: Filing<R>,
+    files: &mut ParquetFiles,
+    partition: Option<&str>,
+    target: &CmdExportTarget,
+    pb: &ProgressBar,
+) -> Result<(), CmdExportParquetError> {
+    let filing_id = filing.filing_id.clone();
+    let mut rows = TargetRows::new(target, &filing.header);
+    while let Some(r) = filing.next_row() {
+        let r = r.map_err(|e| CmdExportParquetError::Row(filing_id.clone(), e))?;
+        if let Some(position) = r.record.position() {
+            pb.set_position(position.byte());
+        }
+        let Some(row) = rows.map(&r) else {
+            continue;
+        };
+        files.push(
+            partition,
+            &rows.versioned_table(&row),
+            row.columns,
+            &filing_id,
+            row.values.iter().map(|value| value.as_ref()),
+        )?;
+    }
+    Ok(())
+}
+
+pub fn cmd_export_parquet(
+    filings: Vec<String>,
+    out_dir: &str,
+    target: CmdExportTarget,
+    options: ParquetOptions,
+) -> Result<(), Box<dyn Error>> {
+    let filing_sourcer = FilingSourcer::new();
+    let t0 = Instant::now();
+    let mut files = ParquetFiles::new(out_dir, &options);
+    let mb = MultiProgress::new();
+    let pb_files = if filings.len() > 1 {
+        let pb_files = mb.add(ProgressBar::new(filings.len() as u64));
+        pb_files.set_style(BAR_FILES_STYLE.clone());
+        pb_files.enable_steady_tick(Duration::from_millis(100));
+        Some(pb_files)
+    } else {
+        None
+    };
+
+    for filing in &filings {
+        let filing = filing_sourcer.resolve(filing);
+        let pb_file = mb.add(ProgressBar::new(
+            filing.source_length.unwrap_or_default() as u64
+        ));
+        pb_file.set_style(BAR_FILE_STYLE.clone());
+        pb_file.set_message(format!("FEC-{}", filing.filing_id));
+        let partition = partition_dir(&options.partition, &filing);
+        export_filing(filing, &mut files, partition.as_deref(), &target, &pb_file)?;
+        // a filing's partition is never written to again
+        if matches!(options.partition, ParquetPartition::FilingId) {
+            files.close()?;
+        }
+        pb_file.finish_and_clear();
+        if let Some(pb_files) = &pb_files {
+            pb_files.inc(1);
+        }
+    }
+    files.close()?;
+    if let Some(pb_files) = &pb_files {
+        pb_files.finish_and_clear();
+    }
+
+    let rows: usize = files.written.iter().map(|(_, rows)| rows).sum();
+    println!(
+        "Wrote {rows} rows to {} Parquet files in {}",
+        files.written.len(),
+        files.out_dir.display()
+    );
+    println!(
+        "Finished {} files in {}",
+        filings.len(),
+        HumanDuration(Instant::now() - t0)
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::filing_with_cover;
+    use arrow_array::{
+        cast::AsArray,
+        types::{Date32Type, Decimal128Type},
+        Array,
+    };
+    use fec_parser::FecDate;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReader;
+    use std::io::Cursor;
+
+    fn sa<'a>(transaction_id: &'a str, date: &'a str, amount: &'a str) -> Vec<(&'a str, &'a str)> {
+        vec![
+            ("form_type", "SA11AI"),
+            ("transaction_id", transaction_id),
+            ("contribution_date", date),
+            ("contribution_amount", amount),
+        ]
+    }
+
+    // an 8.4 filing in the 2024 cycle, and a 6.4 one in the 2022 cycle
+    fn filings() -> Vec<Filing<Cursor<Vec<u8>>>> {
+        vec![
+            filing_with_cover(
+                "1",
+                "8.4",
+                "",
+                &[("coverage_through_date", "20240331")],
+                &[sa("SA.1", "20240115", "100.5"), sa("SA.2", "", "20")],
+            ),
+            filing_with_cover(
+                "2",
+                "6.4",
+                "",
+                &[("coverage_through_date", "20210630")],
+                &[sa("SA.3", "20210601", "30")],
+            ),
+        ]
+    }
+
+    fn export(
+        out_dir: &Path,
+        partition: ParquetPartition,
+        filings: Vec<Filing<Cursor<Vec<u8>>>>,
+    ) -> Vec<(String, usize)> {
+        let options = ParquetOptions {
+            compression: Compression::UNCOMPRESSED,
+            row_group_size: 1024,
+            partition,
+        };
+        let mut files = ParquetFiles::new(out_dir.to_str().unwrap(), &options);
+        for filing in filings {
+            let partition = partition_dir(&options.partition, &filing);
+            export_filing(
+                filing,
+                &mut files,
+                partition.as_deref(),
+                &CmdExportTarget::ByFormType,
+                &ProgressBar::hidden(),
+            )
+            .unwrap();
+        }
+        files.close().unwrap();
+        let mut written: Vec<_> = files
+            .written
+            .into_iter()
+            .map(|(path, rows)| {
+                let path = path.strip_prefix(out_dir).unwrap();
+                (path.to_string_lossy().into_owned(), rows)
+            })
+            .collect();
+        written.sort();
+        written
+    }
+
+    fn read(path: &Path) -> RecordBatch {
+        let reader = ParquetRecordBatchReader::try_new(File::open(path).unwrap(), 1024).unwrap();
+        let mut batches: Vec<_> = reader.map(|batch| batch.unwrap()).collect();
+        assert_eq!(batches.len(), 1);
+        batches.remove(0)
+    }
+
+    #[test]
+    fn splits_versions() {
+        let out_dir = tempfile::tempdir().unwrap();
+        assert_eq!(
+            export(out_dir.path(), ParquetPartition::None, filings()),
+            vec![
+                ("SA11AI.parquet".to_owned(), 2),
+                ("SA11AI_v6_4.parquet".to_owned(), 1)
+            ]
+        );
+
+        let batch = read(&out_dir.path().join("SA11AI.parquet"));
+        let filing_ids = batch
+            .column_by_name("filing_id")
+            .unwrap()
+            .as_string::<i32>();
+        assert_eq!(filing_ids.value(0), "1");
+        let dates = batch
+            .column_by_name("contribution_date")
+            .unwrap()
+            .as_primitive::<Date32Type>();
+        assert_eq!(
+            dates.value(0),
+            FecDate::parse("20240115").unwrap().days_since_epoch()
+        );
+        assert!(dates.is_null(1));
+        let amounts = batch
+            .column_by_name("contribution_amount")
+            .unwrap()
+            .as_primitive::<Decimal128Type>();
+        assert_eq!((amounts.precision(), amounts.scale()), (14, 2));
+        assert_eq!(amounts.values(), &[10050, 2000]);
+
+        // 6.4 has a contribution_purpose_code column that 8.4 doesn't
+        let batch = read(&out_dir.path().join("SA11AI_v6_4.parquet"));
+        assert!(batch.column_by_name("contribution_purpose_code").is_some());
+        let transaction_ids = batch
+            .column_by_name("transaction_id")
+            .unwrap()
+            .as_string::<i32>();
+        assert_eq!(transaction_ids.value(0), "SA.3");
+
+        // the same files whichever version comes first
+        let out_dir = tempfile::tempdir().unwrap();
+        let mut reversed = filings();
+        reversed.reverse();
+        assert_eq!(
+            export(out_dir.path(), ParquetPartition::None, reversed),
+            vec![
+                ("SA11AI.parquet".to_owned(), 2),
+                ("SA11AI_v6_4.parquet".to_owned(), 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn partitions() {
+        let out_dir = tempfile::tempdir().unwrap();
+        assert_eq!(
+            export(out_dir.path(), ParquetPartition::Cycle, filings()),
+            vec![
+                ("cycle=2022/SA11AI_v6_4.parquet".to_owned(), 1),
+                ("cycle=2024/SA11AI.parquet".to_owned(), 2)
+            ]
+        );
+        let batch = read(&out_dir.path().join("cycle=2022/SA11AI_v6_4.parquet"));
+        assert_eq!(batch.num_rows(), 1);
+        assert!(batch.column_by_name("filing_id").is_some());
+
+        let out_dir = tempfile::tempdir().unwrap();
+        assert_eq!(
+            export(out_dir.path(), ParquetPartition::FilingId, filings()),
+            vec![
+                ("filing_id=1/SA11AI.parquet".to_owned(), 2),
+                ("filing_id=2/SA11AI_v6_4.parquet".to_owned(), 1)
+            ]
+        );
+        let batch = read(&out_dir.path().join("filing_id=2/SA11AI_v6_4.parquet"));
+        assert!(batch.column_by_name("filing_id").is_none());
+    }
+}
@@ -0,0 +1,524 @@
+use fec_parser::{
+    amendments::AmendmentChains,
+    format_amount,
+    mappings::{column_type, ColumnType},
+    parse_amount, FecDate, Filing, FilingRowReadError, AMOUNT_PRECISION, AMOUNT_SCALE,
+};
+use indicatif::{HumanDuration, MultiProgress, ProgressBar};
+use native_tls::TlsConnector;
+use postgres::{Client, Transaction};
+use postgres_native_tls::MakeTlsConnector;
+use std::{
+    collections::HashMap,
+    error::Error,
+    io::{Read, Write},
+    time::{Duration, Instant},
+};
+use thiserror::Error;
+
+use crate::{
+    cmd_export::{quote_ident, CmdExportTarget, TargetRows, BAR_FILES_STYLE, BAR_FILE_STYLE},
+    sourcer::FilingSourcer,
+};
+
+// rows are sent to COPY in chunks of about this many bytes
+const COPY_BUFFER_SIZE: usize = 8 * 1024 * 1024;
+
+#[derive(Error, Debug)]
+pub enum CmdExportPostgresError {
+    #[error("`{0}`: {1}")]
+    Postgres(String, #[source] postgres::Error),
+    #[error("`{0}`: {1}")]
+    Io(String, #[source] std::io::Error),
+    #[error("Error reading filing {0}: {1}")]
+    Row(String, #[source] FilingRowReadError),
+    #[error("Error setting up TLS for Postgres: {0}")]
+    Tls(#[source] native_tls::Error),
+}
+
+// TLS follows the URL's sslmode: the default "prefer" uses it when the server
+// supports it, "require" refuses to connect without it, and "disable" never
+// uses it. Certificates are verified against the system's trusted roots.
+fn connect(url: &str) -> Result<Client, CmdExportPostgresError> {
+    let connector = TlsConnector::new().map_err(CmdExportPostgresError::Tls)?;
+    Client::connect(url, MakeTlsConnector::new(connector))
+        .map_err(|e| CmdExportPostgresError::Postgres("Error connecting to Postgres".to_owned(), e))
+}
+
+// Postgres folds unquoted names to lowercase, so tables are created that
+// way to be queryable without quotes, ex libfec_sa11ai
+fn table_name(schema: &str, table: &str) -> String {
+    format!(
+        "{}.{}",
+        quote_ident(schema),
+        quote_ident(&format!("libfec_{}", table.to_lowercase()))
+    )
+}
+
+fn postgres_type(column: &str) -> String {
+    match column_type(column) {
+        ColumnType::Date => "date".to_owned(),
+        ColumnType::Float => format!("numeric({AMOUNT_PRECISION}, {AMOUNT_SCALE})"),
+        ColumnType::Text => "text".to_owned(),
+    }
+}
+
+// A value in COPY's text format, where empty and invalid values are \N
+fn write_copy_value(buffer: &mut String, column: &str, value: Option<&str>) {
+    let Some(value) = value.filter(|v| !v.trim().is_empty()) else {
+        buffer.push_str("\\N");
+        return;
+    };
+    match column_type(column) {
+        ColumnType::Date => match FecDate::parse(value.trim()) {
+            Some(date) => buffer.push_str(&date.to_string()),
+            None => buffer.push_str("\\N"),
+        },
+        ColumnType::Float => match parse_amount(value) {
+            Some(cents) => buffer.push_str(&format_amount(cents)),
+            None => buffer.push_str("\\N"),
+        },
+        ColumnType::Text => {
+            for c in value.chars() {
+                match c {
+                    '\\' => buffer.push_str("\\\\"),
+                    '\t' => buffer.push_str("\\t"),
+                    '\n' => buffer.push_str("\\n"),
+                    '\r' => buffer.push_str("\\r"),
+                    c => buffer.push(c),
+                }
+            }
+        }
+    }
+}
+
+fn create_filings_sql(schema: &str) -> String {
+    format!(
+        "CREATE TABLE IF NOT EXISTS {}(
+          filing_id text PRIMARY KEY,
+          fec_version text NOT NULL,
+          software_name text NOT NULL,
+          software_version text NOT NULL,
+          report_id text,
+          report_number text,
+          comment text,
+          cover_record_form_type text NOT NULL,
+          filer_id text NOT NULL,
+          filer_name text NOT NULL,
+          report_code text,
+          coverage_from_date date,
+          coverage_through_date date,
+          superseded_by text
+        )",
+        table_name(schema, "filings")
+    )
+}
+
+// The rows of one table with the same columns, sent with a single COPY
+// once the table changes or the buffer is full
+struct CopyBuffer {
+    table: String,
+    columns: &'static [String],
+    buffer: String,
+}
+
+impl CopyBuffer {
+    fn flush(&mut self, tx: &mut Transaction) -> Result<(), CmdExportPostgresError> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let columns: Vec<String> = std::iter::once("filing_id")
+            .chain(self.columns.iter().map(|c| c.as_str()))
+            .map(quote_ident)
+            .collect();
+        let sql = format!("COPY {} ({}) FROM STDIN", self.table, columns.join(", "));
+        let context = || format!("Error copying rows into {}", self.table);
+        let mut writer = tx
+            .copy_in(&sql)
+            .map_err(|e| CmdExportPostgresError::Postgres(context(), e))?;
+        writer
+            .write_all(self.buffer.as_bytes())
+            .map_err(|e| CmdExportPostgresError::Io(context(), e))?;
+        writer
+            .finish()
+            .map_err(|e| CmdExportPostgresError::Postgres(context(), e))?;
+        self.buffer.clear();
+        Ok(())
+    }
+}
+
+struct PostgresExport {
+    schema: String,
+    // the columns each table was last created or altered with
+    tables: HashMap<String, &'static [String]>,
+}
+
+impl PostgresExport {
+    // Creates the table, and adds any columns that a table from an earlier
+    // run or another version doesn't have yet. The filing_id index keeps
+    // replacing a filing's rows fast on large tables.
+    fn ensure_table(
+        &mut self,
+        tx: &mut Transaction,
+        name: &str,
+        columns: &'static [String],
+    ) -> Result<(), postgres::Error> {
+        let table = table_name(&self.schema, name);
+        if self.tables.get(&table) == Some(&columns) {
+            return Ok(());
+        }
+        let definitions: Vec<String> = columns
+            .iter()
+            .map(|column| format!("{} {}", quote_ident(column), postgres_type(column)))
+            .collect();
+        tx.batch_execute(&format!(
+            "CREATE TABLE IF NOT EXISTS {table}(\n  filing_id text NOT NULL,\n  {}\n)",
+            definitions.join(",\n  ")
+        ))?;
+        for definition in definitions {
+            tx.batch_execute(&format!(
+                "ALTER TABLE {table} ADD COLUMN IF NOT EXISTS {definition}"
+            ))?;
+        }
+        tx.batch_execute(&format!(
+            "CREATE INDEX IF NOT EXISTS {} ON {table}(filing_id)",
+            quote_ident(&format!("libfec_{}_filing_id", name.to_lowercase()))
+        ))?;
+        self.tables.insert(table, columns);
+        Ok(())
+    }
+
+    // Re-running an export replaces the rows of filings that were exported
+    // before, in every libfec table of the schema
+    fn delete_filing(&self, tx: &mut Transaction, filing_id: &str) -> Result<(), postgres::Error> {
+        let tables: Vec<String> = tx
+            .query(
+                "SELECT table_name FROM information_schema.columns
+                  WHERE table_schema = $1
+                    AND table_name LIKE 'libfec\\_%'
+                    AND column_name = 'filing_id'",
+                &[&self.schema],
+            )?
+            .iter()
+            .map(|row| row.get(0))
+            .collect();
+        for table in tables {
+            tx.execute(
+                &format!(
+                    "DELETE FROM {}.{} WHERE filing_id = $1",
+                    quote_ident(&self.schema),
+                    quote_ident(&table)
+                ),
+                &[&filing_id],
+            )?;
+        }
+        Ok(())
+    }
+
+    fn export_filing<R: Read>(
+        &mut self,
+        mut filing: Filing<R>,
+        tx: &mut Transaction,
+        target: &CmdExportTarget,
+        pb: &ProgressBar,
+    ) -> Result<(), CmdExportPostgresError> {
+        let filing_id = filing.filing_id.clone();
+        let sql_error =
+            |e| CmdExportPostgresError::Postgres(format!("Error inserting filing {filing_id}"), e);
+        self.delete_filing(tx, &filing_id).map_err(sql_error)?;
+        let date = |value: &Option<String>| {
+            value
+                .as_deref()
+                .and_then(FecDate::parse)
+                .map(|date| date.to_string())
+        };
+        tx.execute(
+            &format!(
+                "INSERT INTO {} VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12::text::date, $13::text::date, NULL)",
+                table_name(&self.schema, "filings")
+            ),
+            &[
+                &filing.filing_id,
+                &filing.header.fec_version,
+                &filing.header.soft_name,
+                &filing.header.soft_ver,
+                &filing.header.report_id,
+                &filing.header.report_number,
+                &filing.header.comment,
+                &filing.cover.form_type,
+                &filing.cover.filer_id,
+                &filing.cover.filer_name,
+                &filing.cover.report_code,
+                &date(&filing.cover.coverage_from_date),
+                &date(&filing.cover.coverage_through_date),
+            ],
+        )
+        .map_err(sql_error)?;
+
+        let mut rows = TargetRows::new(target, &filing.header);
+        let mut copy: Option<CopyBuffer> = None;
+        while let Some(r) = filing.next_row() {
+            let r = r.map_err(|e| CmdExportPostgresError::Row(filing_id.clone(), e))?;
+            if let Some(position) = r.record.position() {
+                pb.set_position(position.byte());
+            }
+            let Some(row) = rows.map(&r) else {
+                continue;
+            };
+            let table = table_name(&self.schema, row.table);
+            let switch = copy
+                .as_ref()
+                .is_none_or(|copy| copy.table != table || copy.columns != row.columns);
+            if switch {
+                if let Some(mut copy) = copy.take() {
+                    copy.flush(tx)?;
+                }
+                self.ensure_table(tx, row.table, row.columns)
+                    .map_err(sql_error)?;
+                copy = Some(CopyBuffer {
+                    table,
+                    columns: row.columns,
+                    buffer: String::new(),
+                });
+            }
+            let copy = copy.as_mut().unwrap();
+            write_copy_value(&mut copy.buffer, "filing_id", Some(&filing_id));
+            // fields past the columns are dropped, missing ones are null
+            for (idx, column) in row.columns.iter().enumerate() {
+                copy.buffer.push('\t');
+                write_copy_value(
+                    &mut copy.buffer,
+                    column,
+                    row.values.get(idx).map(|v| v.as_ref()),
+                );
+            }
+            copy.buffer.push('\n');
+            if copy.buffer.len() >= COPY_BUFFER_SIZE {
+                copy.flush(tx)?;
+            }
+        }
+        if let Some(mut copy) = copy {
+            copy.flush(tx)?;
+        }
+        Ok(())
+    }
+
+    // Points every filing in libfec_filings at the latest amendment of its
+    // report, like the SQLite export
+    fn mark_superseded(&self, client: &mut Client) -> Result<(), postgres::Error> {
+        let filings = table_name(&self.schema, "filings");
+        let mut chains = AmendmentChains::new();
+        for row in client.query(
+            &format!("SELECT filing_id, report_id, report_number FROM {filings}"),
+            &[],
+        )? {
+            let filing_id: String = row.get(0);
+            let report_id: Option<String> = row.get(1);
+            let report_number: Option<String> = row.get(2);
+            chains.insert(&filing_id, report_id.as_deref(), report_number.as_deref());
+        }
+        let mut tx = client.transaction()?;
+        let statement = tx.prepare(&format!(
+            "UPDATE {filings} SET superseded_by = $1 WHERE filing_id = $2"
+        ))?;
+        for chain in chains.chains() {
+            let latest = chain.last().copied();
+            for filing_id in chain {
+                let superseded_by = latest.filter(|latest| *latest != filing_id);
+                tx.execute(&statement, &[&superseded_by, &filing_id])?;
+            }
+        }
+        tx.commit()
+    }
+}
+
+pub fn cmd_export_postgres(
+    filings: Vec<String>,
+    url: &str,
+    schema: &str,
+    target: CmdExportTarget,
+) -> Result<(), Box<dyn Error>> {
+    let filing_sourcer = FilingSourcer::new();
+    let t0 = Instant::now();
+    let mut client = connect(url)?;
+    let mut export = PostgresExport {
+        schema: schema.to_owned(),
+        tables: HashMap::new(),
+    };
+    client
+        .batch_execute(&format!(
+            "CREATE SCHEMA IF NOT EXISTS {};\n{}",
+            quote_ident(schema),
+            create_filings_sql(schema)
+        ))
+        .map_err(|e| {
+            CmdExportPostgresError::Postgres(format!("Error creating tables in {schema}"), e)
+        })?;
+
+    let mb = MultiProgress::new();
+    let pb_files = if filings.len() > 1 {
+        let pb_files = mb.add(ProgressBar::new(filings.len() as u64));
+        pb_files.set_style(BAR_FILES_STYLE.clone());
+        pb_files.enable_steady_tick(Duration::from_millis(100));
+        Some(pb_files)
+    } else {
+        None
+    };
+
+    for filing in &filings {
+        let filing = filing_sourcer.resolve(filing);
+        let pb_file = mb.add(ProgressBar::new(
+            filing.source_length.unwrap_or_default() as u64
+        ));
+        pb_file.set_style(BAR_FILE_STYLE.clone());
+        pb_file.set_message(format!("FEC-{}", filing.filing_id));
+        // each filing is exported in its own transaction, so a failed
+        // export never leaves a partial filing behind
+        let filing_id = filing.filing_id.clone();
+        let sql_error =
+            |e| CmdExportPostgresError::Postgres(format!("Error inserting filing {filing_id}"), e);
+        let mut tx = client.transaction().map_err(sql_error)?;
+        export.export_filing(filing, &mut tx, &target, &pb_file)?;
+        tx.commit().map_err(sql_error)?;
+        pb_file.finish_and_clear();
+        if let Some(pb_files) = &pb_files {
+            pb_files.inc(1);
+        }
+    }
+    export.mark_superseded(&mut client).map_err(|e| {
+        CmdExportPostgresError::Postgres("Error marking superseded filings".to_owned(), e)
+    })?;
+    if let Some(pb_files) = &pb_files {
+        pb_files.finish_and_clear();
+    }
+
+    println!(
+        "Finished {} files in {}",
+        filings.len(),
+        HumanDuration(Instant::now() - t0)
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::filing;
+
+    fn copy_value(column: &str, value: Option<&str>) -> String {
+        let mut buffer = String::new();
+        write_copy_value(&mut buffer, column, value);
+        buffer
+    }
+
+    #[test]
+    fn copy_values() {
+        assert_eq!(
+            copy_value("contributor_employer", Some("A\\B\tC\nD\rE")),
+            "A\\\\B\\tC\\nD\\rE"
+        );
+        assert_eq!(copy_value("contributor_employer", None), "\\N");
+        assert_eq!(copy_value("contributor_employer", Some("  ")), "\\N");
+        assert_eq!(
+            copy_value("contribution_date", Some("20240115")),
+            "2024-01-15"
+        );
+        assert_eq!(copy_value("contribution_date", Some("20240231")), "\\N");
+        assert_eq!(copy_value("contribution_amount", Some(" 100.5")), "100.50");
+        assert_eq!(copy_value("contribution_amount", Some("-0.015")), "-0.02");
+        assert_eq!(copy_value("contribution_amount", Some("1,000")), "\\N");
+        // too large for numeric(14, 2)
+        assert_eq!(
+            copy_value("contribution_amount", Some("1000000000000")),
+            "\\N"
+        );
+    }
+
+    #[test]
+    fn identifiers() {
+        assert_eq!(quote_ident("libfec"), "\"libfec\"");
+        assert_eq!(quote_ident("a\"b"), "\"a\"\"b\"");
+        assert_eq!(table_name("fec", "SA11AI"), "\"fec\".\"libfec_sa11ai\"");
+        assert_eq!(
+            postgres_type("contribution_amount"),
+            "numeric(14, 2)".to_owned()
+        );
+    }
+
+    #[test]
+    #[ignore = "needs a Postgres database in DATABASE_URL"]
+    fn reexports_replace_rows() {
+        let url = std::env::var("DATABASE_URL").unwrap();
+        let mut client = connect(&url).unwrap();
+        let schema = format!("libfec_test_{}", std::process::id());
+        client
+            .batch_execute(&format!(
+                "DROP SCHEMA IF EXISTS {0} CASCADE;\nCREATE SCHEMA {0};\n{1}",
+                quote_ident(&schema),
+                create_filings_sql(&schema)
+            ))
+            .unwrap();
+        let sa = |transaction_id, amount| {
+            vec![
+                ("form_type", "SA11AI"),
+                ("transaction_id", transaction_id),
+                ("contribution_date", "20240115"),
+                ("contribution_amount", amount),
+            ]
+        };
+
+        // the same filing twice, as separate runs of the export
+        for _ in 0..2 {
+            let mut export = PostgresExport {
+                schema: schema.clone(),
+                tables: HashMap::new(),
+            };
+            let mut tx = client.transaction().unwrap();
+            export
+                .export_filing(
+                    filing("1", "8.4", "", &[sa("SA.1", "100.5"), sa("SA.2", "")]),
+                    &mut tx,
+                    &CmdExportTarget::ByFormType,
+                    &ProgressBar::hidden(),
+                )
+                .unwrap();
+            tx.commit().unwrap();
+        }
+        let rows: Vec<(String, String, Option<String>)> = client
+            .query(
+                &format!(
+                    "SELECT filing_id, contribution_date::text, contribution_amount::text
+                       FROM {} ORDER BY transaction_id",
+                    table_name(&schema, "SA11AI")
+                ),
+                &[],
+            )
+            .unwrap()
+            .iter()
+            .map(|row| (row.get(0), row.get(1), row.get(2)))
+            .collect();
+        let filings: i64 = client
+            .query_one(
+                &format!("SELECT count(*) FROM {}", table_name(&schema, "filings")),
+                &[],
+            )
+            .unwrap()
+            .get(0);
+        client
+            .batch_execute(&format!("DROP SCHEMA {} CASCADE", quote_ident(&schema)))
+            .unwrap();
+
+        assert_eq!(filings, 1);
+        assert_eq!(
+            rows,
+            vec![
+                (
+                    "1".to_owned(),
+                    "2024-01-15".to_owned(),
+                    Some("100.50".to_owned())
+                ),
+                ("1".to_owned(), "2024-01-15".to_owned(), None),
+            ]
+        );
+    }
+}
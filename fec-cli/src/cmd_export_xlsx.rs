@@ -0,0 +1,583 @@
+use fec_parser::{
+    mappings::{column_type, ColumnType},
+    parse_amount, FecDate, Filing, FilingRowReadError,
+};
+use indicatif::{HumanDuration, MultiProgress, ProgressBar};
+use rust_xlsxwriter::{ExcelDateTime, Format, Workbook, Worksheet, XlsxError};
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    io::Read,
+    time::{Duration, Instant},
+};
+use thiserror::Error;
+
+use crate::{
+    cmd_export::{CmdExportTarget, TargetRows, BAR_FILES_STYLE, BAR_FILE_STYLE},
+    cmd_info::summary_line_label,
+    sourcer::FilingSourcer,
+};
+
+// Excel's limits, the rows include the header row
+const MAX_ROWS: u32 = 1_048_576;
+const MAX_SHEET_NAME_LENGTH: usize = 31;
+
+#[derive(Error, Debug)]
+pub enum CmdExportXlsxError {
+    #[error("`{0}`: {1}")]
+    Xlsx(String, #[source] XlsxError),
+    #[error("Error reading filing {0}: {1}")]
+    Row(String, #[source] FilingRowReadError),
+}
+
+const SUMMARY_COLUMNS: &[&str] = &[
+    "filing_id",
+    "form_type",
+    "filer_name",
+    "report_code",
+    "coverage_from_date",
+    "coverage_through_date",
+    "line",
+    "period",
+    "cycle_to_date",
+];
+
+struct Formats {
+    header: Format,
+    date: Format,
+    currency: Format,
+}
+
+// A sheet of one form type, or of the summary. Once a sheet is full, rows
+// go to a new sheet with the same columns, ex "SA11AI (2)".
+struct Sheet {
+    index: usize,
+    columns: &'static [String],
+    part: usize,
+    row: u32,
+}
+
+fn write_header(
+    worksheet: &mut Worksheet,
+    columns: &[&str],
+    formats: &Formats,
+) -> Result<(), XlsxError> {
+    for (col, column) in columns.iter().enumerate() {
+        worksheet.write_string_with_format(0, col as u16, *column, &formats.header)?;
+        worksheet.set_column_width(col as u16, column.len().clamp(10, 40) as f64)?;
+    }
+    worksheet.set_freeze_panes(1, 0)?;
+    Ok(())
+}
+
+// A name Excel accepts, with the characters it doesn't allow replaced by
+// '_' and the name cut to fit the suffix in 31 characters, ex
+// "SA11AI_v6_4 (2)"
+fn sheet_name(name: &str, suffix: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| match c {
+            '[' | ']' | ':' | '*' | '?' | '/' | '\\' => '_',
+            c => c,
+        })
+        .collect();
+    let name: String = name
+        .trim_matches('\'')
+        .chars()
+        .take(MAX_SHEET_NAME_LENGTH - suffix.chars().count())
+        .collect();
+    match name.is_empty() {
+        true => format!("_{suffix}"),
+        false => format!("{name}{suffix}"),
+    }
+}
+
+// Dates and amounts as typed cells, values that don't parse as text
+fn write_value(
+    worksheet: &mut Worksheet,
+    row: u32,
+    col: u16,
+    column: &str,
+    value: &str,
+    formats: &Formats,
+) -> Result<(), XlsxError> {
+    let trimmed = value.trim();
+    if trimmed.is_empty() {
+        return Ok(());
+    }
+    match column_type(column) {
+        ColumnType::Date => {
+            if let Some(date) = FecDate::parse(trimmed) {
+                if let Ok(date) =
+                    ExcelDateTime::from_ymd(date.year as u16, date.month as u8, date.day as u8)
+                {
+                    worksheet.write_datetime_with_format(row, col, &date, &formats.date)?;
+                    return Ok(());
+                }
+            }
+        }
+        ColumnType::Float => {
+            if let Some(cents) = parse_amount(trimmed) {
+                let amount = cents as f64 / 100.0;
+                worksheet.write_number_with_format(row, col, amount, &formats.currency)?;
+                return Ok(());
+            }
+        }
+        ColumnType::Text => {}
+    }
+    worksheet.write_string(row, col, value)?;
+    Ok(())
+}
+
+// Every sheet is in constant memory mode, where a row is written out once
+// a later row is started, so rows are only ever added at the end of a sheet.
+struct XlsxExport {
+    workbook: Workbook,
+    formats: Formats,
+    max_rows: u32,
+    // the current summary sheet
+    summary: Sheet,
+    // the lowercased name of every sheet, since Excel ignores case
+    sheet_names: HashSet<String>,
+    // the current sheet of each form type, keyed by name
+    sheets: HashMap<String, Sheet>,
+    // the last row and last column of every sheet, by index, for the
+    // autofilters added once they're complete
+    filters: Vec<(u32, u16)>,
+}
+
+impl XlsxExport {
+    fn new() -> Result<Self, XlsxError> {
+        let formats = Formats {
+            header: Format::new().set_bold(),
+            date: Format::new().set_num_format("yyyy-mm-dd"),
+            currency: Format::new().set_num_format("$#,##0.00"),
+        };
+        let mut export = Self {
+            workbook: Workbook::new(),
+            formats,
+            max_rows: MAX_ROWS,
+            summary: Sheet {
+                index: 0,
+                columns: &[],
+                part: 1,
+                row: 1,
+            },
+            sheet_names: HashSet::new(),
+            sheets: HashMap::new(),
+            filters: vec![],
+        };
+        export.add_worksheet("Summary", SUMMARY_COLUMNS, 1)?;
+        Ok(export)
+    }
+
+    // A row per line of the filing's financial summary, or a single row of
+    // the cover for filings without one
+    fn write_summary<R: Read>(&mut self, filing: &Filing<R>) -> Result<(), XlsxError> {
+        let cover = &filing.cover;
+        let lines: Vec<(String, Option<f64>, Option<f64>)> = match cover.financial_summary() {
+            Some(summary) => summary
+                .lines()
+                .into_iter()
+                .filter(|(_, amount)| amount.period.is_some() || amount.cycle_to_date.is_some())
+                .map(|(name, amount)| {
+                    (
+                        summary_line_label(name),
+                        amount.period,
+                        amount.cycle_to_date,
+                    )
+                })
+                .collect(),
+            None => vec![(String::new(), None, None)],
+        };
+        for (line, period, cycle_to_date) in lines {
+            if self.summary.row >= self.max_rows {
+                let part = self.summary.part + 1;
+                self.summary = Sheet {
+                    index: self.add_worksheet("Summary", SUMMARY_COLUMNS, part)?,
+                    columns: &[],
+                    part,
+                    row: 1,
+                };
+            }
+            let row = self.summary.row;
+            let worksheet = self.workbook.worksheet_from_index(self.summary.index)?;
+            let values = [
+                filing.filing_id.as_str(),
+                cover.form_type.as_str(),
+                cover.filer_name.as_str(),
+                cover.report_code.as_deref().unwrap_or(""),
+                cover.coverage_from_date.as_deref().unwrap_or(""),
+                cover.coverage_through_date.as_deref().unwrap_or(""),
+                &line,
+            ];
+            for (col, (column, value)) in SUMMARY_COLUMNS.iter().zip(values).enumerate() {
+                write_value(worksheet, row, col as u16, column, value, &self.formats)?;
+            }
+            for (col, amount) in [(7, period), (8, cycle_to_date)] {
+                if let Some(amount) = amount {
+                    worksheet.write_number_with_format(row, col, amount, &self.formats.currency)?;
+                }
+            }
+            self.summary.row += 1;
+            self.filters[self.summary.index].0 = row;
+        }
+        Ok(())
+    }
+
+    // A sheet with a header row, returning its index
+    fn add_worksheet(
+        &mut self,
+        name: &str,
+        header: &[&str],
+        part: usize,
+    ) -> Result<usize, XlsxError> {
+        let suffix = match part {
+            1 => String::new(),
+            part => format!(" ({part})"),
+        };
+        // names that are the same once cleaned up, ex "sa11ai" and "SA11AI",
+        // are numbered
+        let mut title = sheet_name(name, &suffix);
+        let mut n = 1;
+        while !self.sheet_names.insert(title.to_lowercase()) {
+            n += 1;
+            title = sheet_name(name, &format!("~{n}{suffix}"));
+        }
+        let worksheet = self.workbook.add_worksheet_with_constant_memory();
+        worksheet.set_name(&title)?;
+        write_header(worksheet, header, &self.formats)?;
+        self.filters.push((0, header.len() as u16 - 1));
+        Ok(self.filters.len() - 1)
+    }
+
+    // `name` is the form type's table, ex "SA11AI", or "SA11AI_v6_4" for
+    // rows with the columns of an older version
+    fn write_row(
+        &mut self,
+        name: &str,
+        columns: &'static [String],
+        filing_id: &str,
+        values: &[impl AsRef<str>],
+    ) -> Result<(), XlsxError> {
+        let full = match self.sheets.get(name) {
+            Some(sheet) => sheet.row >= self.max_rows,
+            None => true,
+        };
+        if full {
+            let part = self.sheets.get(name).map_or(1, |sheet| sheet.part + 1);
+            let header: Vec<&str> = std::iter::once("filing_id")
+                .chain(columns.iter().map(|c| c.as_str()))
+                .collect();
+            let sheet = Sheet {
+                index: self.add_worksheet(name, &header, part)?,
+                columns,
+                part,
+                row: 1,
+            };
+            self.sheets.insert(name.to_owned(), sheet);
+        }
+        let sheet = self.sheets.get_mut(name).unwrap();
+        let row = sheet.row;
+        let worksheet = self.workbook.worksheet_from_index(sheet.index)?;
+        worksheet.write_string(row, 0, filing_id)?;
+        // fields past the columns are dropped, missing ones are empty
+        for (idx, (column, value)) in sheet.columns.iter().zip(values).enumerate() {
+            write_value(
+                worksheet,
+                row,
+                idx as u16 + 1,
+                column,
+                value.as_ref(),
+                &self.formats,
+            )?;
+        }
+        sheet.row += 1;
+        self.filters[sheet.index].0 = row;
+        Ok(())
+    }
+
+    fn save(mut self, path: &str) -> Result<(), XlsxError> {
+        for (index, (last_row, last_col)) in
+            std::mem::take(&mut self.filters).into_iter().enumerate()
+        {
+            self.workbook
+                .worksheet_from_index(index)?
+                .autofilter(0, 0, last_row, last_col)?;
+        }
+        self.workbook.save(path)
+    }
+}
+
+fn export_filing<R: Read>(
+    mut filing: Filing<R>,
+    export: &mut XlsxExport,
+    target: &CmdExportTarget,
+    pb: &ProgressBar,
+) -> Result<(), CmdExportXlsxError> {
+    let filing_id = filing.filing_id.clone();
+    let xlsx_error = |e| CmdExportXlsxError::Xlsx(format!("Error writing filing {filing_id}"), e);
+    export.write_summary(&filing).map_err(xlsx_error)?;
+    let mut rows = TargetRows::new(target, &filing.header);
+    while let Some(r) = filing.next_row() {
+        let r = r.map_err(|e| CmdExportXlsxError::Row(filing_id.clone(), e))?;
+        if let Some(position) = r.record.position() {
+            pb.set_position(position.byte());
+        }
+        let Some(row) = rows.map(&r) else {
+            continue;
+        };
+        export
+            .write_row(
+                &rows.versioned_table(&row),
+                row.columns,
+                &filing_id,
+                &row.values,
+            )
+            .map_err(xlsx_error)?;
+    }
+    Ok(())
+}
+
+pub fn cmd_export_xlsx(
+    filings: Vec<String>,
+    output: &str,
+    target: CmdExportTarget,
+) -> Result<(), Box<dyn Error>> {
+    let filing_sourcer = FilingSourcer::new();
+    let t0 = Instant::now();
+    let xlsx_error = |e| CmdExportXlsxError::Xlsx(format!("Error writing {output}"), e);
+    let mut export = XlsxExport::new().map_err(xlsx_error)?;
+    let mb = MultiProgress::new();
+    let pb_files = if filings.len() > 1 {
+        let pb_files = mb.add(ProgressBar::new(filings.len() as u64));
+        pb_files.set_style(BAR_FILES_STYLE.clone());
+        pb_files.enable_steady_tick(Duration::from_millis(100));
+        Some(pb_files)
+    } else {
+        None
+    };
+
+    for filing in &filings {
+        let filing = filing_sourcer.resolve(filing);
+        let pb_file = mb.add(ProgressBar::new(
+            filing.source_length.unwrap_or_default() as u64
+        ));
+        pb_file.set_style(BAR_FILE_STYLE.clone());
+        pb_file.set_message(format!("FEC-{}", filing.filing_id));
+        export_filing(filing, &mut export, &target, &pb_file)?;
+        pb_file.finish_and_clear();
+        if let Some(pb_files) = &pb_files {
+            pb_files.inc(1);
+        }
+    }
+    if let Some(pb_files) = &pb_files {
+        pb_files.finish_and_clear();
+    }
+    let sheets = export.filters.len();
+    export.save(output).map_err(xlsx_error)?;
+
+    println!("Wrote {sheets} sheets to {output}");
+    println!(
+        "Finished {} files in {}",
+        filings.len(),
+        HumanDuration(Instant::now() - t0)
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::filing_with_cover;
+    use std::{fs::File, io::Read};
+
+    #[test]
+    fn sheet_names() {
+        assert_eq!(sheet_name("SA11AI", ""), "SA11AI");
+        assert_eq!(sheet_name("SA11AI_v6_4", " (2)"), "SA11AI_v6_4 (2)");
+        assert_eq!(sheet_name("'S[A]:1*?/\\'", ""), "S_A__1____");
+        assert_eq!(
+            sheet_name(&"A".repeat(40), " (12)"),
+            "A".repeat(26) + " (12)"
+        );
+        assert_eq!(sheet_name("''", ""), "_");
+    }
+
+    // Every part of the workbook, by path in the zip file
+    fn read_workbook(path: &std::path::Path) -> HashMap<String, String> {
+        let mut zip = zip::ZipArchive::new(File::open(path).unwrap()).unwrap();
+        (0..zip.len())
+            .map(|idx| {
+                let mut file = zip.by_index(idx).unwrap();
+                let mut contents = String::new();
+                file.read_to_string(&mut contents).unwrap();
+                (file.name().to_owned(), contents)
+            })
+            .collect()
+    }
+
+    // the names of the sheets of a workbook, in order
+    fn workbook_sheet_names(parts: &HashMap<String, String>) -> Vec<String> {
+        parts["xl/workbook.xml"]
+            .split("<sheet name=\"")
+            .skip(1)
+            .map(|sheet| sheet.split('"').next().unwrap().to_owned())
+            .collect()
+    }
+
+    fn export(
+        filings: Vec<Filing<std::io::Cursor<Vec<u8>>>>,
+        max_rows: u32,
+    ) -> HashMap<String, String> {
+        let mut export = XlsxExport::new().unwrap();
+        export.max_rows = max_rows;
+        for filing in filings {
+            export_filing(
+                filing,
+                &mut export,
+                &CmdExportTarget::ByFormType,
+                &ProgressBar::hidden(),
+            )
+            .unwrap();
+        }
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("export.xlsx");
+        export.save(path.to_str().unwrap()).unwrap();
+        read_workbook(&path)
+    }
+
+    fn sa(transaction_id: &str) -> Vec<(&str, &str)> {
+        vec![
+            ("form_type", "SA11AI"),
+            ("transaction_id", transaction_id),
+            ("contributor_last_name", "Doe"),
+            ("contribution_date", "20240115"),
+            ("contribution_amount", "100.5"),
+        ]
+    }
+
+    #[test]
+    fn writes_sheets() {
+        let filing = filing_with_cover(
+            "1",
+            "8.4",
+            "",
+            &[
+                ("coverage_through_date", "20240331"),
+                ("col_a_total_receipts", "1000"),
+                ("col_b_total_receipts", "5000"),
+            ],
+            &["SA.1", "SA.2", "SA.3", "SA.4", "SA.5"].map(sa),
+        );
+        // a header and 2 rows per sheet
+        let parts = export(vec![filing], 3);
+
+        // the rows spill over to numbered sheets
+        assert_eq!(
+            workbook_sheet_names(&parts),
+            vec!["Summary", "SA11AI", "SA11AI (2)", "SA11AI (3)"]
+        );
+        let rows = |sheet: &str| sheet.matches("<row ").count();
+        assert_eq!(rows(&parts["xl/worksheets/sheet2.xml"]), 3);
+        assert_eq!(rows(&parts["xl/worksheets/sheet3.xml"]), 3);
+        assert_eq!(rows(&parts["xl/worksheets/sheet4.xml"]), 2);
+
+        // a summary row per line with an amount
+        let summary = &parts["xl/worksheets/sheet1.xml"];
+        assert_eq!(rows(summary), 2);
+        assert!(summary.contains("<t>Total receipts</t>"));
+        assert!(summary.contains("<v>1000</v>"));
+        assert!(summary.contains("<v>5000</v>"));
+        // 2024-03-31 as an Excel date
+        assert!(summary.contains("<v>45382</v>"));
+
+        // dates and amounts are typed cells, text is inline
+        let sheet = &parts["xl/worksheets/sheet2.xml"];
+        assert!(sheet.contains("<v>45306</v>"));
+        assert!(sheet.contains("<v>100.5</v>"));
+        assert!(sheet.contains("<is><t>Doe</t></is>"));
+        assert!(sheet.contains("<autoFilter ref=\"A1:"));
+    }
+
+    #[test]
+    fn spills_summary() {
+        let filings = ["1", "2", "3"]
+            .into_iter()
+            .map(|filing_id| {
+                filing_with_cover(
+                    filing_id,
+                    "8.4",
+                    "",
+                    &[("col_a_total_receipts", "1000")],
+                    &[],
+                )
+            })
+            .collect();
+        let parts = export(filings, 3);
+        assert_eq!(workbook_sheet_names(&parts), vec!["Summary", "Summary (2)"]);
+        let rows = |sheet: &str| sheet.matches("<row ").count();
+        assert_eq!(rows(&parts["xl/worksheets/sheet1.xml"]), 3);
+        assert_eq!(rows(&parts["xl/worksheets/sheet2.xml"]), 2);
+        assert!(parts["xl/worksheets/sheet2.xml"].contains("<autoFilter ref=\"A1:I2\""));
+    }
+
+    #[test]
+    fn names_sheets_by_version() {
+        let filings = || {
+            vec![
+                filing_with_cover("1", "8.4", "", &[], &[sa("SA.1")]),
+                filing_with_cover("2", "6.4", "", &[], &[sa("SA.2")]),
+            ]
+        };
+        assert_eq!(
+            workbook_sheet_names(&export(filings(), MAX_ROWS)),
+            vec!["Summary", "SA11AI", "SA11AI_v6_4"]
+        );
+        // the same sheets whichever filing comes first
+        let mut reversed = filings();
+        reversed.reverse();
+        assert_eq!(
+            workbook_sheet_names(&export(reversed, MAX_ROWS)),
+            vec!["Summary", "SA11AI_v6_4", "SA11AI"]
+        );
+    }
+
+    #[test]
+    fn writes_amounts() {
+        let mut export = XlsxExport::new().unwrap();
+        let worksheet = export.workbook.worksheet_from_index(0).unwrap();
+        let formats = Formats {
+            header: Format::new(),
+            date: Format::new(),
+            currency: Format::new(),
+        };
+        for (row, value) in ["100.555", "-0.1", "1e3", "inf", "1,000"]
+            .iter()
+            .enumerate()
+        {
+            write_value(
+                worksheet,
+                row as u32 + 1,
+                0,
+                "contribution_amount",
+                value,
+                &formats,
+            )
+            .unwrap();
+        }
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("export.xlsx");
+        export.save(path.to_str().unwrap()).unwrap();
+        let sheet = &read_workbook(&path)["xl/worksheets/sheet1.xml"];
+        assert!(sheet.contains("<v>100.56</v>"));
+        assert!(sheet.contains("<v>-0.1</v>"));
+        // what isn't an amount to the FEC is text, even if it's a number
+        for value in ["1e3", "inf", "1,000"] {
+            assert!(
+                sheet.contains(&format!("<is><t>{value}</t></is>")),
+                "{value}"
+            );
+        }
+    }
+}
@@ -3,6 +3,7 @@ use quick_xml::events::Event;
 use quick_xml::reader::Reader;
 use std::time::Instant;
 
+#[allow(dead_code)]
 #[derive(Debug)]
 struct RssFeedItem {
     title: String,
@@ -14,13 +15,11 @@ struct RssFeedItem {
 
 fn item_read(reader: &mut Reader<&[u8]>) -> Result<RssFeedItem, ()> {
     let mut buf = Vec::new();
-    let mut last_key = String::new();
     let mut last_text = String::new();
 
     let mut title = None;
     let mut link = None;
     let mut description = None;
-    let mut pub_date = None;
     let mut guid = None;
     let mut dc_date = None;
     loop {
@@ -28,7 +27,7 @@ fn item_read(reader: &mut Reader<&[u8]>) -> Result<RssFeedItem, ()> {
         match reader.read_event_into(&mut buf) {
             Err(e) => panic!("Error at position {}: {:?}", reader.error_position(), e),
             Ok(Event::Eof) => todo!(),
-            Ok(Event::Start(e)) => {}
+            Ok(Event::Start(_e)) => {}
             Ok(Event::Text(e)) => last_text = std::str::from_utf8(e.as_ref()).unwrap().to_string(),
             Ok(Event::End(e)) => {
                 match e.as_ref() {
@@ -36,10 +35,10 @@ fn item_read(reader: &mut Reader<&[u8]>) -> Result<RssFeedItem, ()> {
                     b"title" => title = Some(last_text.clone()),
                     b"link" => link = Some(last_text.clone()),
                     b"description" => description = Some(last_text.clone()),
-                    b"pubDate" => pub_date = Some(last_text.clone()),
+                    b"pubDate" => (),
                     b"guid" => guid = Some(last_text.clone()),
                     b"dc:date" => dc_date = Some(last_text.clone()),
-                    key => todo!("{}", std::str::from_utf8(key.as_ref()).unwrap()),
+                    key => todo!("{}", std::str::from_utf8(key).unwrap()),
                 }
                 if e.as_ref() == b"item" {
                     break;
@@ -49,11 +48,11 @@ fn item_read(reader: &mut Reader<&[u8]>) -> Result<RssFeedItem, ()> {
         }
     }
     Ok(RssFeedItem {
-        title: title.ok_or_else(|| ())?,
-        link: link.ok_or_else(|| ())?,
-        description: description.ok_or_else(|| ())?,
-        guid: guid.ok_or_else(|| ())?,
-        date: dc_date.ok_or_else(|| ())?,
+        title: title.ok_or(())?,
+        link: link.ok_or(())?,
+        description: description.ok_or(())?,
+        guid: guid.ok_or(())?,
+        date: dc_date.ok_or(())?,
     })
 }
 
@@ -81,8 +80,8 @@ pub fn cmd_feed() -> Result<(), ()> {
                     items.push(item);
                 }
             }
-            Ok(Event::End(e)) => {}
-            Ok(Event::Text(e)) => {}
+            Ok(Event::End(_e)) => {}
+            Ok(Event::Text(_e)) => {}
 
             // There are several other `Event`s we do not consider here
             _ => (),
@@ -1,5 +1,10 @@
 use colored::Colorize;
-use fec_parser::{report_code_label, Filing};
+use fec_parser::{
+    descriptions::{form_type_description, line_label},
+    report_code_label,
+    summary::{FinancialSummary, SummaryForm},
+    Filing,
+};
 use indicatif::{HumanBytes, ProgressBar};
 use serde_json::Value;
 use std::{collections::HashMap, error::Error, io::Read, time::Duration};
@@ -11,6 +16,8 @@ use tabled::{
 
 use crate::sourcer::FilingSourcer;
 struct FilingFormMetadata {
+    // raw form type of the first row, ex 'SC/10', for mapping lookups
+    form_type: String,
     count: usize,
     bytes: usize,
 }
@@ -20,6 +27,65 @@ pub(crate) enum CmdInfoFormat {
     Json,
 }
 
+// lines of the summary page that are always shown in the human output
+const SUMMARY_HEADLINE_LINES: &[&str] = &[
+    "cash_on_hand_beginning",
+    "total_receipts",
+    "total_disbursements",
+    "cash_on_hand_close",
+    "debts_owed_to_committee",
+    "debts_owed_by_committee",
+];
+
+fn format_amount(amount: Option<f64>) -> String {
+    amount.map_or("".to_owned(), |v| format!("${v:.2}"))
+}
+
+pub(crate) fn summary_line_label(name: &str) -> String {
+    let label = name.replace('_', " ");
+    let mut chars = label.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => label,
+    }
+}
+
+fn summary_table(summary: &FinancialSummary, full: bool) -> String {
+    let mut tbl = TableBuilder::new();
+    tbl.push_record(["Summary", "Period", "Cycle to date"]);
+    for (name, amount) in summary.lines() {
+        if !full && !SUMMARY_HEADLINE_LINES.contains(&name) {
+            continue;
+        }
+        if amount.period.is_none() && amount.cycle_to_date.is_none() {
+            continue;
+        }
+        tbl.push_record([
+            summary_line_label(name),
+            format_amount(amount.period),
+            format_amount(amount.cycle_to_date),
+        ]);
+    }
+    tbl.build()
+        .with(TableStyle::modern_rounded())
+        .modify(TableColumns::new(1..3), TableAlignment::right())
+        .to_string()
+}
+
+fn summary_json(summary: &FinancialSummary) -> Value {
+    let mut lines = serde_json::Map::new();
+    for (name, amount) in summary.lines() {
+        lines.insert(
+            name.to_owned(),
+            serde_json::json!({
+                "period": amount.period,
+                "cycle_to_date": amount.cycle_to_date,
+            }),
+        );
+    }
+    Value::Object(lines)
+}
+
 fn process_filing<R: Read>(
     filing: &mut Filing<R>,
     format: &CmdInfoFormat,
@@ -58,11 +124,41 @@ fn process_filing<R: Read>(
             filing.cover.filer_name,
             filing.cover.filer_id,
         );
+        if let Some(description) = form_type_description(&filing.cover.form_type) {
+            println!("{}", description.dimmed());
+        }
+        if let Some(summary) = filing.cover.financial_summary() {
+            if let Some(ref spinner) = spinner {
+                spinner.suspend(|| println!("{}", summary_table(&summary, full)));
+            } else {
+                println!("{}", summary_table(&summary, full));
+            }
+        }
     }
+    let mut json = serde_json::json!({
+        "filing_id": filing.filing_id,
+        "fec_version": filing.header.fec_version,
+        "software_name": filing.header.soft_name,
+        "software_version": filing.header.soft_ver,
+        "report_id": filing.header.report_id,
+        "report_number": filing.header.report_number,
+        "comment": filing.header.comment,
+        "form_type": filing.cover.form_type,
+        "form_type_description": form_type_description(&filing.cover.form_type),
+        "filer_id": filing.cover.filer_id,
+        "filer_name": filing.cover.filer_name,
+        "report_code": filing.cover.report_code,
+        "coverage_from_date": filing.cover.coverage_from_date,
+        "coverage_through_date": filing.cover.coverage_through_date,
+        "financial_summary": filing.cover.financial_summary().as_ref().map(summary_json),
+    });
     if !full {
         if let Some(ref spinner) = spinner {
             spinner.finish_and_clear();
         }
+        if matches!(format, CmdInfoFormat::Json) {
+            println!("{}", json);
+        }
         return;
     }
 
@@ -76,6 +172,7 @@ fn process_filing<R: Read>(
             status.insert(
                 row.row_type.clone(),
                 FilingFormMetadata {
+                    form_type: row.record.get(0).unwrap_or(&row.row_type).to_owned(),
                     count: 1,
                     bytes: row.original_size,
                 },
@@ -87,15 +184,20 @@ fn process_filing<R: Read>(
         spinner.finish_and_clear();
     }
 
+    let summary_form = SummaryForm::from_form_type(&filing.cover.form_type);
+    let line = |form_type: &str| summary_form.and_then(|form| line_label(form, form_type));
+
     let mut x: Vec<_> = status.iter().collect();
-    x.sort_by(|a, b| b.1.count.cmp(&a.1.count));
+    x.sort_by_key(|(_, metadata)| std::cmp::Reverse(metadata.count));
     match format {
         CmdInfoFormat::Human => {
             let mut tbl = TableBuilder::new();
-            tbl.push_record(["Form Type", "# Rows", "Size"]);
+            tbl.push_record(["Form Type", "Description", "Line", "# Rows", "Size"]);
             for (x, y) in x {
                 tbl.push_record([
                     x,
+                    form_type_description(&y.form_type).unwrap_or(""),
+                    line(&y.form_type).unwrap_or(""),
                     &indicatif::HumanCount(y.count as u64).to_string(),
                     &indicatif::HumanBytes(y.bytes as u64).to_string(),
                 ]);
@@ -103,14 +205,28 @@ fn process_filing<R: Read>(
             let tbl = tbl
                 .build()
                 .with(TableStyle::modern_rounded())
-                .modify(TableColumns::new(1..3), TableAlignment::right())
+                .modify(TableColumns::new(3..5), TableAlignment::right())
                 .to_string();
 
             println!("{tbl}");
         }
         CmdInfoFormat::Json => {
-            let v = Value::Null;
-            println!("{}", v);
+            let rows: serde_json::Map<String, Value> = x
+                .iter()
+                .map(|(form_type, metadata)| {
+                    (
+                        form_type.to_string(),
+                        serde_json::json!({
+                            "description": form_type_description(&metadata.form_type),
+                            "line": line(&metadata.form_type),
+                            "count": metadata.count,
+                            "bytes": metadata.bytes,
+                        }),
+                    )
+                })
+                .collect();
+            json["rows"] = Value::Object(rows);
+            println!("{}", json);
         }
     }
 }
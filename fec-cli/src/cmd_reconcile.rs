@@ -0,0 +1,130 @@
+use colored::Colorize;
+use fec_parser::validation::reconcile::{ReconcileLine, Reconciliation};
+use serde_json::Value;
+use std::error::Error;
+use tabled::{
+    builder::Builder as TableBuilder,
+    settings::{object::Columns as TableColumns, Alignment as TableAlignment, Style as TableStyle},
+};
+use thiserror::Error;
+
+use crate::sourcer::FilingSourcer;
+
+#[derive(Error, Debug)]
+pub enum CmdReconcileError {
+    #[error("{0} summary line(s) differ from their itemizations by more than {1}")]
+    ToleranceExceeded(usize, f64),
+}
+
+pub(crate) enum CmdReconcileFormat {
+    Human,
+    Json,
+}
+
+fn format_amount(amount: f64) -> String {
+    if amount < 0.0 {
+        format!("-${:.2}", -amount)
+    } else {
+        format!("${amount:.2}")
+    }
+}
+
+fn line_json(line: &ReconcileLine, tolerance: f64) -> Value {
+    serde_json::json!({
+        "line": line.line,
+        "row_type_prefix": line.row_type_prefix,
+        "description": line.description,
+        "itemized_count": line.itemized_count,
+        "itemized_total": line.itemized_total,
+        "reported": line.reported,
+        "difference": line.difference(),
+        "exceeds_tolerance": line.exceeds(tolerance),
+    })
+}
+
+fn print_human(filing_id: &str, reconciliation: &Reconciliation, tolerance: f64) {
+    println!(
+        "{} {:?} reconciliation",
+        format!("FEC-{filing_id}").bold(),
+        reconciliation.form
+    );
+    let mut tbl = TableBuilder::new();
+    tbl.push_record([
+        "Line",
+        "Description",
+        "# Itemized",
+        "Itemized",
+        "Reported",
+        "Difference",
+    ]);
+    for line in &reconciliation.lines {
+        if line.itemized_count == 0 && line.reported.is_none() {
+            continue;
+        }
+        let difference = format_amount(line.difference());
+        tbl.push_record([
+            line.line.to_owned(),
+            line.description.to_owned(),
+            indicatif::HumanCount(line.itemized_count as u64).to_string(),
+            format_amount(line.itemized_total),
+            line.reported.map_or("".to_owned(), format_amount),
+            if line.exceeds(tolerance) {
+                difference.red().to_string()
+            } else {
+                difference
+            },
+        ]);
+    }
+    let tbl = tbl
+        .build()
+        .with(TableStyle::modern_rounded())
+        .modify(TableColumns::new(2..6), TableAlignment::right())
+        .to_string();
+    println!("{tbl}");
+}
+
+pub fn cmd_reconcile(
+    filings: Vec<String>,
+    format: CmdReconcileFormat,
+    tolerance: f64,
+) -> Result<(), Box<dyn Error>> {
+    let filing_sourcer = FilingSourcer::new();
+    let mut exceeding = 0;
+    let mut results = vec![];
+
+    for filing in &filings {
+        let mut filing = filing_sourcer.resolve(filing);
+        let reconciliation = match Reconciliation::from_filing(&mut filing)? {
+            Some(reconciliation) => reconciliation,
+            None => {
+                eprintln!(
+                    "FEC-{}: {} filings don't have a summary page to reconcile, skipping",
+                    filing.filing_id, filing.cover.form_type
+                );
+                continue;
+            }
+        };
+        exceeding += reconciliation.exceeding(tolerance).count();
+        match format {
+            CmdReconcileFormat::Human => print_human(&filing.filing_id, &reconciliation, tolerance),
+            CmdReconcileFormat::Json => results.push(serde_json::json!({
+                "filing_id": filing.filing_id,
+                "form": format!("{:?}", reconciliation.form),
+                "lines": reconciliation
+                    .lines
+                    .iter()
+                    .map(|line| line_json(line, tolerance))
+                    .collect::<Vec<_>>(),
+            })),
+        }
+    }
+
+    if matches!(format, CmdReconcileFormat::Json) {
+        println!("{}", Value::Array(results));
+    }
+
+    if exceeding > 0 {
+        return Err(CmdReconcileError::ToleranceExceeded(exceeding, tolerance).into());
+    }
+    Ok(())
+}
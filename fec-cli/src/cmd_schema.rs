@@ -0,0 +1,168 @@
+use fec_parser::{
+    descriptions::{column_description, form_pattern_description, form_type_description},
+    mappings::{schema_for_field, ColumnType, FormSchema, FORM_TYPES, FORM_TYPE_VERSIONS_SET},
+    transactions::TransactionColumns,
+};
+use serde_json::Value;
+use std::error::Error;
+use tabled::{builder::Builder as TableBuilder, settings::Style as TableStyle};
+use thiserror::Error;
+
+use crate::cmd_export::create_table_sql;
+
+#[derive(Error, Debug)]
+pub enum CmdSchemaError {
+    #[error("No mappings found for form type '{0}' in FEC version {1}")]
+    UnknownForm(String, String),
+    #[error("A form type is required for the '{0}' format")]
+    FormRequired(&'static str),
+}
+
+pub(crate) enum CmdSchemaFormat {
+    Human,
+    Json,
+    Sql,
+    JsonSchema,
+}
+
+fn schema_json(form: &str, fec_version: &str, schema: &FormSchema) -> Value {
+    serde_json::json!({
+        "form_type": form,
+        "description": form_type_description(form),
+        "fec_version": fec_version,
+        "pattern": schema.form_type,
+        "version_pattern": schema.version_pattern,
+        "columns": schema
+            .columns
+            .iter()
+            .map(|column| serde_json::json!({
+                "name": column.name,
+                "type": column.column_type.as_str(),
+                "description": column_description(column.name),
+            }))
+            .collect::<Vec<_>>(),
+    })
+}
+
+fn json_schema(form: &str, fec_version: &str, schema: &FormSchema) -> Value {
+    let properties: serde_json::Map<String, Value> = schema
+        .columns
+        .iter()
+        .map(|column| {
+            let mut property = match column.column_type {
+                ColumnType::Text => serde_json::json!({"type": ["string", "null"]}),
+                ColumnType::Float => serde_json::json!({"type": ["number", "null"]}),
+                ColumnType::Date => {
+                    serde_json::json!({"type": ["string", "null"], "format": "date"})
+                }
+            };
+            if let Some(description) = column_description(column.name) {
+                property["description"] = description.into();
+            }
+            (column.name.to_owned(), property)
+        })
+        .collect();
+    serde_json::json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": format!("{form} (FEC version {fec_version})"),
+        "description": form_type_description(form),
+        "type": "object",
+        "properties": properties,
+    })
+}
+
+fn print_form_types(format: CmdSchemaFormat) -> Result<(), Box<dyn Error>> {
+    match format {
+        CmdSchemaFormat::Human => {
+            let mut tbl = TableBuilder::new();
+            tbl.push_record(["Form type", "Version patterns", "Description"]);
+            for (form_type, versions) in FORM_TYPES.iter().zip(FORM_TYPE_VERSIONS_SET.iter()) {
+                tbl.push_record([
+                    form_type.to_string(),
+                    versions.patterns().join(", "),
+                    form_pattern_description(form_type).unwrap_or("").to_owned(),
+                ]);
+            }
+            println!("{}", tbl.build().with(TableStyle::modern_rounded()));
+        }
+        CmdSchemaFormat::Json => {
+            let form_types: Vec<Value> = FORM_TYPES
+                .iter()
+                .zip(FORM_TYPE_VERSIONS_SET.iter())
+                .map(|(form_type, versions)| {
+                    serde_json::json!({
+                        "pattern": form_type,
+                        "version_patterns": versions.patterns(),
+                        "description": form_pattern_description(form_type),
+                    })
+                })
+                .collect();
+            println!("{}", Value::Array(form_types));
+        }
+        CmdSchemaFormat::Sql => return Err(CmdSchemaError::FormRequired("sql").into()),
+        CmdSchemaFormat::JsonSchema => {
+            return Err(CmdSchemaError::FormRequired("jsonschema").into())
+        }
+    }
+    Ok(())
+}
+
+pub fn cmd_schema(
+    form: Option<&str>,
+    fec_version: &str,
+    format: CmdSchemaFormat,
+) -> Result<(), Box<dyn Error>> {
+    let form = match form {
+        Some(form) => form,
+        None => return print_form_types(format),
+    };
+    let schema = schema_for_field(form, fec_version)
+        .ok_or_else(|| CmdSchemaError::UnknownForm(form.to_owned(), fec_version.to_owned()))?;
+
+    match format {
+        CmdSchemaFormat::Human => {
+            println!(
+                "{form} (FEC version {fec_version}, version pattern {})",
+                schema.version_pattern
+            );
+            if let Some(description) = form_type_description(form) {
+                println!("{description}");
+            }
+            let mut tbl = TableBuilder::new();
+            tbl.push_record(["#", "Column", "Type", "Description"]);
+            for (idx, column) in schema.columns.iter().enumerate() {
+                tbl.push_record([
+                    (idx + 1).to_string(),
+                    column.name.to_owned(),
+                    column.column_type.as_str().to_owned(),
+                    column_description(column.name).unwrap_or("").to_owned(),
+                ]);
+            }
+            println!("{}", tbl.build().with(TableStyle::modern_rounded()));
+        }
+        CmdSchemaFormat::Json => println!("{}", schema_json(form, fec_version, &schema)),
+        CmdSchemaFormat::JsonSchema => println!(
+            "{}",
+            serde_json::to_string_pretty(&json_schema(form, fec_version, &schema))?
+        ),
+        CmdSchemaFormat::Sql => {
+            let column_names: Vec<String> = schema
+                .columns
+                .iter()
+                .map(|column| column.name.to_owned())
+                .collect();
+            let transaction_columns = TransactionColumns::new(&column_names);
+            println!(
+                "{};",
+                create_table_sql(
+                    &form.replace('/', ""),
+                    &column_names,
+                    transaction_columns.has_back_reference(),
+                    transaction_columns.has_transaction_id(),
+                    &[],
+                )
+            );
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,102 @@
+use colored::Colorize;
+use fec_parser::validation::spec::{Finding, Severity, SpecValidator};
+use serde_json::Value;
+use std::error::Error;
+use tabled::{builder::Builder as TableBuilder, settings::Style as TableStyle};
+use thiserror::Error;
+
+use crate::sourcer::FilingSourcer;
+
+#[derive(Error, Debug)]
+pub enum CmdValidateError {
+    #[error("{0} validation error(s) found")]
+    Invalid(usize),
+}
+
+pub(crate) enum CmdValidateFormat {
+    Human,
+    Json,
+}
+
+fn finding_json(filing_id: &str, finding: &Finding) -> Value {
+    serde_json::json!({
+        "filing_id": filing_id,
+        "line": finding.line,
+        "row_type": finding.row_type,
+        "column": finding.column,
+        "value": finding.value,
+        "severity": finding.severity().as_str(),
+        "rule": finding.rule.as_str(),
+        "message": finding.message,
+    })
+}
+
+fn print_human(filing_id: &str, findings: &[Finding]) {
+    let errors = findings
+        .iter()
+        .filter(|f| f.severity() == Severity::Error)
+        .count();
+    let summary = format!("{errors} error(s), {} warning(s)", findings.len() - errors);
+    println!(
+        "{} {}",
+        format!("FEC-{filing_id}").bold(),
+        if errors > 0 {
+            summary.red()
+        } else {
+            summary.green()
+        }
+    );
+    if findings.is_empty() {
+        return;
+    }
+    let mut tbl = TableBuilder::new();
+    tbl.push_record(["Line", "Row type", "Column", "Severity", "Rule", "Message"]);
+    for finding in findings {
+        let severity = finding.severity().as_str();
+        tbl.push_record([
+            finding.line.map_or("".to_owned(), |line| line.to_string()),
+            finding.row_type.clone(),
+            finding.column.clone().unwrap_or_default(),
+            match finding.severity() {
+                Severity::Error => severity.red().to_string(),
+                Severity::Warning => severity.yellow().to_string(),
+            },
+            finding.rule.as_str().to_owned(),
+            finding.message.clone(),
+        ]);
+    }
+    let tbl = tbl.build().with(TableStyle::modern_rounded()).to_string();
+    println!("{tbl}");
+}
+
+pub fn cmd_validate(filings: Vec<String>, format: CmdValidateFormat) -> Result<(), Box<dyn Error>> {
+    let filing_sourcer = FilingSourcer::new();
+    let mut errors = 0;
+    let mut results = vec![];
+
+    for filing in &filings {
+        let mut filing = filing_sourcer.resolve(filing);
+        let findings = SpecValidator::validate_filing(&mut filing)?;
+        errors += findings
+            .iter()
+            .filter(|f| f.severity() == Severity::Error)
+            .count();
+        match format {
+            CmdValidateFormat::Human => print_human(&filing.filing_id, &findings),
+            CmdValidateFormat::Json => results.extend(
+                findings
+                    .iter()
+                    .map(|finding| finding_json(&filing.filing_id, finding)),
+            ),
+        }
+    }
+
+    if matches!(format, CmdValidateFormat::Json) {
+        println!("{}", Value::Array(results));
+    }
+
+    if errors > 0 {
+        return Err(CmdValidateError::Invalid(errors).into());
+    }
+    Ok(())
+}
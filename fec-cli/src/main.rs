@@ -1,15 +1,34 @@
+mod cmd_diff;
 mod cmd_download;
 mod cmd_export;
+mod cmd_export_csv;
+#[cfg(feature = "duckdb")]
+mod cmd_export_duckdb;
+mod cmd_export_jsonl;
+mod cmd_export_parquet;
+mod cmd_export_postgres;
+mod cmd_export_xlsx;
 mod cmd_fastfec;
 mod cmd_feed;
 mod cmd_info;
+mod cmd_reconcile;
+mod cmd_schema;
+mod cmd_validate;
 mod sourcer;
+#[cfg(test)]
+mod test_utils;
 
 use std::{error::Error, fs, process};
 
 use clap::{parser::ValuesRef, Arg, Command};
+use cmd_diff::CmdDiffFormat;
 use cmd_export::CmdExportTarget;
+use cmd_export_csv::CsvOptions;
+use cmd_export_parquet::{ParquetOptions, ParquetPartition};
 use cmd_info::CmdInfoFormat;
+use cmd_reconcile::CmdReconcileFormat;
+use cmd_schema::CmdSchemaFormat;
+use cmd_validate::CmdValidateFormat;
 
 fn resolve_filing_ids(
     filing_matches: Option<ValuesRef<String>>,
@@ -60,20 +79,155 @@ fn cmd() -> Command {
         .arg(Arg::new("output-directory").help("Directory to store downloaded files into"));
 
     let export = Command::new("export")
-        .about("Export FEC filings itemizations to a SQLite database.")
+        .about("Export FEC filings itemizations to a SQLite, DuckDB or Postgres database, or to Parquet files.")
         .arg(arg_filings.clone())
         .arg(
             Arg::new("db")
                 .short('o')
-                .help("SQLite db to export to")
+                .help("SQLite or DuckDB db to export to, a postgres:// URL, the directory to write Parquet or CSV files to, or the JSONL or Excel file to write, '-' for stdout")
                 .required(true),
         )
         .arg(arg_input_file.clone())
+        .arg(
+            Arg::new("format")
+                .short('f')
+                .long("format")
+                .help("Format to export to, 'sqlite', 'duckdb', 'postgres', 'parquet', 'jsonl', 'csv' or 'xlsx'. Defaults to 'postgres' for postgres:// URLs, 'sqlite' otherwise")
+                .required(false),
+        )
         .arg(
             Arg::new("target")
                 .long("target")
-                .help("Which itemizations to export and in what format")
+                .help("Which itemizations to export and in what format, 'form-type', 'schedule-a' or, for every format but SQLite, 'schedules'")
                 .default_value("form-type"),
+        )
+        .arg(
+            Arg::new("labels")
+                .long("labels")
+                .help("Add decoded '_label' columns for coded columns like entity_type")
+                .num_args(0)
+                .required(false),
+        )
+        .arg(
+            Arg::new("latest-only")
+                .long("latest-only")
+                .help("Only keep the itemizations of the latest amendment of each report")
+                .num_args(0)
+                .required(false),
+        )
+        .arg(
+            Arg::new("merge-amendments")
+                .long("merge-amendments")
+                .help("Apply each report's amendments transaction by transaction, and export the merged itemizations under the latest filing")
+                .num_args(0)
+                .required(false),
+        )
+        .arg(
+            Arg::new("compression")
+                .long("compression")
+                .help("Parquet compression, 'none', 'snappy', 'gzip' or 'zstd', with an optional level like 'zstd(9)'")
+                .default_value("zstd"),
+        )
+        .arg(
+            Arg::new("row-group-size")
+                .long("row-group-size")
+                .help("Largest number of rows in a Parquet row group")
+                .value_parser(clap::value_parser!(usize))
+                .default_value("1048576"),
+        )
+        .arg(
+            Arg::new("partition-by")
+                .long("partition-by")
+                .help("Write Parquet files into Hive-style partitions, by 'filing-id' or election 'cycle'")
+                .required(false),
+        )
+        .arg(
+            Arg::new("schema")
+                .long("schema")
+                .help("Postgres schema to create the tables in")
+                .default_value("public"),
+        )
+        .arg(
+            Arg::new("append")
+                .long("append")
+                .help("Add rows to existing CSV files instead of replacing them")
+                .num_args(0)
+                .required(false),
+        )
+        .arg(
+            Arg::new("gzip")
+                .long("gzip")
+                .help("Write gzip compressed .csv.gz files")
+                .num_args(0)
+                .required(false),
+        )
+        .arg(
+            Arg::new("covers")
+                .long("covers")
+                .help("Include a header and a cover object for each filing in JSONL exports")
+                .num_args(0)
+                .required(false),
+        );
+
+    let reconcile = Command::new("reconcile")
+        .about("Compare itemizations against the totals reported on a filing's summary page")
+        .arg(arg_filings.clone())
+        .arg(arg_input_file.clone())
+        .arg(
+            Arg::new("format")
+                .short('f')
+                .help("Format to output results to, 'table' or 'json'")
+                .required(false),
+        )
+        .arg(
+            Arg::new("tolerance")
+                .long("tolerance")
+                .help("Largest allowed difference in dollars before exiting with an error")
+                .value_parser(clap::value_parser!(f64))
+                .default_value("0.01"),
+        );
+
+    let diff = Command::new("diff")
+        .about("Compare two filings, ex an amendment against the filing it amends")
+        .arg(
+            Arg::new("old")
+                .help("Filing to compare against")
+                .required(true),
+        )
+        .arg(Arg::new("new").help("Filing to compare").required(true))
+        .arg(
+            Arg::new("format")
+                .short('f')
+                .help("Format to output differences to, 'table', 'json' or 'csv'")
+                .required(false),
+        );
+
+    let validate = Command::new("validate")
+        .about("Check filings against the FEC file format specification")
+        .arg(arg_filings.clone())
+        .arg(arg_input_file.clone())
+        .arg(
+            Arg::new("format")
+                .short('f')
+                .help("Format to output findings to, 'table' or 'json'")
+                .required(false),
+        );
+
+    let schema = Command::new("schema")
+        .about("Print the columns of a form type, or list every known form type")
+        .arg(Arg::new("form").help("Form or row type, ex 'F3X' or 'SA11AI'"))
+        .arg(
+            Arg::new("version")
+                .long("version")
+                .help("FEC version of the columns")
+                .default_value("8.4"),
+        )
+        .arg(
+            Arg::new("format")
+                .short('f')
+                .long("format")
+                .help("Format to output to, 'table', 'json', 'sql' or 'jsonschema'")
+                .required(false),
         );
 
     let feed = Command::new("feed").hide(true);
@@ -90,6 +244,10 @@ fn cmd() -> Command {
   .subcommand(download)
   .subcommand(feed)
   .subcommand(export)
+  .subcommand(reconcile)
+  .subcommand(diff)
+  .subcommand(validate)
+  .subcommand(schema)
   .subcommand(fastfec_compat)
 }
 
@@ -124,11 +282,150 @@ fn main() {
             );
             let db = m.get_one::<String>("db").unwrap();
             let target = match m.get_one::<String>("target").map(|v| v.as_str()) {
-                Some("form-type") => CmdExportTarget::ByFormType,
-                Some("schedule-a") | Some("a") => CmdExportTarget::ScheduleA,
-                Some(_) | None => todo!(),
+                Some("form-type") => Ok(CmdExportTarget::ByFormType),
+                Some("schedule-a") | Some("a") => Ok(CmdExportTarget::ScheduleA),
+                Some("schedules") => Ok(CmdExportTarget::Schedules),
+                t => Err(format!("Unknown target {}", t.unwrap_or_default()).into()),
+            };
+            let labels = *m.get_one::<bool>("labels").unwrap();
+            let latest_only = *m.get_one::<bool>("latest-only").unwrap();
+            let merge_amendments = *m.get_one::<bool>("merge-amendments").unwrap();
+            let format = match m.get_one::<String>("format").map(String::as_str) {
+                Some(format) => format,
+                None if db.starts_with("postgres://") || db.starts_with("postgresql://") => {
+                    "postgres"
+                }
+                None => "sqlite",
+            };
+            target.and_then(|target| match format {
+                "sqlite" => {
+                    cmd_export::cmd_export(
+                        filings,
+                        db,
+                        target,
+                        labels,
+                        latest_only,
+                        merge_amendments,
+                    )
+                }
+                "duckdb" | "parquet" | "postgres" | "jsonl" | "csv" | "xlsx" if labels || latest_only || merge_amendments => Err(
+                    "--labels, --latest-only and --merge-amendments are only supported for SQLite exports"
+                        .into(),
+                ),
+                #[cfg(feature = "duckdb")]
+                "duckdb" => cmd_export_duckdb::cmd_export_duckdb(filings, db, target),
+                #[cfg(not(feature = "duckdb"))]
+                "duckdb" => Err(
+                    "libfec was built without DuckDB support, rebuild it with --features duckdb"
+                        .into(),
+                ),
+                "postgres" => cmd_export_postgres::cmd_export_postgres(
+                    filings,
+                    db,
+                    m.get_one::<String>("schema").unwrap(),
+                    target,
+                ),
+                "csv" => cmd_export_csv::cmd_export_csv(
+                    filings,
+                    db,
+                    target,
+                    CsvOptions {
+                        append: *m.get_one::<bool>("append").unwrap(),
+                        gzip: *m.get_one::<bool>("gzip").unwrap(),
+                    },
+                ),
+                "xlsx" => cmd_export_xlsx::cmd_export_xlsx(filings, db, target),
+                "jsonl" => cmd_export_jsonl::cmd_export_jsonl(
+                    filings,
+                    db,
+                    target,
+                    *m.get_one::<bool>("covers").unwrap(),
+                ),
+                "parquet" => {
+                    let partition = match m.get_one::<String>("partition-by").map(String::as_str) {
+                        None => Ok(ParquetPartition::None),
+                        Some("filing-id") | Some("filing_id") => Ok(ParquetPartition::FilingId),
+                        Some("cycle") => Ok(ParquetPartition::Cycle),
+                        Some(p) => Err(format!("Unknown partition {p}").into()),
+                    };
+                    let row_group_size = *m.get_one::<usize>("row-group-size").unwrap();
+                    partition.and_then(|partition| {
+                        cmd_export_parquet::parse_compression(
+                            m.get_one::<String>("compression").unwrap(),
+                        )
+                        .map_err(Into::into)
+                        .and_then(|compression| {
+                            cmd_export_parquet::cmd_export_parquet(
+                                filings,
+                                db,
+                                target,
+                                ParquetOptions {
+                                    compression,
+                                    row_group_size,
+                                    partition,
+                                },
+                            )
+                        })
+                    })
+                }
+                f => Err(format!("Unknown format {f}").into()),
+            })
+        }
+        Some(("reconcile", m)) => {
+            let filings = resolve_filing_ids(
+                m.get_many::<String>("filing"),
+                m.get_one::<String>("input-file"),
+            );
+            let format = match m.get_one::<String>("format").map(String::as_str) {
+                None | Some("table") => Ok(CmdReconcileFormat::Human),
+                Some("json") => Ok(CmdReconcileFormat::Json),
+                Some(f) => Err(format!("Unknown format {f}").into()),
+            };
+            let tolerance = *m.get_one::<f64>("tolerance").unwrap();
+            format.and_then(|format| cmd_reconcile::cmd_reconcile(filings, format, tolerance))
+        }
+        Some(("diff", m)) => {
+            let format = match m.get_one::<String>("format").map(String::as_str) {
+                None | Some("table") => Ok(CmdDiffFormat::Human),
+                Some("json") => Ok(CmdDiffFormat::Json),
+                Some("csv") => Ok(CmdDiffFormat::Csv),
+                Some(f) => Err(format!("Unknown format {f}").into()),
+            };
+            format.and_then(|format| {
+                cmd_diff::cmd_diff(
+                    m.get_one::<String>("old").unwrap(),
+                    m.get_one::<String>("new").unwrap(),
+                    format,
+                )
+            })
+        }
+        Some(("validate", m)) => {
+            let filings = resolve_filing_ids(
+                m.get_many::<String>("filing"),
+                m.get_one::<String>("input-file"),
+            );
+            let format = match m.get_one::<String>("format").map(String::as_str) {
+                None | Some("table") => Ok(CmdValidateFormat::Human),
+                Some("json") => Ok(CmdValidateFormat::Json),
+                Some(f) => Err(format!("Unknown format {f}").into()),
+            };
+            format.and_then(|format| cmd_validate::cmd_validate(filings, format))
+        }
+        Some(("schema", m)) => {
+            let format = match m.get_one::<String>("format").map(String::as_str) {
+                None | Some("table") => Ok(CmdSchemaFormat::Human),
+                Some("json") => Ok(CmdSchemaFormat::Json),
+                Some("sql") => Ok(CmdSchemaFormat::Sql),
+                Some("jsonschema") => Ok(CmdSchemaFormat::JsonSchema),
+                Some(f) => Err(format!("Unknown format {f}").into()),
             };
-            cmd_export::cmd_export(filings, db, target)
+            format.and_then(|format| {
+                cmd_schema::cmd_schema(
+                    m.get_one::<String>("form").map(String::as_str),
+                    m.get_one::<String>("version").unwrap(),
+                    format,
+                )
+            })
         }
         Some(("download", m)) => {
             let filings = resolve_filing_ids(
@@ -151,6 +448,9 @@ fn main() {
 
     match result {
         Ok(_) => process::exit(0),
-        Err(_) => process::exit(1),
+        Err(e) => {
+            eprintln!("{e}");
+            process::exit(1)
+        }
     }
 }
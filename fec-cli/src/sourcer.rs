@@ -23,7 +23,7 @@ impl FilingSourcer {
                     let filing_id = Path::new(input)
                         .file_stem()
                         .map(|os_str| os_str.to_string_lossy().to_string());
-                    let source_length = f.metadata().map(|v| (v.len() as usize)).ok();
+                    let source_length = f.metadata().map(|v| v.len() as usize).ok();
                     (Box::new(f), filing_id.unwrap(), source_length)
                 }
                 Err(_) => {
@@ -43,7 +43,7 @@ impl FilingSourcer {
                         )
                     } else {
                         match self.cache_directory.as_ref() {
-                            Some(cache_directory) => todo!(),
+                            Some(_cache_directory) => todo!(),
                             None => {
                                 let filing_id = input
                                     .strip_prefix("FEC-")
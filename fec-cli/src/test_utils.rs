@@ -0,0 +1,75 @@
+use fec_parser::{mappings::column_names_for_field, Filing};
+use std::io::Cursor;
+
+// a filing with the given records after the header and an F3XA cover,
+// comma separated for 5.3
+pub fn filing(
+    filing_id: &str,
+    fec_version: &str,
+    report_id: &str,
+    records: &[Vec<(&str, &str)>],
+) -> Filing<Cursor<Vec<u8>>> {
+    filing_with_cover(filing_id, fec_version, report_id, &[], records)
+}
+
+// same as `filing`, with more fields of the cover, ex its coverage dates
+pub fn filing_with_cover(
+    filing_id: &str,
+    fec_version: &str,
+    report_id: &str,
+    cover: &[(&str, &str)],
+    records: &[Vec<(&str, &str)>],
+) -> Filing<Cursor<Vec<u8>>> {
+    let delimiter = if fec_version.starts_with('5') {
+        ","
+    } else {
+        "\x1c"
+    };
+    let header: &[&str] = if fec_version.starts_with('5') {
+        &[
+            "HDR",
+            "FEC",
+            fec_version,
+            "libfec",
+            "0.1",
+            "^",
+            report_id,
+            "1",
+            "",
+        ]
+    } else {
+        &[
+            "HDR",
+            "FEC",
+            fec_version,
+            "libfec",
+            "0.1",
+            report_id,
+            "1",
+            "",
+        ]
+    };
+    let mut contents = header.join(delimiter) + "\n";
+    let cover: Vec<(&str, &str)> = [
+        ("form_type", "F3XA"),
+        ("filer_committee_id_number", "C00000001"),
+        ("committee_name", "Test Committee"),
+    ]
+    .into_iter()
+    .chain(cover.iter().copied())
+    .collect();
+    for values in std::iter::once(&cover).chain(records) {
+        let columns = column_names_for_field(values[0].1, fec_version).unwrap();
+        let mut record = vec![""; columns.len()];
+        for (name, value) in values {
+            record[columns.iter().position(|v| v == name).unwrap()] = value;
+        }
+        contents += &(record.join(delimiter) + "\n");
+    }
+    Filing::from_reader(
+        Cursor::new(contents.into_bytes()),
+        filing_id.to_owned(),
+        None,
+    )
+    .unwrap()
+}
@@ -0,0 +1,37 @@
+use std::{fs, process::Command};
+
+// `-o -` writes the JSON lines to stdout, and nothing else
+#[test]
+fn exports_jsonl_to_stdout() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("1.fec");
+    let mut sa = vec![""; 45];
+    sa[0] = "SA11AI";
+    sa[1] = "C00000001";
+    sa[2] = "SA.1";
+    sa[19] = "20240115";
+    sa[20] = "250.5";
+    let contents = [
+        "HDR\u{1c}FEC\u{1c}8.4\u{1c}libfec\u{1c}0.1\u{1c}\u{1c}\u{1c}".to_owned(),
+        "F3XN\u{1c}C00000001\u{1c}Test PAC".to_owned(),
+        sa.join("\u{1c}"),
+    ]
+    .join("\n");
+    fs::write(&path, contents).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_libfec"))
+        .args(["export", path.to_str().unwrap(), "-f", "jsonl", "-o", "-"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let rows: Vec<serde_json::Value> = stdout
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0]["filing_id"], "1");
+    assert_eq!(rows[0]["transaction_id"], "SA.1");
+    assert_eq!(rows[0]["contribution_date"], "2024-01-15");
+    assert!(stdout.contains("\"contribution_amount\":250.50,"));
+}
@@ -115,7 +115,7 @@ pub fn gen_column_names(_: TokenStream) -> TokenStream {
             let column_names: Vec<String> = item
                 .as_array()
                 .unwrap()
-                .into_iter()
+                .iter()
                 .map(|value| value.as_str().unwrap().to_owned())
                 .collect();
 
@@ -141,3 +141,140 @@ pub fn gen_column_names(_: TokenStream) -> TokenStream {
 
     output.into()
 }
+
+const SPEC_JSON_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/spec.json");
+
+fn read_spec() -> serde_json::Value {
+    let contents = std::fs::read_to_string(SPEC_JSON_PATH).expect("Unable to read spec.json");
+    serde_json::from_str(&contents).expect("JSON parsing error")
+}
+
+#[proc_macro]
+pub fn gen_spec_codes(_: TokenStream) -> TokenStream {
+    let json_data = read_spec();
+    let mut result = vec![];
+    for (name, codes) in json_data["codes"].as_object().unwrap().iter() {
+        let codes: Vec<&str> = codes
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|code| code.as_str().unwrap())
+            .collect();
+        result.push(quote! {
+          (#name, &[ #( #codes ),* ])
+        });
+    }
+
+    let output = quote! {
+      [
+          #( #result ),*
+      ]
+    };
+
+    output.into()
+}
+
+#[proc_macro]
+pub fn gen_spec_columns(_: TokenStream) -> TokenStream {
+    let json_data = read_spec();
+    let mut result = vec![];
+    for (column, format) in json_data["columns"].as_object().unwrap().iter() {
+        let format = format.as_str().unwrap();
+        result.push(quote! {
+          (#column, #format)
+        });
+    }
+
+    let output = quote! {
+      [
+          #( #result ),*
+      ]
+    };
+
+    output.into()
+}
+
+#[proc_macro]
+pub fn gen_spec_fields(_: TokenStream) -> TokenStream {
+    let json_data = read_spec();
+    let mut result = vec![];
+    for (form_type, versions) in json_data["forms"].as_object().unwrap().iter() {
+        for (version, fields) in versions.as_object().unwrap().iter() {
+            let mut field_specs = vec![];
+            for (column, spec) in fields.as_object().unwrap().iter() {
+                let required = spec["required"].as_bool().unwrap_or(false);
+                let max_length = spec["max_length"].as_u64().unwrap_or(0) as usize;
+                field_specs.push(quote! {
+                  (#column, #required, #max_length)
+                });
+            }
+            result.push(quote! {
+              (#form_type, #version, &[ #( #field_specs ),* ])
+            });
+        }
+    }
+
+    let output = quote! {
+      [
+          #( #result ),*
+      ]
+    };
+
+    output.into()
+}
+
+const DESCRIPTIONS_JSON_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/descriptions.json");
+
+fn read_descriptions() -> serde_json::Value {
+    let contents =
+        std::fs::read_to_string(DESCRIPTIONS_JSON_PATH).expect("Unable to read descriptions.json");
+    serde_json::from_str(&contents).expect("JSON parsing error")
+}
+
+// ex gen_descriptions!(forms) -> [("^sa", "Schedule A, itemized receipts"), ...]
+#[proc_macro]
+pub fn gen_descriptions(input: TokenStream) -> TokenStream {
+    let key = input.to_string();
+    let json_data = read_descriptions();
+    let mut result = vec![];
+    for (name, description) in json_data[key.as_str()]
+        .as_object()
+        .expect("unknown descriptions key")
+        .iter()
+    {
+        let description = description.as_str().unwrap();
+        result.push(quote! {
+          (#name, #description)
+        });
+    }
+
+    let output = quote! {
+      [
+          #( #result ),*
+      ]
+    };
+
+    output.into()
+}
+
+#[proc_macro]
+pub fn gen_line_descriptions(_: TokenStream) -> TokenStream {
+    let json_data = read_descriptions();
+    let mut result = vec![];
+    for (form, lines) in json_data["lines"].as_object().unwrap().iter() {
+        for (row_type, description) in lines.as_object().unwrap().iter() {
+            let description = description.as_str().unwrap();
+            result.push(quote! {
+              (#form, #row_type, #description)
+            });
+        }
+    }
+
+    let output = quote! {
+      [
+          #( #result ),*
+      ]
+    };
+
+    output.into()
+}
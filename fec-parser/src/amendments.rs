@@ -0,0 +1,422 @@
+use crate::{
+    codes::TransactionAmendment, transactions::TRANSACTION_ID_COLUMNS, Filing, FilingRow,
+    FilingRowReadError,
+};
+use indexmap::IndexMap;
+use std::{
+    collections::{HashMap, HashSet},
+    io::Read,
+};
+
+// The filing an amendment amends, from the header's report_id, ex
+// "FEC-1234567" -> "1234567"
+pub fn amended_filing_id(report_id: &str) -> Option<String> {
+    let id = report_id
+        .trim()
+        .strip_prefix("FEC-")
+        .or_else(|| report_id.trim().strip_prefix("FEC"))?;
+    (!id.is_empty() && id.bytes().all(|b| b.is_ascii_digit())).then(|| id.to_owned())
+}
+
+#[derive(Debug, Clone)]
+struct ChainFiling {
+    amends: Option<String>,
+    amendment_number: Option<u32>,
+}
+
+// Chains of original -> amendment 1 -> amendment 2 ... across a set of
+// filings. Amendments point at the original filing, or sometimes at the
+// amendment before them, so every filing is resolved to the root of its
+// chain first.
+#[derive(Debug, Clone, Default)]
+pub struct AmendmentChains {
+    filings: HashMap<String, ChainFiling>,
+    // the latest filing of each chain, by the chain's root
+    latest: HashMap<String, String>,
+}
+
+impl AmendmentChains {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // `report_id` and `report_number` as found in the filing's header
+    pub fn insert(
+        &mut self,
+        filing_id: &str,
+        report_id: Option<&str>,
+        report_number: Option<&str>,
+    ) {
+        let amends = report_id
+            .and_then(amended_filing_id)
+            .filter(|amends| amends != filing_id);
+        let previous = self.filings.insert(
+            filing_id.to_owned(),
+            ChainFiling {
+                amends,
+                amendment_number: report_number.and_then(|v| v.trim().parse().ok()),
+            },
+        );
+        if previous.is_some() {
+            // the filing may have moved to another chain
+            self.latest.clear();
+            let ids: Vec<String> = self.filings.keys().cloned().collect();
+            for id in ids {
+                self.update_latest(&id);
+            }
+            return;
+        }
+        // a chain rooted at this filing, from amendments inserted before it,
+        // now belongs to this filing's chain
+        if let Some(latest) = self.latest.remove(filing_id) {
+            self.update_latest(&latest);
+        }
+        self.update_latest(filing_id);
+    }
+
+    fn update_latest(&mut self, filing_id: &str) {
+        let root = self.root(filing_id).to_owned();
+        let is_later = match self.latest.get(&root) {
+            Some(latest) => self.sort_key(filing_id) > self.sort_key(latest),
+            None => true,
+        };
+        if is_later {
+            self.latest.insert(root, filing_id.to_owned());
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.filings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.filings.is_empty()
+    }
+
+    // the original filing of the chain, which may not be in the set
+    pub fn root<'a>(&'a self, filing_id: &'a str) -> &'a str {
+        let mut current = filing_id;
+        let mut seen = HashSet::new();
+        while let Some(amends) = self
+            .filings
+            .get(current)
+            .and_then(|filing| filing.amends.as_deref())
+        {
+            if !seen.insert(current) {
+                // a cycle, which any member resolves to the same way
+                return seen.into_iter().min().unwrap();
+            }
+            current = amends;
+        }
+        current
+    }
+
+    fn sort_key<'a>(&self, filing_id: &'a str) -> (bool, u32, u64, &'a str) {
+        let filing = &self.filings[filing_id];
+        (
+            filing.amends.is_some(),
+            filing.amendment_number.unwrap_or(0),
+            filing_id.parse::<u64>().unwrap_or(0),
+            filing_id,
+        )
+    }
+
+    fn sort_chain(&self, chain: &mut [&str]) {
+        chain.sort_by_key(|id| self.sort_key(id));
+    }
+
+    // every known filing of the chain, oldest first
+    pub fn chain(&self, filing_id: &str) -> Vec<&str> {
+        let root = self.root(filing_id);
+        let mut chain: Vec<&str> = self
+            .filings
+            .keys()
+            .map(|id| id.as_str())
+            .filter(|id| self.root(id) == root)
+            .collect();
+        self.sort_chain(&mut chain);
+        chain
+    }
+
+    // the current version of the report a filing belongs to
+    pub fn latest(&self, filing_id: &str) -> Option<&str> {
+        self.latest
+            .get(self.root(filing_id))
+            .map(|latest| latest.as_str())
+    }
+
+    pub fn superseded_by(&self, filing_id: &str) -> Option<&str> {
+        self.latest(filing_id).filter(|latest| *latest != filing_id)
+    }
+
+    pub fn is_latest(&self, filing_id: &str) -> bool {
+        self.filings.contains_key(filing_id) && self.superseded_by(filing_id).is_none()
+    }
+
+    // chains with more than one known filing, oldest first
+    pub fn chains(&self) -> Vec<Vec<&str>> {
+        let mut by_root: HashMap<&str, Vec<&str>> = HashMap::new();
+        for id in self.filings.keys() {
+            by_root.entry(self.root(id)).or_default().push(id);
+        }
+        let mut chains: Vec<Vec<&str>> = by_root
+            .into_values()
+            .filter(|chain| chain.len() > 1)
+            .map(|mut chain| {
+                self.sort_chain(&mut chain);
+                chain
+            })
+            .collect();
+        chains.sort();
+        chains
+    }
+}
+
+// An itemization in the merged result, and the filing it was last taken from
+pub struct MergedRow {
+    pub filing_id: String,
+    pub row: FilingRow,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MergeStats {
+    pub added: usize,
+    pub changed: usize,
+    pub deleted: usize,
+    // whether the filing replaced every earlier row, see `apply_filing`
+    pub replaced: bool,
+}
+
+fn transaction_id(row: &FilingRow) -> Option<&str> {
+    TRANSACTION_ID_COLUMNS
+        .iter()
+        .find_map(|column| row.get(column))
+        .map(|v| v.trim())
+        .filter(|v| !v.is_empty())
+}
+
+// Applies a sequence of amendments to an original filing, keyed by
+// transaction ID, to get the effective set of itemizations
+#[derive(Default)]
+pub struct TransactionMerger {
+    rows: IndexMap<String, MergedRow>,
+}
+
+impl TransactionMerger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Filings must be applied oldest first, ex in `AmendmentChains::chain`
+    // order. Pre-6.0 amendments only carry the changed transactions, marked
+    // with an `amended_cd` of A, C or D. Later amendments are a complete copy
+    // of the report, so they replace every earlier row.
+    pub fn apply_filing<R: Read>(
+        &mut self,
+        filing: &mut Filing<R>,
+    ) -> Result<MergeStats, FilingRowReadError> {
+        let mut rows = vec![];
+        while let Some(row) = filing.next_row() {
+            rows.push(row?);
+        }
+        Ok(self.apply_rows(&filing.filing_id, rows))
+    }
+
+    // like `apply_filing`, with every row of the filing already read
+    pub fn apply_rows(&mut self, filing_id: &str, rows: Vec<FilingRow>) -> MergeStats {
+        let incremental = rows
+            .iter()
+            .any(|row| row.get("amended_cd").is_some_and(|v| !v.trim().is_empty()));
+        let mut stats = MergeStats::default();
+        if !incremental && !self.rows.is_empty() {
+            self.rows.clear();
+            stats.replaced = true;
+        }
+
+        for (idx, row) in rows.into_iter().enumerate() {
+            let key = match transaction_id(&row) {
+                Some(transaction_id) => transaction_id.to_owned(),
+                None => format!("{filing_id}:{idx}"),
+            };
+            let amendment = row
+                .get("amended_cd")
+                .and_then(TransactionAmendment::from_code);
+            if amendment == Some(TransactionAmendment::Delete) {
+                if self.rows.shift_remove(&key).is_some() {
+                    stats.deleted += 1;
+                }
+                continue;
+            }
+            let merged = MergedRow {
+                filing_id: filing_id.to_owned(),
+                row,
+            };
+            match self.rows.insert(key, merged) {
+                Some(_) => stats.changed += 1,
+                None => stats.added += 1,
+            }
+        }
+        stats
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    pub fn get(&self, transaction_id: &str) -> Option<&MergedRow> {
+        self.rows.get(transaction_id)
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &MergedRow> {
+        self.rows.values()
+    }
+
+    pub fn into_rows(self) -> impl Iterator<Item = MergedRow> {
+        self.rows.into_values()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{legacy_record, test_filing, test_legacy_filing, F3XN};
+
+    #[test]
+    fn amendment_chains() {
+        assert_eq!(amended_filing_id("FEC-1234"), Some("1234".to_owned()));
+        assert_eq!(amended_filing_id(" FEC1234 "), Some("1234".to_owned()));
+        assert_eq!(amended_filing_id("FEC-"), None);
+        assert_eq!(amended_filing_id("1234"), None);
+
+        let mut chains = AmendmentChains::new();
+        chains.insert("100", None, None);
+        chains.insert("300", Some("FEC-100"), Some("2"));
+        chains.insert("200", Some("FEC-100"), Some("1"));
+        // points at the previous amendment instead of the original
+        chains.insert("400", Some("FEC-300"), Some("3"));
+        chains.insert("500", None, None);
+        // the original isn't in the set
+        chains.insert("700", Some("FEC-600"), Some("1"));
+
+        assert_eq!(chains.root("400"), "100");
+        assert_eq!(chains.chain("200"), vec!["100", "200", "300", "400"]);
+        assert_eq!(chains.latest("100"), Some("400"));
+        assert_eq!(chains.superseded_by("200"), Some("400"));
+        assert_eq!(chains.superseded_by("400"), None);
+        assert!(chains.is_latest("500"));
+        assert!(chains.is_latest("700"));
+        assert!(!chains.is_latest("600"));
+        assert_eq!(chains.chains(), vec![vec!["100", "200", "300", "400"]]);
+
+        // cycles don't loop forever
+        let mut chains = AmendmentChains::new();
+        chains.insert("1", Some("FEC-2"), None);
+        chains.insert("2", Some("FEC-1"), None);
+        assert_eq!(chains.chain("1").len(), 2);
+        assert_eq!(chains.latest("1"), chains.latest("2"));
+
+        // the latest filings match the full chains in any insert order, ex an
+        // amendment of an amendment before the amendment it points at
+        let filings = [
+            ("400", Some("FEC-300"), Some("3")),
+            ("100", None, None),
+            ("300", Some("FEC-100"), Some("2")),
+            ("700", Some("FEC-600"), Some("1")),
+            ("200", Some("FEC-100"), Some("1")),
+            ("500", None, None),
+        ];
+        let mut chains = AmendmentChains::new();
+        for (filing_id, report_id, report_number) in filings {
+            chains.insert(filing_id, report_id, report_number);
+            for (filing_id, ..) in filings {
+                assert_eq!(
+                    chains.latest(filing_id),
+                    chains.chain(filing_id).last().copied()
+                );
+            }
+        }
+        assert_eq!(chains.latest("200"), Some("400"));
+        chains.insert("400", None, None);
+        assert_eq!(chains.latest("200"), Some("300"));
+        assert_eq!(chains.latest("400"), Some("400"));
+    }
+
+    fn sa(transaction_id: &'static str, amount: &'static str) -> Vec<&'static str> {
+        let mut sa = vec![""; 45];
+        sa[0] = "SA11AI";
+        sa[2] = transaction_id;
+        sa[20] = amount;
+        sa
+    }
+
+    #[test]
+    fn merges_amendments() {
+        let mut merger = TransactionMerger::new();
+        let original = test_filing(&[F3XN, &sa("SA.1", "100"), &sa("SA.2", "200")]);
+        let stats = merger.apply_filing(&mut { original }).unwrap();
+        assert_eq!(stats.added, 2);
+
+        // full replacement
+        let mut amendment = test_filing(&[F3XN, &sa("SA.1", "150"), &sa("SA.3", "300")]);
+        let stats = merger.apply_filing(&mut amendment).unwrap();
+        assert!(stats.replaced);
+        let amounts: Vec<_> = merger
+            .rows()
+            .map(|merged| merged.row.get("contribution_amount").unwrap())
+            .collect();
+        assert_eq!(amounts, vec!["150", "300"]);
+        assert!(merger.get("SA.2").is_none());
+    }
+
+    #[test]
+    fn applies_amended_cd() {
+        // 5.3 amendments only list the changed transactions
+        let f3x = legacy_record(
+            "F3XA",
+            &[
+                ("filer_committee_id_number", "C00000001"),
+                ("committee_name", "Test Committee"),
+            ],
+        );
+        let sa = |transaction_id, amended_cd| {
+            legacy_record(
+                "SA11A1",
+                &[
+                    ("transaction_id", transaction_id),
+                    ("amended_cd", amended_cd),
+                ],
+            )
+        };
+        let mut merger = TransactionMerger::new();
+        let mut original =
+            test_legacy_filing(&[&f3x, &sa("SA.1", ""), &sa("SA.2", ""), &sa("SA.3", "")]);
+        merger.apply_filing(&mut original).unwrap();
+        let mut amendment =
+            test_legacy_filing(&[&f3x, &sa("SA.1", "D"), &sa("SA.2", "C"), &sa("SA.4", "A")]);
+        amendment.filing_id = "2".to_owned();
+        let stats = merger.apply_filing(&mut amendment).unwrap();
+        assert_eq!(
+            stats,
+            MergeStats {
+                added: 1,
+                changed: 1,
+                deleted: 1,
+                replaced: false
+            }
+        );
+        let rows: Vec<_> = merger
+            .rows()
+            .map(|merged| {
+                (
+                    merged.filing_id.as_str(),
+                    transaction_id(&merged.row).unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(rows, vec![("2", "SA.2"), ("1", "SA.3"), ("2", "SA.4")]);
+    }
+}
@@ -0,0 +1,284 @@
+use crate::{
+    mappings::{column_type, field_idx, ColumnType},
+    parse_amount, FecDate, Filing, FilingRow, FilingRowReadError, AMOUNT_PRECISION, AMOUNT_SCALE,
+};
+use arrow_array::{
+    builder::{ArrayBuilder, Date32Builder, Decimal128Builder, StringBuilder},
+    ArrayRef, RecordBatch,
+};
+use arrow_schema::{ArrowError, DataType, Field, Schema, SchemaRef};
+use std::{io::Read, sync::Arc};
+use thiserror::Error;
+
+pub const DEFAULT_BATCH_SIZE: usize = 8192;
+
+#[derive(Error, Debug)]
+pub enum ArrowBatchError {
+    #[error("Unknown form type `{0}`")]
+    UnknownFormType(String),
+    #[error("Error reading row: {0}")]
+    Row(#[from] FilingRowReadError),
+    #[error("Error building record batch: {0}")]
+    Arrow(#[from] ArrowError),
+}
+
+pub fn data_type(column: &str) -> DataType {
+    match column_type(column) {
+        ColumnType::Date => DataType::Date32,
+        ColumnType::Float => DataType::Decimal128(AMOUNT_PRECISION, AMOUNT_SCALE),
+        ColumnType::Text => DataType::Utf8,
+    }
+}
+
+// A filing_id column, followed by the given columns. Every column is
+// nullable, empty or invalid values are null.
+pub fn schema(columns: &[String]) -> Schema {
+    let fields: Vec<Field> = std::iter::once(Field::new("filing_id", DataType::Utf8, false))
+        .chain(
+            columns
+                .iter()
+                .map(|column| Field::new(column, data_type(column), true)),
+        )
+        .collect();
+    Schema::new(fields)
+}
+
+enum ColumnBuilder {
+    Text(StringBuilder),
+    Date(Date32Builder),
+    Amount(Decimal128Builder),
+}
+
+impl ColumnBuilder {
+    fn new(column: &str, capacity: usize) -> Self {
+        match column_type(column) {
+            ColumnType::Date => Self::Date(Date32Builder::with_capacity(capacity)),
+            ColumnType::Float => Self::Amount(
+                Decimal128Builder::with_capacity(capacity)
+                    .with_precision_and_scale(AMOUNT_PRECISION, AMOUNT_SCALE)
+                    .unwrap(),
+            ),
+            ColumnType::Text => Self::Text(StringBuilder::with_capacity(capacity, capacity * 8)),
+        }
+    }
+
+    fn append(&mut self, value: Option<&str>) {
+        let value = value.filter(|v| !v.trim().is_empty());
+        match self {
+            Self::Text(builder) => builder.append_option(value),
+            Self::Date(builder) => builder.append_option(
+                value
+                    .and_then(|v| FecDate::parse(v.trim()))
+                    .map(|date| date.days_since_epoch()),
+            ),
+            Self::Amount(builder) => builder.append_option(value.and_then(parse_amount)),
+        }
+    }
+
+    fn finish(&mut self) -> ArrayRef {
+        match self {
+            Self::Text(builder) => Arc::new(builder.finish()),
+            Self::Date(builder) => Arc::new(builder.finish()),
+            Self::Amount(builder) => Arc::new(builder.finish()),
+        }
+    }
+}
+
+// Collects rows with the same columns into record batches of
+// `batch_size` rows, typed by `data_type`.
+pub struct RecordBatchBuilder {
+    schema: SchemaRef,
+    filing_id: StringBuilder,
+    columns: Vec<ColumnBuilder>,
+    batch_size: usize,
+}
+
+impl RecordBatchBuilder {
+    pub fn new(columns: &[String]) -> Self {
+        Self::with_batch_size(columns, DEFAULT_BATCH_SIZE)
+    }
+
+    pub fn with_batch_size(columns: &[String], batch_size: usize) -> Self {
+        let batch_size = batch_size.max(1);
+        Self {
+            schema: Arc::new(schema(columns)),
+            filing_id: StringBuilder::with_capacity(batch_size, batch_size * 8),
+            columns: columns
+                .iter()
+                .map(|column| ColumnBuilder::new(column, batch_size))
+                .collect(),
+            batch_size,
+        }
+    }
+
+    pub fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    pub fn len(&self) -> usize {
+        self.filing_id.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Fields past the builder's columns are dropped, missing ones are null.
+    // Returns a batch once `batch_size` rows have been added.
+    pub fn push(
+        &mut self,
+        filing_id: &str,
+        row: &FilingRow,
+    ) -> Result<Option<RecordBatch>, ArrowError> {
+        self.push_values(filing_id, &row.record)
+    }
+
+    // Like `push`, for values that aren't a row of the builder's columns
+    // as-is, ex a row mapped onto a canonical schedule
+    pub fn push_values<'a>(
+        &mut self,
+        filing_id: &str,
+        values: impl IntoIterator<Item = &'a str>,
+    ) -> Result<Option<RecordBatch>, ArrowError> {
+        self.filing_id.append_value(filing_id);
+        let mut values = values.into_iter();
+        for column in self.columns.iter_mut() {
+            column.append(values.next());
+        }
+        if self.len() >= self.batch_size {
+            return self.finish();
+        }
+        Ok(None)
+    }
+
+    // the rows added since the last batch, if any
+    pub fn finish(&mut self) -> Result<Option<RecordBatch>, ArrowError> {
+        if self.is_empty() {
+            return Ok(None);
+        }
+        let columns: Vec<ArrayRef> = std::iter::once(Arc::new(self.filing_id.finish()) as ArrayRef)
+            .chain(self.columns.iter_mut().map(|column| column.finish()))
+            .collect();
+        RecordBatch::try_new(self.schema.clone(), columns).map(Some)
+    }
+}
+
+// Record batches of every row of a form type in a filing, ex "SA" for
+// every Schedule A row type, which share the same columns. Other rows are
+// skipped.
+pub struct FormTypeBatches<'a, R: Read> {
+    filing: &'a mut Filing<R>,
+    form_type: usize,
+    batch_size: usize,
+    builder: Option<RecordBatchBuilder>,
+    done: bool,
+}
+
+impl<'a, R: Read> FormTypeBatches<'a, R> {
+    pub fn new(
+        filing: &'a mut Filing<R>,
+        form_type: &str,
+        batch_size: usize,
+    ) -> Result<Self, ArrowBatchError> {
+        let form_type = field_idx(form_type)
+            .ok_or_else(|| ArrowBatchError::UnknownFormType(form_type.to_owned()))?;
+        Ok(Self {
+            filing,
+            form_type,
+            batch_size,
+            builder: None,
+            done: false,
+        })
+    }
+
+    fn next_batch(&mut self) -> Result<Option<RecordBatch>, ArrowBatchError> {
+        while let Some(row) = self.filing.next_row() {
+            let row = row?;
+            if row.record.get(0).and_then(field_idx) != Some(self.form_type) {
+                continue;
+            }
+            let builder = self.builder.get_or_insert_with(|| {
+                RecordBatchBuilder::with_batch_size(row.columns(), self.batch_size)
+            });
+            if let Some(batch) = builder.push(&self.filing.filing_id, &row)? {
+                return Ok(Some(batch));
+            }
+        }
+        self.done = true;
+        match &mut self.builder {
+            Some(builder) => Ok(builder.finish()?),
+            None => Ok(None),
+        }
+    }
+}
+
+impl<R: Read> Iterator for FormTypeBatches<'_, R> {
+    type Item = Result<RecordBatch, ArrowBatchError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        self.next_batch().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{test_filing, F3XN};
+    use arrow_array::{cast::AsArray, types::Date32Type, Array};
+
+    #[test]
+    fn record_batches() {
+        assert_eq!(FecDate::parse("19700101").unwrap().days_since_epoch(), 0);
+        assert_eq!(
+            FecDate::parse("20240229").unwrap().days_since_epoch(),
+            19782
+        );
+
+        let sa = |transaction_id: &'static str, date: &'static str, amount: &'static str| {
+            let mut sa = vec![""; 45];
+            sa[0] = "SA11AI";
+            sa[2] = transaction_id;
+            sa[19] = date;
+            sa[20] = amount;
+            sa
+        };
+        let mut sb = vec![""; 45];
+        sb[0] = "SB21B";
+        let mut filing = test_filing(&[
+            F3XN,
+            &sa("SA.1", "20240101", "100"),
+            &sb,
+            &sa("SA.2", "bad", "250.50"),
+            &sa("SA.3", "", ""),
+        ]);
+        let batches: Vec<RecordBatch> = FormTypeBatches::new(&mut filing, "SA", 2)
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            batches.iter().map(|b| b.num_rows()).collect::<Vec<_>>(),
+            vec![2, 1]
+        );
+        let schema = batches[0].schema();
+        assert_eq!(schema.field(0).name(), "filing_id");
+        let idx = |name: &str| schema.index_of(name).unwrap();
+        assert_eq!(
+            schema.field(idx("contribution_date")).data_type(),
+            &DataType::Date32
+        );
+
+        let dates = batches[0]
+            .column(idx("contribution_date"))
+            .as_primitive::<Date32Type>();
+        assert_eq!(dates.value(0), 19723);
+        assert!(dates.is_null(1));
+        let amounts = batches[0]
+            .column(idx("contribution_amount"))
+            .as_primitive::<arrow_array::types::Decimal128Type>();
+        assert_eq!(amounts.value(1), 25050);
+        assert!(batches[1].column(idx("contribution_amount")).is_null(0));
+    }
+}
@@ -0,0 +1,386 @@
+use crate::{
+    mappings::{column_names_for_field, field_idx, FORM_TYPES},
+    names::PersonName,
+};
+use csv::StringRecord;
+use std::borrow::Cow;
+
+// Every schedule is consolidated into the column layout of the latest
+// version, and rows from older versions are mapped onto it.
+pub const CANONICAL_VERSION: &str = "8.4";
+
+// Default separator between last and first names in pre-6.0 filings, which
+// can be overridden with the header's `name_delim`.
+pub const DEFAULT_NAME_DELIM: &str = "^";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Schedule {
+    A,
+    B,
+    C,
+    C1,
+    C2,
+    D,
+    E,
+    F,
+}
+
+pub static SCHEDULES: &[Schedule] = &[
+    Schedule::A,
+    Schedule::B,
+    Schedule::C,
+    Schedule::C1,
+    Schedule::C2,
+    Schedule::D,
+    Schedule::E,
+    Schedule::F,
+];
+
+impl Schedule {
+    // ex "SA11AI" -> A, "SC/10" -> C, "SC1/10" -> C1
+    pub fn from_row_type(row_type: &str) -> Option<Self> {
+        match *FORM_TYPES.get(field_idx(row_type)?)? {
+            "^sa" => Some(Self::A),
+            "^sb" => Some(Self::B),
+            "^sc[^1-2]" => Some(Self::C),
+            "^sc1" => Some(Self::C1),
+            "^sc2" => Some(Self::C2),
+            "^sd" => Some(Self::D),
+            "^se" => Some(Self::E),
+            "^sf" => Some(Self::F),
+            _ => None,
+        }
+    }
+
+    // a row type that resolves to this schedule's mapping
+    fn form_type(&self) -> &'static str {
+        match self {
+            Self::A => "SA",
+            Self::B => "SB",
+            Self::C => "SC/10",
+            Self::C1 => "SC1",
+            Self::C2 => "SC2",
+            Self::D => "SD",
+            Self::E => "SE",
+            Self::F => "SF",
+        }
+    }
+
+    pub fn table_name(&self) -> &'static str {
+        match self {
+            Self::A => "schedule_a",
+            Self::B => "schedule_b",
+            Self::C => "schedule_c",
+            Self::C1 => "schedule_c1",
+            Self::C2 => "schedule_c2",
+            Self::D => "schedule_d",
+            Self::E => "schedule_e",
+            Self::F => "schedule_f",
+        }
+    }
+
+    pub fn columns(&self) -> &'static [String] {
+        column_names_for_field(self.form_type(), CANONICAL_VERSION).unwrap()
+    }
+}
+
+// Single name fields from pre-6.0 filings, and the prefix of the structured
+// name fields that replaced them, ex "contributor_name" ->
+// "contributor_last_name", "contributor_first_name", ... Some were renamed
+// along the way, ex Schedule E's "ind_name_as_signed" -> "completing_*".
+static NAME_COLUMNS: &[(&str, &str)] = &[
+    ("contributor_name", "contributor"),
+    ("payee_name", "payee"),
+    ("lender_name", "lender"),
+    ("guarantor_name", "guarantor"),
+    ("creditor_name", "creditor"),
+    ("treasurer_name", "treasurer"),
+    ("authorized_name", "authorized"),
+    ("candidate_name", "candidate"),
+    ("donor_candidate_name", "donor_candidate"),
+    ("beneficiary_candidate_name", "beneficiary_candidate"),
+    ("lender_candidate_name", "lender_candidate"),
+    ("payee_candidate_name", "payee_candidate"),
+    ("ind_name_as_signed", "completing"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NamePart {
+    Organization,
+    Last,
+    First,
+    Middle,
+    Prefix,
+    Suffix,
+}
+
+fn name_part(column: &str, prefix: &str) -> Option<NamePart> {
+    match column.strip_prefix(prefix)?.strip_prefix('_')? {
+        "organization_name" => Some(NamePart::Organization),
+        "last_name" => Some(NamePart::Last),
+        "first_name" => Some(NamePart::First),
+        "middle_name" | "middle_nm" => Some(NamePart::Middle),
+        "prefix" => Some(NamePart::Prefix),
+        "suffix" => Some(NamePart::Suffix),
+        _ => None,
+    }
+}
+
+// Individuals are split into name parts, anything else is kept whole as an
+// organization name. Without an entity type, a delimited name is assumed to
+// be a person.
+fn is_individual(entity_type: &str, name: &str, delim: &str) -> bool {
+    match entity_type.trim() {
+        "IND" | "CAN" => true,
+        "" => name.contains(delim),
+        _ => false,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Source {
+    Column(usize),
+    // the legacy name field, and the structured field if the version has
+    // both, ex 5.3
+    Name {
+        name: usize,
+        part: NamePart,
+        column: Option<usize>,
+        // whether the schedule has an organization name for this prefix,
+        // otherwise the name is always a person's, ex "completing_*"
+        has_organization: bool,
+    },
+    Missing,
+}
+
+// Maps records of one row type and version onto its schedule's canonical
+// columns. Build one per (row type, version) and re-use it for every row.
+// Fields without an 8.4 equivalent are lost, for Schedule A that's
+// `amended_cd` (3.x-5.x), `contribution_purpose_code` (before 8.0),
+// `increased_limit_code` (5.x-6.3) and `image_number` (paper filings). The
+// conduit fields kept their names, so they are mapped in every version.
+#[derive(Debug, Clone)]
+pub struct CanonicalMapping {
+    schedule: Schedule,
+    sources: Vec<Source>,
+    entity_type: Option<usize>,
+    name_delim: String,
+}
+
+impl CanonicalMapping {
+    pub fn new(schedule: Schedule, columns: &[String]) -> Self {
+        let position = |name: &str| columns.iter().position(|v| v == name);
+        let sources = schedule
+            .columns()
+            .iter()
+            .map(|canonical| {
+                let idx = position(canonical);
+                NAME_COLUMNS
+                    .iter()
+                    .find_map(|(name, prefix)| {
+                        Some(Source::Name {
+                            name: position(name)?,
+                            part: name_part(canonical, prefix)?,
+                            column: idx,
+                            has_organization: schedule
+                                .columns()
+                                .contains(&format!("{prefix}_organization_name")),
+                        })
+                    })
+                    .or(idx.map(Source::Column))
+                    .unwrap_or(Source::Missing)
+            })
+            .collect();
+        Self {
+            schedule,
+            sources,
+            entity_type: position("entity_type"),
+            name_delim: DEFAULT_NAME_DELIM.to_owned(),
+        }
+    }
+
+    pub fn for_field(row_type: &str, fec_version: &str) -> Option<Self> {
+        let schedule = Schedule::from_row_type(row_type)?;
+        let columns = column_names_for_field(row_type, fec_version)?;
+        Some(Self::new(schedule, columns))
+    }
+
+    pub fn with_name_delim(mut self, name_delim: &str) -> Self {
+        if !name_delim.is_empty() {
+            self.name_delim = name_delim.to_owned();
+        }
+        self
+    }
+
+    pub fn schedule(&self) -> Schedule {
+        self.schedule
+    }
+
+    // values in the order of `Schedule::columns()`, empty when the record's
+    // version has no equivalent field
+    pub fn map<'a>(&self, record: &'a StringRecord) -> Vec<Cow<'a, str>> {
+        let entity_type = self
+            .entity_type
+            .and_then(|idx| record.get(idx))
+            .unwrap_or("");
+        self.sources
+            .iter()
+            .map(|source| match *source {
+                Source::Column(idx) => Cow::Borrowed(record.get(idx).unwrap_or("")),
+                Source::Name {
+                    column: Some(idx), ..
+                } if record.get(idx).is_some_and(|v| !v.is_empty()) => {
+                    Cow::Borrowed(record.get(idx).unwrap())
+                }
+                Source::Name {
+                    name,
+                    part,
+                    has_organization,
+                    ..
+                } => {
+                    let name = record.get(name).unwrap_or("");
+                    let individual =
+                        !has_organization || is_individual(entity_type, name, &self.name_delim);
+                    match (part, individual) {
+                        (NamePart::Organization, false) => Cow::Borrowed(name.trim()),
+                        (NamePart::Organization, true) | (_, false) => Cow::Borrowed(""),
+                        (part, true) => {
+                            let name = PersonName::parse_delimited(name, &self.name_delim);
+                            Cow::Owned(
+                                match part {
+                                    NamePart::Last => name.last,
+                                    NamePart::First => name.first,
+                                    NamePart::Middle => name.middle,
+                                    NamePart::Prefix => name.prefix,
+                                    NamePart::Suffix => name.suffix,
+                                    NamePart::Organization => None,
+                                }
+                                .unwrap_or_default(),
+                            )
+                        }
+                    }
+                }
+                Source::Missing => Cow::Borrowed(""),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mappings::schemas_for_field;
+
+    #[test]
+    fn canonical_mapping() {
+        assert_eq!(Schedule::from_row_type("SA11AI"), Some(Schedule::A));
+        assert_eq!(Schedule::from_row_type("SC/10"), Some(Schedule::C));
+        assert_eq!(Schedule::from_row_type("SC1/10"), Some(Schedule::C1));
+        assert_eq!(Schedule::from_row_type("F3XN"), None);
+        for schedule in SCHEDULES {
+            assert!(!schedule.columns().is_empty());
+        }
+
+        let columns = Schedule::A.columns();
+        let idx = |name: &str| columns.iter().position(|v| v == name).unwrap();
+
+        // 8.4 rows map onto themselves
+        let mapping = CanonicalMapping::for_field("SA11AI", "8.4").unwrap();
+        let mut sa = vec![""; 45];
+        sa[0] = "SA11AI";
+        sa[7] = "Doe";
+        let record = StringRecord::from(sa);
+        let values = mapping.map(&record);
+        assert_eq!(values.len(), columns.len());
+        assert_eq!(values[idx("contributor_last_name")], "Doe");
+
+        // 5.3 rows have a single delimited name, and other columns moved
+        let mapping = CanonicalMapping::for_field("SA11A1", "5.3").unwrap();
+        let legacy = column_names_for_field("SA11A1", "5.3").unwrap();
+        let mut sa = vec![""; legacy.len()];
+        let mut set = |name: &str, value| {
+            sa[legacy.iter().position(|v| v == name).unwrap()] = value;
+        };
+        set("form_type", "SA11A1");
+        set("entity_type", "IND");
+        set("contributor_name", "Kellner^Lawrence J");
+        set("contribution_amount", "5000.00");
+        set("transaction_id", "SA11A1.7430");
+        let record = StringRecord::from(sa.clone());
+        let values = mapping.map(&record);
+        assert_eq!(values[idx("contributor_last_name")], "Kellner");
+        assert_eq!(values[idx("contributor_first_name")], "Lawrence");
+        assert_eq!(values[idx("contributor_middle_name")], "J");
+        assert_eq!(values[idx("contributor_organization_name")], "");
+        assert_eq!(values[idx("contribution_amount")], "5000.00");
+        assert_eq!(values[idx("transaction_id")], "SA11A1.7430");
+        assert_eq!(values[idx("donor_committee_name")], "");
+
+        let entity_type = legacy.iter().position(|v| v == "entity_type").unwrap();
+        let name = legacy.iter().position(|v| v == "contributor_name").unwrap();
+        sa[entity_type] = "ORG";
+        sa[name] = "Continental Airlines, Inc.";
+        let record = StringRecord::from(sa);
+        let values = mapping.map(&record);
+        assert_eq!(
+            values[idx("contributor_organization_name")],
+            "Continental Airlines, Inc."
+        );
+        assert_eq!(values[idx("contributor_last_name")], "");
+
+        // renamed name fields, with an irregular "middle_nm" suffix
+        let mapping = CanonicalMapping::for_field("SE", "5.3").unwrap();
+        let legacy = column_names_for_field("SE", "5.3").unwrap();
+        let mut se = vec![""; legacy.len()];
+        se[legacy
+            .iter()
+            .position(|v| v == "ind_name_as_signed")
+            .unwrap()] = "Smith^Jane";
+        let columns = Schedule::E.columns();
+        let record = StringRecord::from(se);
+        let values = mapping.map(&record);
+        let idx = |name: &str| columns.iter().position(|v| v == name).unwrap();
+        assert_eq!(values[idx("completing_last_name")], "Smith");
+        assert_eq!(values[idx("completing_first_name")], "Jane");
+
+        let mapping = CanonicalMapping::for_field("SC/10", "5.3").unwrap();
+        let idx = Schedule::C
+            .columns()
+            .iter()
+            .position(|v| v == "lender_candidate_middle_nm")
+            .unwrap();
+        assert!(matches!(
+            mapping.sources[idx],
+            Source::Name {
+                part: NamePart::Middle,
+                column: None,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn schedule_a_losses() {
+        // keep the list in CanonicalMapping's comment up to date
+        let columns = Schedule::A.columns();
+        let mut lost: Vec<&str> = schemas_for_field("SA")
+            .iter()
+            .flat_map(|schema| schema.columns.iter().map(|column| column.name))
+            .filter(|name| {
+                !columns.iter().any(|column| column == name)
+                    && !NAME_COLUMNS.iter().any(|(column, _)| column == name)
+            })
+            .collect();
+        lost.sort();
+        lost.dedup();
+        assert_eq!(
+            lost,
+            [
+                "amended_cd",
+                "contribution_purpose_code",
+                "image_number",
+                "increased_limit_code"
+            ]
+        );
+    }
+}
@@ -0,0 +1,306 @@
+// Decoders for the coded values that appear in itemizations and cover
+// records, in the same spirit as `report_code_label`.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntityType {
+    Candidate,
+    CandidateCommittee,
+    Committee,
+    Individual,
+    Organization,
+    PoliticalActionCommittee,
+    PartyOrganization,
+}
+
+impl EntityType {
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code.trim().to_ascii_uppercase().as_str() {
+            "CAN" => Some(Self::Candidate),
+            "CCM" => Some(Self::CandidateCommittee),
+            "COM" => Some(Self::Committee),
+            "IND" => Some(Self::Individual),
+            "ORG" => Some(Self::Organization),
+            "PAC" => Some(Self::PoliticalActionCommittee),
+            "PTY" => Some(Self::PartyOrganization),
+            _ => None,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Candidate => "CAN",
+            Self::CandidateCommittee => "CCM",
+            Self::Committee => "COM",
+            Self::Individual => "IND",
+            Self::Organization => "ORG",
+            Self::PoliticalActionCommittee => "PAC",
+            Self::PartyOrganization => "PTY",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Candidate => "Candidate",
+            Self::CandidateCommittee => "Candidate Committee",
+            Self::Committee => "Committee",
+            Self::Individual => "Individual",
+            Self::Organization => "Organization",
+            Self::PoliticalActionCommittee => "Political Action Committee",
+            Self::PartyOrganization => "Party Organization",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ElectionType {
+    Primary,
+    General,
+    Runoff,
+    Special,
+    Convention,
+    Recount,
+    Other,
+}
+
+impl ElectionType {
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code.trim().to_ascii_uppercase().as_str() {
+            "P" => Some(Self::Primary),
+            "G" => Some(Self::General),
+            "R" => Some(Self::Runoff),
+            "S" => Some(Self::Special),
+            "C" => Some(Self::Convention),
+            "E" => Some(Self::Recount),
+            "O" => Some(Self::Other),
+            _ => None,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Primary => "P",
+            Self::General => "G",
+            Self::Runoff => "R",
+            Self::Special => "S",
+            Self::Convention => "C",
+            Self::Recount => "E",
+            Self::Other => "O",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Primary => "Primary",
+            Self::General => "General",
+            Self::Runoff => "Runoff",
+            Self::Special => "Special",
+            Self::Convention => "Convention",
+            Self::Recount => "Recount",
+            Self::Other => "Other",
+        }
+    }
+}
+
+// ex "P2024" -> Primary election in 2024. Older filings sometimes leave
+// out the year, ex "G".
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ElectionCode {
+    pub election_type: ElectionType,
+    pub year: Option<u16>,
+}
+
+impl ElectionCode {
+    pub fn parse(code: &str) -> Option<Self> {
+        let code = code.trim();
+        let election_type = ElectionType::from_code(code.get(0..1)?)?;
+        let year = match code.get(1..)? {
+            "" => None,
+            year if year.len() == 4 => Some(year.parse().ok()?),
+            _ => return None,
+        };
+        Some(Self {
+            election_type,
+            year,
+        })
+    }
+
+    pub fn label(&self) -> String {
+        match self.year {
+            Some(year) => format!("{} {}", self.election_type.label(), year),
+            None => self.election_type.label().to_owned(),
+        }
+    }
+}
+
+pub fn memo_code_label(code: &str) -> Option<&'static str> {
+    match code.trim().to_ascii_uppercase().as_str() {
+        "X" => Some("Memo entry, not included in totals"),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SupportOppose {
+    Support,
+    Oppose,
+}
+
+impl SupportOppose {
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code.trim().to_ascii_uppercase().as_str() {
+            "S" => Some(Self::Support),
+            "O" => Some(Self::Oppose),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Support => "Support",
+            Self::Oppose => "Oppose",
+        }
+    }
+}
+
+// disbursement category codes, used on Schedule B/E/F and Form 3X/3P
+pub fn category_code_label(code: &str) -> Option<&'static str> {
+    match code.trim() {
+        "001" => Some("Administrative/Salary/Overhead Expenses"),
+        "002" => Some("Travel Expenses"),
+        "003" => Some("Solicitation and Fundraising Expenses"),
+        "004" => Some("Advertising Expenses"),
+        "005" => Some("Polling Expenses"),
+        "006" => Some("Campaign Materials"),
+        "007" => Some("Campaign Event Expenses"),
+        "008" => Some("Transfers"),
+        "009" => Some("Loan Repayments"),
+        "010" => Some("Refunds of Contributions"),
+        "011" => Some("Political Contributions"),
+        "012" => Some("Donations"),
+        _ => None,
+    }
+}
+
+// The last letter of a cover record's form type, ex F3XN, F3XA, F3XT.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AmendmentIndicator {
+    New,
+    Amendment,
+    Termination,
+}
+
+impl AmendmentIndicator {
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code.trim().to_ascii_uppercase().as_str() {
+            "N" => Some(Self::New),
+            "A" => Some(Self::Amendment),
+            "T" => Some(Self::Termination),
+            _ => None,
+        }
+    }
+
+    pub fn from_form_type(form_type: &str) -> Option<Self> {
+        Self::from_code(form_type.get(form_type.len().checked_sub(1)?..)?)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::New => "New",
+            Self::Amendment => "Amendment",
+            Self::Termination => "Termination",
+        }
+    }
+}
+
+// Pre-6.0 itemizations in an amended filing mark what happened to each
+// transaction in their `amended_cd` column
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionAmendment {
+    Add,
+    Change,
+    Delete,
+}
+
+impl TransactionAmendment {
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code.trim().to_ascii_uppercase().as_str() {
+            "A" => Some(Self::Add),
+            "C" => Some(Self::Change),
+            "D" => Some(Self::Delete),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Add => "Added",
+            Self::Change => "Changed",
+            Self::Delete => "Deleted",
+        }
+    }
+}
+
+// columns that `code_label` knows how to decode
+pub static CODED_COLUMNS: &[&str] = &[
+    "entity_type",
+    "election_code",
+    "memo_code",
+    "support_oppose_code",
+    "category_code",
+    "amended_cd",
+];
+
+// Human readable label for a coded column's value, ex
+// ("entity_type", "IND") -> "Individual"
+pub fn code_label(column: &str, value: &str) -> Option<String> {
+    match column {
+        "entity_type" => EntityType::from_code(value).map(|v| v.label().to_owned()),
+        "election_code" => ElectionCode::parse(value).map(|v| v.label()),
+        "memo_code" => memo_code_label(value).map(|v| v.to_owned()),
+        "support_oppose_code" => SupportOppose::from_code(value).map(|v| v.label().to_owned()),
+        "category_code" => category_code_label(value).map(|v| v.to_owned()),
+        "amended_cd" => TransactionAmendment::from_code(value).map(|v| v.label().to_owned()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels() {
+        assert_eq!(EntityType::from_code("ind"), Some(EntityType::Individual));
+        assert_eq!(EntityType::PartyOrganization.code(), "PTY");
+        assert_eq!(
+            ElectionCode::parse("P2024"),
+            Some(ElectionCode {
+                election_type: ElectionType::Primary,
+                year: Some(2024)
+            })
+        );
+        assert_eq!(ElectionCode::parse("G").unwrap().label(), "General");
+        assert_eq!(ElectionCode::parse("X2024"), None);
+        assert_eq!(ElectionCode::parse("P24"), None);
+        assert_eq!(
+            AmendmentIndicator::from_form_type("F3XA"),
+            Some(AmendmentIndicator::Amendment)
+        );
+        assert_eq!(AmendmentIndicator::from_form_type(""), None);
+        assert_eq!(
+            code_label("election_code", "G2022").as_deref(),
+            Some("General 2022")
+        );
+        assert_eq!(
+            code_label("category_code", "004").as_deref(),
+            Some("Advertising Expenses")
+        );
+        assert_eq!(
+            code_label("support_oppose_code", "O").as_deref(),
+            Some("Oppose")
+        );
+        assert_eq!(code_label("amended_cd", "D").as_deref(), Some("Deleted"));
+        assert_eq!(code_label("entity_type", ""), None);
+        assert_eq!(code_label("contributor_name", "IND"), None);
+    }
+}
@@ -0,0 +1,192 @@
+use crate::{
+    mappings::{field_idx, FORM_TYPES},
+    summary::SummaryForm,
+};
+use fec_parser_macros::{gen_descriptions, gen_line_descriptions};
+use std::collections::HashMap;
+
+// descriptions from the FEC electronic filing format documentation, see
+// fec-parser-macros/src/descriptions.json
+static FORM_DESCRIPTIONS: &[(&str, &str)] = &gen_descriptions!(forms);
+static COLUMN_DESCRIPTIONS: &[(&str, &str)] = &gen_descriptions!(columns);
+static LINE_DESCRIPTIONS: &[(&str, &str, &str)] = &gen_line_descriptions!();
+
+lazy_static::lazy_static! {
+  static ref FORM_DESCRIPTIONS_MAP: HashMap<&'static str, &'static str> =
+    FORM_DESCRIPTIONS.iter().copied().collect();
+  static ref COLUMN_DESCRIPTIONS_MAP: HashMap<&'static str, &'static str> =
+    COLUMN_DESCRIPTIONS.iter().copied().collect();
+}
+
+fn summary_form_key(form: SummaryForm) -> &'static str {
+    match form {
+        SummaryForm::F3 => "F3",
+        SummaryForm::F3X => "F3X",
+        SummaryForm::F3P => "F3P",
+    }
+}
+
+// ex "F3XN" -> "Form 3X, Report of Receipts and Disbursements for a PAC or party committee"
+pub fn form_type_description(form_type: &str) -> Option<&'static str> {
+    form_pattern_description(FORM_TYPES.get(field_idx(form_type)?)?)
+}
+
+// lookup by the form type regex from mappings2.json, ex "^sa"
+pub fn form_pattern_description(pattern: &str) -> Option<&'static str> {
+    FORM_DESCRIPTIONS_MAP.get(pattern).copied()
+}
+
+// ex "contributor_last_name" -> "Contributor's last name"
+pub fn column_description(column: &str) -> Option<&'static str> {
+    COLUMN_DESCRIPTIONS_MAP.get(column).copied()
+}
+
+// The summary page line an itemization is reported on, which depends on the
+// form being filed, ex SA12 is a transfer from affiliated committees on F3X
+// but from other authorized committees on F3.
+pub fn line_description(form: SummaryForm, row_type: &str) -> Option<&'static str> {
+    let form = summary_form_key(form);
+    let row_type = row_type.replace('/', "");
+    LINE_DESCRIPTIONS
+        .iter()
+        .find(|(line_form, line_row_type, _)| {
+            *line_form == form && line_row_type.eq_ignore_ascii_case(&row_type)
+        })
+        .map(|(_, _, description)| *description)
+}
+
+// A row type broken into its parts, ex "SA11AI" -> schedule 'A', line 11,
+// sub-line "AI". "SE" and "SF" have no line of their own. 3.x-5.x filings
+// number the last part of a sub-line instead of using roman numerals, ex
+// "SA11A1" for SA11AI, which is read as the roman numeral.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineCode {
+    pub schedule: char,
+    pub line: Option<u16>,
+    pub sub_line: Option<String>,
+}
+
+impl LineCode {
+    pub fn parse(row_type: &str) -> Option<Self> {
+        let row_type = row_type.replace('/', "").to_ascii_uppercase();
+        let rest = row_type.strip_prefix('S')?;
+        let mut chars = rest.chars();
+        let schedule = chars.next().filter(|c| c.is_ascii_alphabetic())?;
+        let rest = chars.as_str();
+        let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        let line = match digits {
+            0 => None,
+            _ => Some(rest[..digits].parse().ok()?),
+        };
+        let sub_line = &rest[digits..];
+        let letters = sub_line.trim_end_matches(|c: char| c.is_ascii_digit());
+        let numeral = match &sub_line[letters.len()..] {
+            "" => "",
+            "1" => "I",
+            "2" => "II",
+            "3" => "III",
+            _ => return None,
+        };
+        if !letters.chars().all(|c| c.is_ascii_alphabetic())
+            || (line.is_none() && !sub_line.is_empty())
+            || (letters.is_empty() && !numeral.is_empty())
+        {
+            return None;
+        }
+        let sub_line = format!("{letters}{numeral}");
+        Some(Self {
+            schedule,
+            line,
+            sub_line: (!sub_line.is_empty()).then_some(sub_line),
+        })
+    }
+}
+
+// Like `line_description`, but falls back to the closest parent line when a
+// filer reports on a more specific sub-line than the form defines, ex SA11AII
+// -> SA11AI.
+pub fn line_label(form: SummaryForm, row_type: &str) -> Option<&'static str> {
+    let code = LineCode::parse(row_type)?;
+    let line = format!(
+        "S{}{}",
+        code.schedule,
+        code.line.map_or("".to_owned(), |line| line.to_string())
+    );
+    let mut sub_line = code.sub_line.unwrap_or_default();
+    loop {
+        if let Some(description) = line_description(form, &format!("{line}{sub_line}")) {
+            return Some(description);
+        }
+        sub_line.pop()?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn descriptions() {
+        assert_eq!(
+            form_type_description("SA11AI"),
+            Some("Schedule A, itemized receipts")
+        );
+        assert_eq!(form_type_description("SC/10"), Some("Schedule C, loans"));
+        assert!(form_type_description("F3XN")
+            .unwrap()
+            .starts_with("Form 3X"));
+        assert_eq!(
+            column_description("contributor_last_name"),
+            Some("Contributor's last name")
+        );
+        assert_eq!(column_description("not_a_column"), None);
+        assert_eq!(
+            line_description(SummaryForm::F3X, "SA12"),
+            Some("Transfers from affiliated/other party committees")
+        );
+        assert_eq!(
+            line_description(SummaryForm::F3, "SA12"),
+            Some("Transfers from other authorized committees")
+        );
+        assert_eq!(line_description(SummaryForm::F3, "SC/10"), None);
+
+        assert_eq!(
+            LineCode::parse("SA11AI"),
+            Some(LineCode {
+                schedule: 'A',
+                line: Some(11),
+                sub_line: Some("AI".to_owned())
+            })
+        );
+        assert_eq!(
+            LineCode::parse("SC/10"),
+            Some(LineCode {
+                schedule: 'C',
+                line: Some(10),
+                sub_line: None
+            })
+        );
+        assert_eq!(LineCode::parse("SE").unwrap().line, None);
+        assert_eq!(LineCode::parse("F3XN"), None);
+        assert_eq!(LineCode::parse("SA11A1"), LineCode::parse("SA11AI"));
+        assert_eq!(LineCode::parse("SA11A4"), None);
+        assert_eq!(
+            line_label(SummaryForm::F3X, "SA11A1"),
+            line_description(SummaryForm::F3X, "SA11AI")
+        );
+        assert_eq!(
+            line_label(SummaryForm::F3, "SB17"),
+            Some("Operating expenditures")
+        );
+        assert_eq!(
+            line_label(SummaryForm::F3X, "SB29"),
+            Some("Other disbursements")
+        );
+        assert_eq!(
+            line_label(SummaryForm::F3X, "SA11AII"),
+            line_description(SummaryForm::F3X, "SA11AI")
+        );
+        assert_eq!(line_label(SummaryForm::F3X, "SA99"), None);
+        assert_eq!(line_label(SummaryForm::F3X, "TEXT"), None);
+    }
+}
@@ -0,0 +1,322 @@
+use crate::{
+    mappings::{column_type, ColumnType},
+    transactions::TRANSACTION_ID_COLUMNS,
+    Filing, FilingCover, FilingRow, FilingRowReadError,
+};
+use indexmap::IndexMap;
+use std::{
+    collections::{HashMap, VecDeque},
+    io::Read,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldChange {
+    pub column: String,
+    // None when the column only exists on one side, ex across versions
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+// "100" and "100.00" are the same amount
+fn same_value(column: &str, old: &str, new: &str) -> bool {
+    let (old, new) = (old.trim(), new.trim());
+    if old == new {
+        return true;
+    }
+    match column_type(column) {
+        ColumnType::Float => match (old.parse::<f64>(), new.parse::<f64>()) {
+            (Ok(old), Ok(new)) => old == new,
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+// Field level differences between two sets of named values, matched by
+// column name so that records of different versions can be compared. The
+// form type column is left out, since an amendment always changes it.
+pub fn diff_fields<'a, 'b>(
+    old: impl Iterator<Item = (&'static str, &'a str)>,
+    new: impl Iterator<Item = (&'static str, &'b str)>,
+) -> Vec<FieldChange> {
+    let mut new: IndexMap<&str, &str> = new.collect();
+    let mut changes = vec![];
+    for (column, old_value) in old {
+        let new_value = new.shift_remove(column);
+        if column == "form_type" || same_value(column, old_value, new_value.unwrap_or("")) {
+            continue;
+        }
+        changes.push(FieldChange {
+            column: column.to_owned(),
+            old: Some(old_value.to_owned()),
+            new: new_value.map(|v| v.to_owned()),
+        });
+    }
+    for (column, new_value) in new {
+        if column == "form_type" || new_value.trim().is_empty() {
+            continue;
+        }
+        changes.push(FieldChange {
+            column: column.to_owned(),
+            old: None,
+            new: Some(new_value.to_owned()),
+        });
+    }
+    changes
+}
+
+// Cover record differences, split into the cover fields and the summary
+// page amounts
+pub fn diff_covers(old: &FilingCover, new: &FilingCover) -> (Vec<FieldChange>, Vec<FieldChange>) {
+    diff_fields(old.iter_named(), new.iter_named())
+        .into_iter()
+        .partition(|change| column_type(&change.column) != ColumnType::Float)
+}
+
+pub enum RowDiff {
+    Added(FilingRow),
+    Removed(FilingRow),
+    Changed {
+        old: FilingRow,
+        new: FilingRow,
+        changes: Vec<FieldChange>,
+    },
+}
+
+impl RowDiff {
+    // the row as it is in the newer filing, or the removed row
+    pub fn row(&self) -> &FilingRow {
+        match self {
+            Self::Added(row) | Self::Removed(row) => row,
+            Self::Changed { new, .. } => new,
+        }
+    }
+
+    pub fn transaction_id(&self) -> Option<&str> {
+        transaction_id(self.row())
+    }
+}
+
+fn transaction_id(row: &FilingRow) -> Option<&str> {
+    TRANSACTION_ID_COLUMNS
+        .iter()
+        .find_map(|column| row.get(column))
+        .map(|v| v.trim())
+        .filter(|v| !v.is_empty())
+}
+
+#[derive(Default)]
+struct Side {
+    // rows not matched by the other filing yet
+    pending: IndexMap<String, FilingRow>,
+    // rows without a transaction ID are matched by their position among
+    // rows of the same type, ex the 2nd TEXT row
+    untracked: HashMap<String, usize>,
+    done: bool,
+}
+
+impl Side {
+    fn key(&mut self, row: &FilingRow) -> String {
+        match transaction_id(row) {
+            Some(transaction_id) => transaction_id.to_owned(),
+            None => {
+                let n = self.untracked.entry(row.row_type.clone()).or_default();
+                *n += 1;
+                format!("{}#{}", row.row_type, n)
+            }
+        }
+    }
+}
+
+// Streams the itemizations of two filings, matching transactions by ID.
+// Both filings are read in lockstep, so only rows that haven't been matched
+// yet are kept in memory, which stays small when the filings list their
+// transactions in a similar order, as amendments usually do. There is no
+// bound on it otherwise: when the order differs, ex an amendment that lists
+// its transactions in reverse, up to every row of both filings is held
+// until its match is read, and rows that are never matched are only
+// reported once both filings are done. Unchanged transactions are skipped.
+pub struct TransactionDiff<'a, A: Read, B: Read> {
+    old: &'a mut Filing<A>,
+    new: &'a mut Filing<B>,
+    old_side: Side,
+    new_side: Side,
+    queue: VecDeque<RowDiff>,
+}
+
+impl<'a, A: Read, B: Read> TransactionDiff<'a, A, B> {
+    pub fn new(old: &'a mut Filing<A>, new: &'a mut Filing<B>) -> Self {
+        Self {
+            old,
+            new,
+            old_side: Side::default(),
+            new_side: Side::default(),
+            queue: VecDeque::new(),
+        }
+    }
+
+    fn step(&mut self) -> Result<(), FilingRowReadError> {
+        if !self.old_side.done {
+            match self.old.next_row().transpose()? {
+                Some(row) => {
+                    let key = self.old_side.key(&row);
+                    match self.new_side.pending.shift_remove(&key) {
+                        Some(new) => self.matched(row, new),
+                        None => {
+                            self.old_side.pending.insert(key, row);
+                        }
+                    }
+                }
+                None => self.old_side.done = true,
+            }
+        }
+        if !self.new_side.done {
+            match self.new.next_row().transpose()? {
+                Some(row) => {
+                    let key = self.new_side.key(&row);
+                    match self.old_side.pending.shift_remove(&key) {
+                        Some(old) => self.matched(old, row),
+                        None => {
+                            self.new_side.pending.insert(key, row);
+                        }
+                    }
+                }
+                None => self.new_side.done = true,
+            }
+        }
+        if self.old_side.done && self.new_side.done {
+            self.queue.extend(
+                self.old_side
+                    .pending
+                    .drain(..)
+                    .map(|(_, row)| RowDiff::Removed(row)),
+            );
+            self.queue.extend(
+                self.new_side
+                    .pending
+                    .drain(..)
+                    .map(|(_, row)| RowDiff::Added(row)),
+            );
+        }
+        Ok(())
+    }
+
+    fn matched(&mut self, old: FilingRow, new: FilingRow) {
+        let changes = diff_fields(old.iter_named(), new.iter_named());
+        if !changes.is_empty() {
+            self.queue.push_back(RowDiff::Changed { old, new, changes });
+        }
+    }
+}
+
+impl<A: Read, B: Read> Iterator for TransactionDiff<'_, A, B> {
+    type Item = Result<RowDiff, FilingRowReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(diff) = self.queue.pop_front() {
+                return Some(Ok(diff));
+            }
+            if self.old_side.done && self.new_side.done {
+                return None;
+            }
+            if let Err(e) = self.step() {
+                return Some(Err(e));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{test_filing, F3XN};
+
+    fn sa(transaction_id: &'static str, amount: &'static str) -> Vec<&'static str> {
+        let mut sa = vec![""; 45];
+        sa[0] = "SA11AI";
+        sa[2] = transaction_id;
+        sa[20] = amount;
+        sa
+    }
+
+    fn summary(diffs: &[RowDiff]) -> Vec<(&'static str, &str)> {
+        diffs
+            .iter()
+            .map(|diff| {
+                let kind = match diff {
+                    RowDiff::Added(_) => "added",
+                    RowDiff::Removed(_) => "removed",
+                    RowDiff::Changed { .. } => "changed",
+                };
+                (kind, diff.transaction_id().unwrap())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn diffs_transactions() {
+        let mut old = test_filing(&[
+            F3XN,
+            &sa("SA.1", "100"),
+            &sa("SA.2", "200"),
+            &sa("SA.3", "300"),
+        ]);
+        let mut new = test_filing(&[
+            F3XN,
+            &sa("SA.4", "400"),
+            &sa("SA.3", "300.00"),
+            &sa("SA.1", "150"),
+        ]);
+        let diffs: Vec<_> = TransactionDiff::new(&mut old, &mut new)
+            .map(|diff| diff.unwrap())
+            .collect();
+        assert_eq!(
+            summary(&diffs),
+            vec![("changed", "SA.1"), ("removed", "SA.2"), ("added", "SA.4")]
+        );
+        let RowDiff::Changed { changes, .. } = &diffs[0] else {
+            unreachable!()
+        };
+        assert_eq!(
+            changes,
+            &vec![FieldChange {
+                column: "contribution_amount".to_owned(),
+                old: Some("100".to_owned()),
+                new: Some("150".to_owned()),
+            }]
+        );
+    }
+
+    #[test]
+    fn diffs_transactions_in_reverse_order() {
+        let mut old = test_filing(&[
+            F3XN,
+            &sa("SA.1", "100"),
+            &sa("SA.2", "200"),
+            &sa("SA.3", "300"),
+            &sa("SA.4", "400"),
+            &sa("SA.5", "500"),
+        ]);
+        let mut new = test_filing(&[
+            F3XN,
+            &sa("SA.6", "600"),
+            &sa("SA.5", "500"),
+            &sa("SA.4", "450"),
+            &sa("SA.2", "200"),
+            &sa("SA.1", "125"),
+        ]);
+        let diffs: Vec<_> = TransactionDiff::new(&mut old, &mut new)
+            .map(|diff| diff.unwrap())
+            .collect();
+        assert_eq!(
+            summary(&diffs),
+            vec![
+                ("changed", "SA.4"),
+                ("changed", "SA.1"),
+                ("removed", "SA.3"),
+                ("added", "SA.6"),
+            ]
+        );
+    }
+}
@@ -1,19 +1,177 @@
+pub mod amendments;
+#[cfg(feature = "arrow")]
+pub mod arrow;
+pub mod canonical;
+pub mod codes;
+pub mod descriptions;
+pub mod diff;
 pub mod mappings;
+pub mod names;
+pub mod summary;
+#[cfg(test)]
+mod test_utils;
+pub mod transactions;
+pub mod validation;
 
+use amendments::amended_filing_id;
+use codes::AmendmentIndicator;
 use csv::{ByteRecordsIntoIter, StringRecord};
 use mappings::column_names_for_field;
 use std::{
-    fs,
-    io::{Error as IOError, Read},
+    collections::HashMap,
+    fmt, fs,
+    io::{Chain, Cursor, Error as IOError, Read},
     path::{Path, PathBuf},
 };
+use summary::FinancialSummary;
 use thiserror::Error;
 
 pub fn try_format_fec_date(value: &str) -> String {
-    if value.len() == "YYYYMMDD".len() {
-        return format!("{}-{}-{}", &value[0..4], &value[4..6], &value[6..8]);
+    FecDate::parse(value).map_or_else(|| value.to_owned(), |date| date.to_string())
+}
+
+fn days_in_month(year: u32, month: u32) -> u32 {
+    match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if (year.is_multiple_of(4) && !year.is_multiple_of(100)) || year.is_multiple_of(400) => {
+            29
+        }
+        2 => 28,
+        _ => 0,
+    }
+}
+
+// A calendar date from a YYYYMMDD field, displayed as YYYY-MM-DD
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FecDate {
+    pub year: u32,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl FecDate {
+    pub fn parse(value: &str) -> Option<Self> {
+        if value.len() != 8 || !value.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let year = value[0..4].parse().ok()?;
+        let month = value[4..6].parse().ok()?;
+        let day = value[6..8].parse().ok()?;
+        if day == 0 || day > days_in_month(year, month) {
+            return None;
+        }
+        Some(Self { year, month, day })
+    }
+
+    // days since 1970-01-01, ex for Arrow's Date32
+    pub fn days_since_epoch(&self) -> i32 {
+        let (year, month) = match self.month {
+            1 | 2 => (self.year as i32 - 1, self.month as i32 + 9),
+            month => (self.year as i32, month as i32 - 3),
+        };
+        let era = year.div_euclid(400);
+        let year_of_era = year.rem_euclid(400);
+        let day_of_year = (153 * month + 2) / 5 + self.day as i32 - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        era * 146_097 + day_of_era - 719_468
     }
-    value.to_owned()
+
+    pub fn from_days_since_epoch(days: i32) -> Self {
+        let days = days + 719_468;
+        let era = days.div_euclid(146_097);
+        let day_of_era = days.rem_euclid(146_097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * month + 2) / 5 + 1;
+        let (year, month) = match month {
+            10 | 11 => (era * 400 + year_of_era + 1, month - 9),
+            month => (era * 400 + year_of_era, month + 3),
+        };
+        Self {
+            year: year as u32,
+            month: month as u32,
+            day: day as u32,
+        }
+    }
+
+    // today in UTC
+    pub fn today() -> Self {
+        let seconds = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        Self::from_days_since_epoch((seconds / 86_400) as i32)
+    }
+}
+
+impl fmt::Display for FecDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+// Amounts are at most 12 digits with 2 decimal places in every version
+pub const AMOUNT_PRECISION: u8 = 14;
+pub const AMOUNT_SCALE: i8 = 2;
+
+// "1234.5" -> 123450, rounded to 2 decimal places. Amounts that don't fit
+// in AMOUNT_PRECISION digits are invalid rather than clamped, so that
+// exports store them as null instead of failing or changing them.
+pub fn parse_amount(value: &str) -> Option<i128> {
+    let value = value.trim();
+    let (negative, value) = match value.strip_prefix('-') {
+        Some(value) => (true, value),
+        None => (false, value.strip_prefix('+').unwrap_or(value)),
+    };
+    let (whole, fraction) = value.split_once('.').unwrap_or((value, ""));
+    if (whole.is_empty() && fraction.is_empty())
+        || !whole
+            .bytes()
+            .chain(fraction.bytes())
+            .all(|b| b.is_ascii_digit())
+    {
+        return None;
+    }
+    let whole: i128 = if whole.is_empty() {
+        0
+    } else {
+        whole.parse().ok()?
+    };
+    let mut digits = fraction.bytes().map(|b| (b - b'0') as i128);
+    let cents = digits.next().unwrap_or(0) * 10 + digits.next().unwrap_or(0);
+    let round = i128::from(digits.next().is_some_and(|digit| digit >= 5));
+    let amount = whole
+        .checked_mul(100)?
+        .checked_add(cents + round)
+        .filter(|amount| *amount < 10i128.pow(AMOUNT_PRECISION as u32))?;
+    Some(if negative { -amount } else { amount })
+}
+
+// 123450 -> "1234.50"
+pub fn format_amount(cents: i128) -> String {
+    let sign = if cents < 0 { "-" } else { "" };
+    let cents = cents.abs();
+    format!("{sign}{}.{:02}", cents / 100, cents % 100)
+}
+
+// Field access by column name, shared by the cover record and every row.
+// Values are trimmed, and empty fields are returned as None.
+fn record_get<'a>(columns: &[String], record: &'a StringRecord, column: &str) -> Option<&'a str> {
+    let idx = columns.iter().position(|v| v == column)?;
+    record.get(idx).map(str::trim).filter(|v| !v.is_empty())
+}
+
+fn record_iter_named<'a>(
+    columns: &'static [String],
+    record: &'a StringRecord,
+) -> impl Iterator<Item = (&'static str, &'a str)> {
+    columns
+        .iter()
+        .zip(record.iter())
+        .map(|(column, value)| (column.as_str(), value))
 }
 
 macro_rules! header_get_field {
@@ -34,7 +192,8 @@ pub enum FilingHeaderError {
     #[error("`{0}`")]
     UnsupportedVersion(String),
 }
-// fields from mappings2.json -> '^hdr$' -> '$[6-8]'
+// fields from mappings2.json -> '^hdr$' -> '$[6-8]', and '^[3-5]' which
+// has name_delim after soft_ver
 #[derive(Debug)]
 pub struct FilingHeader {
     pub header_record: StringRecord,
@@ -43,6 +202,8 @@ pub struct FilingHeader {
     pub fec_version: String,
     pub soft_name: String,
     pub soft_ver: String,
+    // separator in the combined names of pre-6.0 filings, ex "^"
+    pub name_delim: Option<String>,
     pub report_id: Option<String>,
     pub report_number: Option<String>,
     pub comment: Option<String>,
@@ -53,28 +214,29 @@ impl FilingHeader {
         let record_type = header_get_field!(hdr, 0, "record_type");
         let ef_type = header_get_field!(hdr, 1, "ef_type");
         let fec_version = header_get_field!(hdr, 2, "fec_version").trim().to_owned();
-        match fec_version.as_str() {
-            "8.3" | "8.4" => (),
+        // 6.x onwards share this header layout and the \x1c delimiter, 3.x
+        // to 5.x are comma separated with an extra name_delim field
+        let legacy = match fec_version.get(0..2) {
+            Some("6." | "7." | "8.") => false,
+            Some("3." | "4." | "5.") => true,
             _ => {
                 return Err(FilingHeaderError::UnsupportedVersion(format!(
-                    "Unsupported version '{fec_version}', only 8.4 is currently supported."
-                )));
+                    "Unsupported version '{fec_version}', only 3.x to 8.x are currently supported."
+                )))
             }
-        }
+        };
         let soft_name = header_get_field!(hdr, 3, "soft_name");
         let soft_ver = header_get_field!(hdr, 4, "soft_ver");
-        let report_id = hdr
-            .get(5)
-            .map(|v| String::from(v.trim()))
-            .filter(|v| !String::is_empty(v));
-        let report_number = hdr
-            .get(6)
-            .map(|v| String::from(v.trim()))
-            .filter(|v| !String::is_empty(v));
-        let comment = hdr
-            .get(7)
-            .map(|v| String::from(v.trim()))
-            .filter(|v| !String::is_empty(v));
+        let optional_field = |idx: usize| {
+            hdr.get(idx)
+                .map(|v| String::from(v.trim()))
+                .filter(|v| !String::is_empty(v))
+        };
+        let offset = usize::from(legacy);
+        let name_delim = if legacy { optional_field(5) } else { None };
+        let report_id = optional_field(5 + offset);
+        let report_number = optional_field(6 + offset);
+        let comment = optional_field(7 + offset);
 
         Ok(FilingHeader {
             header_record: hdr,
@@ -83,11 +245,17 @@ impl FilingHeader {
             fec_version,
             soft_name,
             soft_ver,
+            name_delim,
             report_id,
             report_number,
             comment,
         })
     }
+
+    // for amendments, the id of the filing being amended, ex "FEC-1234567"
+    pub fn amended_filing_id(&self) -> Option<String> {
+        self.report_id.as_deref().and_then(amended_filing_id)
+    }
 }
 
 pub fn report_code_label(report_code: &str) -> &'static str {
@@ -147,6 +315,7 @@ pub fn report_code_label(report_code: &str) -> &'static str {
 }
 pub struct FilingCover {
     cover_record: StringRecord,
+    columns: &'static [String],
     pub form_type: String,
     pub filer_id: String,
     pub filer_name: String,
@@ -193,6 +362,7 @@ impl FilingCover {
 
         Ok(Self {
             cover_record,
+            columns,
             form_type,
             filer_id,
             filer_name,
@@ -201,6 +371,48 @@ impl FilingCover {
             coverage_through_date,
         })
     }
+
+    pub fn record(&self) -> &StringRecord {
+        &self.cover_record
+    }
+
+    pub fn columns(&self) -> &'static [String] {
+        self.columns
+    }
+
+    // any cover field by column name, ex "treasurer_last_name"
+    pub fn get(&self, column: &str) -> Option<&str> {
+        record_get(self.columns, &self.cover_record, column)
+    }
+
+    pub fn get_date(&self, column: &str) -> Option<FecDate> {
+        FecDate::parse(self.get(column)?)
+    }
+
+    // exact amounts in cents, ex "1234.5" -> 123450
+    pub fn get_cents(&self, column: &str) -> Option<i128> {
+        parse_amount(self.get(column)?)
+    }
+
+    // for display only, sums and comparisons should use `get_cents`
+    pub fn get_amount(&self, column: &str) -> Option<f64> {
+        self.get_cents(column).map(|cents| cents as f64 / 100.0)
+    }
+
+    // (column, value) pairs in record order, including empty fields
+    pub fn iter_named(&self) -> impl Iterator<Item = (&'static str, &str)> {
+        record_iter_named(self.columns, &self.cover_record)
+    }
+
+    // new, amendment or termination, from the last letter of the form type
+    pub fn amendment_indicator(&self) -> Option<AmendmentIndicator> {
+        AmendmentIndicator::from_form_type(&self.form_type)
+    }
+
+    // summary page totals, only for F3, F3X and F3P filings
+    pub fn financial_summary(&self) -> Option<FinancialSummary> {
+        FinancialSummary::from_record(&self.form_type, self.columns, &self.cover_record)
+    }
 }
 
 #[derive(Error, Debug)]
@@ -215,6 +427,8 @@ pub enum FilingReaderError {
     IncorrectHeader(String),
     #[error("Error parsing header")]
     HeaderRead(#[from] FilingHeaderError),
+    #[error("Error reading the first line")]
+    FirstLine(#[from] IOError),
 }
 
 #[derive(Error, Debug)]
@@ -231,7 +445,10 @@ pub struct Filing<R: Read> {
     pub filing_id: String,
     pub header: FilingHeader,
     pub cover: FilingCover,
-    records_iter: ByteRecordsIntoIter<R>,
+    // the first line is read ahead to find the delimiter, then re-read
+    records_iter: ByteRecordsIntoIter<Chain<Cursor<Vec<u8>>, R>>,
+    // column names of each raw form type seen so far, ex 'SC/10'
+    row_columns: HashMap<String, &'static [String]>,
     pub source_length: Option<usize>,
 }
 
@@ -241,22 +458,34 @@ impl<R: Read> Filing<R> {
         filing_id: String,
         source_length: Option<usize>,
     ) -> Result<Self, FilingReaderError> {
+        // a byte at a time, so the reader isn't advanced past the first line
+        let mut rdr = rdr;
+        let mut first_line = vec![];
+        let mut byte = [0];
+        while rdr.read(&mut byte)? == 1 {
+            first_line.push(byte[0]);
+            if byte[0] == b'\n' {
+                break;
+            }
+        }
+        // pre-6.0 filings are comma separated
+        let delimiter = match first_line.contains(&b'\x1c') {
+            true => b'\x1c',
+            false => b',',
+        };
         let csv_reader = csv::ReaderBuilder::new()
-            .delimiter(b"\x1c"[0])
+            .delimiter(delimiter)
             .flexible(true)
             .has_headers(false)
-            .from_reader(rdr);
+            .from_reader(Cursor::new(first_line).chain(rdr));
 
         let mut records_iter = csv_reader.into_byte_records();
 
         let hdr = records_iter.next().ok_or(FilingReaderError::NoRecords)??;
 
-        let hdr_record_type = String::from_utf8(
-            hdr.get(0)
-                .ok_or_else(|| FilingReaderError::MissingHeader)?
-                .to_vec(),
-        )
-        .unwrap();
+        let hdr_record_type =
+            String::from_utf8(hdr.get(0).ok_or(FilingReaderError::MissingHeader)?.to_vec())
+                .unwrap();
         if hdr_record_type != "HDR" {
             return Err(FilingReaderError::IncorrectHeader(
                 hdr_record_type.to_owned(),
@@ -280,6 +509,7 @@ impl<R: Read> Filing<R> {
             header,
             cover,
             records_iter,
+            row_columns: HashMap::new(),
             source_length,
         })
     }
@@ -291,7 +521,7 @@ impl<R: Read> Filing<R> {
             .ok_or_else(|| FilingError::UnknownFilingId(filing_path.to_path_buf()))?;
 
         let filing_file = std::fs::File::open(filing_path)?;
-        let source_length = filing_file.metadata().map(|v| (v.len() as usize)).ok();
+        let source_length = filing_file.metadata().map(|v| v.len() as usize).ok();
 
         Ok(Filing::from_reader(
             filing_file,
@@ -300,6 +530,16 @@ impl<R: Read> Filing<R> {
         )?)
     }
 
+    fn columns_for(&mut self, form_type: &str) -> &'static [String] {
+        if let Some(columns) = self.row_columns.get(form_type) {
+            return columns;
+        }
+        let columns = column_names_for_field(form_type, &self.header.fec_version)
+            .map_or(&[][..], |v| v.as_slice());
+        self.row_columns.insert(form_type.to_owned(), columns);
+        columns
+    }
+
     pub fn next_row(&mut self) -> Option<Result<FilingRow, FilingRowReadError>> {
         let (record, original_size) = match self.records_iter.next() {
             Some(Ok(record)) => {
@@ -331,10 +571,12 @@ impl<R: Read> Filing<R> {
                                 let original_size = record.as_slice().len();
                                 let record = StringRecord::from_byte_record_lossy(record);
                                 let row_type = record.get(0).unwrap().to_owned();
+                                let columns = self.columns_for(&row_type);
                                 return Some(Ok(FilingRow {
                                     row_type,
                                     record,
                                     original_size,
+                                    columns,
                                 }));
                             }
                             None => return None,
@@ -352,10 +594,12 @@ impl<R: Read> Filing<R> {
             }
         }
 
+        let columns = self.columns_for(record.get(0).unwrap());
         Some(Ok(FilingRow {
             row_type,
             record,
             original_size,
+            columns,
         }))
     }
 }
@@ -374,9 +618,53 @@ pub struct FilingRow {
     pub row_type: String,
     pub record: StringRecord,
     pub original_size: usize,
+    columns: &'static [String],
+}
+
+impl FilingRow {
+    // A row of a filing with the given version, ex one read outside of
+    // Filing::next_row
+    pub fn new(record: StringRecord, fec_version: &str) -> Self {
+        let form_type = record.get(0).unwrap_or_default();
+        let columns =
+            column_names_for_field(form_type, fec_version).map_or(&[][..], |v| v.as_slice());
+        Self {
+            row_type: form_type.replace('/', ""),
+            original_size: record.as_slice().len(),
+            record,
+            columns,
+        }
+    }
+
+    // column names for this row's form type and the filing's version, empty
+    // for unknown row types
+    pub fn columns(&self) -> &'static [String] {
+        self.columns
+    }
+
+    pub fn get(&self, column: &str) -> Option<&str> {
+        record_get(self.columns, &self.record, column)
+    }
+
+    pub fn get_date(&self, column: &str) -> Option<FecDate> {
+        FecDate::parse(self.get(column)?)
+    }
+
+    pub fn get_cents(&self, column: &str) -> Option<i128> {
+        parse_amount(self.get(column)?)
+    }
+
+    pub fn get_amount(&self, column: &str) -> Option<f64> {
+        self.get_cents(column).map(|cents| cents as f64 / 100.0)
+    }
+
+    pub fn iter_named(&self) -> impl Iterator<Item = (&'static str, &str)> {
+        record_iter_named(self.columns, &self.record)
+    }
 }
 
 #[cfg(test)]
+#[allow(unused_variables)]
 mod tests {
     use crate::*;
     use mappings::*;
@@ -410,6 +698,135 @@ mod tests {
         );
     }
 
+    #[test]
+    fn form_schemas() {
+        let schema = schema_for_field("SB21B", "8.3").unwrap();
+        assert_eq!(schema.form_type, "^sb");
+        assert_eq!(schema.version_pattern, "^8.4|8.3|8.2|8.1|8.0");
+        assert_eq!(schema.columns[2].name, "transaction_id_number");
+        assert_eq!(schema.columns[19].name, "expenditure_date");
+        assert_eq!(schema.columns[19].column_type, ColumnType::Date);
+        assert_eq!(schema.columns[20].column_type, ColumnType::Float);
+        assert!(form_type_version_patterns("SA11AI")
+            .unwrap()
+            .contains(&"^8.4|8.3|8.2|8.1|8.0".to_owned()));
+        assert_eq!(schemas_for_field("SA11AI").len(), 14);
+        assert_eq!(
+            schemas().count(),
+            COLUMN_NAMES.iter().map(|v| v.len()).sum::<usize>()
+        );
+        assert!(schema_for_field("ZZZ", "8.4").is_none());
+    }
+
+    #[test]
+    fn amounts() {
+        assert_eq!(parse_amount("1234.5"), Some(123450));
+        assert_eq!(parse_amount("-0.015"), Some(-2));
+        assert_eq!(parse_amount(".99"), Some(99));
+        assert_eq!(parse_amount("+5"), Some(500));
+        assert_eq!(parse_amount("1,000"), None);
+        assert_eq!(parse_amount(""), None);
+        assert_eq!(parse_amount("-"), None);
+        assert_eq!(parse_amount("999999999999.99"), Some(99_999_999_999_999));
+        assert_eq!(parse_amount("-999999999999.99"), Some(-99_999_999_999_999));
+        assert_eq!(parse_amount("0000000000001.00"), Some(100));
+        // past numeric(14, 2), including after rounding
+        assert_eq!(parse_amount("1000000000000"), None);
+        assert_eq!(parse_amount("999999999999.995"), None);
+        assert_eq!(parse_amount(&"9".repeat(60)), None);
+        assert_eq!(format_amount(-2), "-0.02");
+        assert_eq!(format_amount(123450), "1234.50");
+    }
+
+    #[test]
+    fn named_fields() {
+        let mut sa = vec![""; 45];
+        sa[0] = "SA11AI";
+        sa[7] = "Doe ";
+        sa[8] = " ";
+        sa[19] = "20240515";
+        sa[20] = " 250.00";
+        let mut filing = test_utils::test_filing(&[test_utils::F3XN, &sa]);
+
+        assert_eq!(filing.cover.get("committee_name"), Some("Test Committee"));
+        assert_eq!(filing.cover.get("street_2"), None);
+        assert_eq!(filing.cover.get("not_a_column"), None);
+        assert_eq!(
+            filing.cover.get_date("coverage_through_date"),
+            Some(FecDate {
+                year: 2024,
+                month: 6,
+                day: 30
+            })
+        );
+        assert_eq!(
+            filing.cover.iter_named().nth(1),
+            Some(("filer_committee_id_number", "C00000001"))
+        );
+
+        let row = filing.next_row().unwrap().unwrap();
+        assert_eq!(row.get("contributor_last_name"), Some("Doe"));
+        assert_eq!(
+            row.get_date("contribution_date").unwrap().to_string(),
+            "2024-05-15"
+        );
+        assert_eq!(row.get_cents("contribution_amount"), Some(25000));
+        assert_eq!(row.get_amount("contribution_amount"), Some(250.0));
+        assert_eq!(row.get_cents("contribution_aggregate"), None);
+        assert_eq!(row.get_amount("contribution_aggregate"), None);
+        assert_eq!(row.get("contributor_first_name"), None);
+        assert_eq!(row.iter_named().count(), row.columns().len());
+
+        let built = FilingRow::new(row.record.clone(), "8.4");
+        assert_eq!(built.row_type, "SA11AI");
+        assert_eq!(built.columns(), row.columns());
+        assert_eq!(built.get_cents("contribution_amount"), Some(25000));
+        assert_eq!(try_format_fec_date("20240515"), "2024-05-15");
+        assert_eq!(try_format_fec_date("20241301"), "20241301");
+    }
+
+    #[test]
+    fn legacy_filing() {
+        use test_utils::legacy_record as record;
+        let f3x = record(
+            "F3XN",
+            &[
+                ("filer_committee_id_number", "C00000001"),
+                ("committee_name", "Test, Committee"),
+            ],
+        );
+        let sa = record(
+            "SA11A1",
+            &[("entity_type", "IND"), ("contributor_name", "Doe|Jane|Ms")],
+        );
+        let mut filing = test_utils::test_legacy_filing(&[&f3x, &sa]);
+
+        assert_eq!(filing.header.fec_version, "5.3");
+        assert_eq!(filing.header.name_delim.as_deref(), Some("|"));
+        assert_eq!(filing.header.report_id.as_deref(), Some("FEC-1"));
+        assert_eq!(filing.header.report_number.as_deref(), Some("1"));
+        assert_eq!(filing.cover.filer_name, "Test, Committee");
+
+        let row = filing.next_row().unwrap().unwrap();
+        assert_eq!(row.row_type, "SA11A1");
+        assert_eq!(row.get("contributor_name"), Some("Doe|Jane|Ms"));
+        let mapping = canonical::CanonicalMapping::new(canonical::Schedule::A, row.columns())
+            .with_name_delim(filing.header.name_delim.as_deref().unwrap());
+        let values = mapping.map(&row.record);
+        let value = |name: &str| {
+            let idx = canonical::Schedule::A
+                .columns()
+                .iter()
+                .position(|v| v == name)
+                .unwrap();
+            values[idx].clone()
+        };
+        assert_eq!(value("contributor_last_name"), "Doe");
+        assert_eq!(value("contributor_first_name"), "Jane");
+        assert_eq!(value("contributor_prefix"), "Ms");
+        assert!(filing.next_row().is_none());
+    }
+
     #[test]
     fn xxx() {
         let file = File::open("../tests/13360.fec").unwrap();
@@ -34,18 +34,106 @@ pub fn field_idx(field: &str) -> Option<usize> {
     matches.iter().next()
 }
 
-pub fn column_names_for_field<'a>(
-    form_type: &str,
-    fec_version: &str,
-) -> std::result::Result<&'a Vec<String>, ()> {
-    let idx = field_idx(form_type).unwrap();
+pub fn column_names_for_field(form_type: &str, fec_version: &str) -> Option<&'static Vec<String>> {
+    let idx = field_idx(form_type)?;
     let idx2 = FORM_TYPE_VERSIONS_SET
-        .get(idx)
-        .unwrap()
+        .get(idx)?
         .matches(fec_version)
         .iter()
-        .next()
-        .unwrap();
-    let columns = COLUMN_NAMES.get(idx).unwrap().get(idx2).unwrap();
-    Ok(columns)
+        .next()?;
+    COLUMN_NAMES.get(idx)?.get(idx2)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnType {
+    Text,
+    Date,
+    Float,
+}
+
+impl ColumnType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ColumnType::Text => "text",
+            ColumnType::Date => "date",
+            ColumnType::Float => "float",
+        }
+    }
+}
+
+pub fn column_type(column: &str) -> ColumnType {
+    if DATE_COLUMNS.contains(column) {
+        ColumnType::Date
+    } else if FLOAT_COLUMNS.contains(column) {
+        ColumnType::Float
+    } else {
+        ColumnType::Text
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnSchema {
+    pub name: &'static str,
+    pub column_type: ColumnType,
+}
+
+// The ordered columns of a form type for a range of FEC versions. Both
+// `form_type` and `version_pattern` are the regex patterns from mappings2.json,
+// ex "^sa" and "^8.4|8.3|8.2|8.1|8.0"
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormSchema {
+    pub form_type: &'static str,
+    pub version_pattern: &'static str,
+    pub columns: Vec<ColumnSchema>,
+}
+
+fn form_schema(idx: usize, idx2: usize) -> Option<FormSchema> {
+    let columns = COLUMN_NAMES.get(idx)?.get(idx2)?;
+    Some(FormSchema {
+        form_type: FORM_TYPES.get(idx)?,
+        version_pattern: FORM_TYPE_VERSIONS_SET.get(idx)?.patterns().get(idx2)?,
+        columns: columns
+            .iter()
+            .map(|name| ColumnSchema {
+                name: name.as_str(),
+                column_type: column_type(name),
+            })
+            .collect(),
+    })
+}
+
+// version patterns that a form type has mappings for, in mappings2.json order,
+// ex "^8.4|8.3|8.2|8.1|8.0" or "^[3-5]", not the concrete versions
+pub fn form_type_version_patterns(form_type: &str) -> Option<&'static [String]> {
+    Some(
+        FORM_TYPE_VERSIONS_SET
+            .get(field_idx(form_type)?)?
+            .patterns(),
+    )
+}
+
+pub fn schema_for_field(form_type: &str, fec_version: &str) -> Option<FormSchema> {
+    let idx = field_idx(form_type)?;
+    let idx2 = FORM_TYPE_VERSIONS_SET
+        .get(idx)?
+        .matches(fec_version)
+        .iter()
+        .next()?;
+    form_schema(idx, idx2)
+}
+
+pub fn schemas_for_field(form_type: &str) -> Vec<FormSchema> {
+    field_idx(form_type)
+        .map(|idx| {
+            (0..FORM_TYPE_VERSIONS_SET[idx].len())
+                .filter_map(|idx2| form_schema(idx, idx2))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+pub fn schemas() -> impl Iterator<Item = FormSchema> {
+    (0..FORM_TYPES.len()).flat_map(|idx| {
+        (0..FORM_TYPE_VERSIONS_SET[idx].len()).filter_map(move |idx2| form_schema(idx, idx2))
+    })
 }
@@ -0,0 +1,341 @@
+use crate::{canonical::DEFAULT_NAME_DELIM, FilingRow};
+use std::fmt;
+
+static PREFIXES: &[&str] = &[
+    "MR", "MRS", "MS", "MISS", "DR", "REV", "HON", "SEN", "REP", "GOV", "JUDGE", "PROF", "SIR",
+    "GEN", "COL", "CAPT", "SGT",
+];
+static SUFFIXES: &[&str] = &[
+    "JR", "SR", "II", "III", "IV", "V", "MD", "PHD", "ESQ", "DDS", "CPA", "RN", "DO",
+];
+
+// suffixes that are also initials or names, ex "JANE V" or "JOHN DO", which
+// are only read as suffixes after a comma, ex "JANE, V"
+static AMBIGUOUS_SUFFIXES: &[&str] = &["V", "DO"];
+
+fn normalize_token(token: &str) -> String {
+    token
+        .trim_matches(|c: char| c.is_whitespace() || c == '.' || c == ',')
+        .to_ascii_uppercase()
+}
+
+fn is_prefix(token: &str) -> bool {
+    PREFIXES.contains(&normalize_token(token).as_str())
+}
+
+fn is_suffix(token: &str) -> bool {
+    SUFFIXES.contains(&normalize_token(token).as_str())
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_owned())
+}
+
+fn join(tokens: &[&str]) -> Option<String> {
+    non_empty(&tokens.join(" "))
+}
+
+// A person's name, from either the structured 8.x name fields, a legacy
+// delimited name ("Kellner^Lawrence") or free text ("KELLNER, LAWRENCE J")
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PersonName {
+    pub prefix: Option<String>,
+    pub first: Option<String>,
+    pub middle: Option<String>,
+    pub last: Option<String>,
+    pub suffix: Option<String>,
+}
+
+impl PersonName {
+    // From 8.x last/first/middle/prefix/suffix fields. Some filers put the
+    // whole name in the last name field, which is then parsed as free text.
+    pub fn from_parts(last: &str, first: &str, middle: &str, prefix: &str, suffix: &str) -> Self {
+        if first.trim().is_empty() && middle.trim().is_empty() && last.contains(',') {
+            let mut name = Self::parse(last);
+            name.prefix = non_empty(prefix).or(name.prefix);
+            name.suffix = non_empty(suffix).or(name.suffix);
+            return name;
+        }
+        Self {
+            prefix: non_empty(prefix),
+            first: non_empty(first),
+            middle: non_empty(middle),
+            last: non_empty(last),
+            suffix: non_empty(suffix),
+        }
+    }
+
+    // Pre-8.0 names, ex "Kellner^Lawrence J^Mr^Jr". The first part is the
+    // last name, the rest is the given names with any prefix or suffix.
+    pub fn parse_delimited(value: &str, delim: &str) -> Self {
+        let delim = if delim.is_empty() {
+            DEFAULT_NAME_DELIM
+        } else {
+            delim
+        };
+        let mut parts = value.split(delim);
+        let last = parts.next().unwrap_or("");
+        let Some(given) = parts.next() else {
+            return Self::parse(value);
+        };
+        let mut name = Self::parse_given(given);
+        name.last = non_empty(last);
+        for part in parts.filter(|v| !v.trim().is_empty()) {
+            if is_prefix(part) {
+                name.prefix = non_empty(part);
+            } else if is_suffix(part) {
+                name.suffix = non_empty(part);
+            } else {
+                name.middle = join(&[name.middle.as_deref().unwrap_or(""), part]);
+            }
+        }
+        name
+    }
+
+    // Free text names, either "LAST, FIRST M" or "First M Last"
+    pub fn parse(value: &str) -> Self {
+        match value.split_once(',') {
+            Some((last, rest)) => {
+                // "DOE, JR., JOHN" puts the suffix right after the last name
+                let mut last_tokens: Vec<&str> = last.split_whitespace().collect();
+                let (suffix, given) = match rest.split_once(',') {
+                    Some((suffix, given)) if is_suffix(suffix) => (non_empty(suffix), given),
+                    _ => (None, rest),
+                };
+                let suffix = suffix.or_else(|| {
+                    (last_tokens.len() > 1 && is_suffix(last_tokens[last_tokens.len() - 1]))
+                        .then(|| last_tokens.pop().unwrap().to_owned())
+                });
+                let mut name = Self::parse_given(given);
+                name.last = join(&last_tokens);
+                name.suffix = suffix.or(name.suffix);
+                name
+            }
+            None => {
+                let mut name = Self::parse_given(value);
+                // without a comma, the last given name is the last name
+                match name.middle.take() {
+                    Some(middle) => match middle.rsplit_once(' ') {
+                        Some((middle, last)) => {
+                            name.middle = non_empty(middle);
+                            name.last = non_empty(last);
+                        }
+                        None => name.last = Some(middle),
+                    },
+                    None => name.last = name.first.take(),
+                }
+                name
+            }
+        }
+    }
+
+    // "Mr. Lawrence J Jr." -> prefix, first, middle names and suffix
+    fn parse_given(value: &str) -> Self {
+        let mut tokens: Vec<&str> = value
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|v| !v.is_empty())
+            .collect();
+        let prefix = match tokens.first() {
+            Some(token) if tokens.len() > 1 && is_prefix(token) => Some(tokens.remove(0)),
+            _ => None,
+        };
+        let suffix = match tokens.last() {
+            Some(token) if tokens.len() > 1 && is_suffix(token) => {
+                let after_comma = value
+                    .trim_end()
+                    .strip_suffix(token)
+                    .is_some_and(|rest| rest.trim_end().ends_with(','));
+                let ambiguous = AMBIGUOUS_SUFFIXES.contains(&normalize_token(token).as_str());
+                (after_comma || !ambiguous).then(|| tokens.pop()).flatten()
+            }
+            _ => None,
+        };
+        Self {
+            prefix: prefix.and_then(non_empty),
+            first: tokens.first().and_then(|v| non_empty(v)),
+            middle: tokens.get(1..).and_then(join),
+            last: None,
+            suffix: suffix.and_then(non_empty),
+        }
+    }
+
+    // The name of a person in a row, ex `from_row(row, "contributor", None)`,
+    // reading the `contributor_last_name`/... fields, or when those are empty
+    // the legacy `contributor_name` field split on the filing's
+    // `FilingHeader::name_delim`. 3.x-5.x layouts have both.
+    pub fn from_row(row: &FilingRow, prefix: &str, name_delim: Option<&str>) -> Option<Self> {
+        let field = |suffix: &str| row.get(&format!("{prefix}_{suffix}")).unwrap_or("");
+        let columns = row.columns();
+        let name = if field("last_name").is_empty() && columns.contains(&format!("{prefix}_name")) {
+            Self::parse_delimited(field("name"), name_delim.unwrap_or(DEFAULT_NAME_DELIM))
+        } else {
+            let middle = match field("middle_name") {
+                "" => field("middle_nm"),
+                middle => middle,
+            };
+            Self::from_parts(
+                field("last_name"),
+                field("first_name"),
+                middle,
+                field("prefix"),
+                field("suffix"),
+            )
+        };
+        (!name.is_empty()).then_some(name)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.last.is_none() && self.first.is_none() && self.middle.is_none()
+    }
+
+    // ex "KELLNER, LAWRENCE J", for grouping the same donor across filings
+    pub fn normalized(&self) -> String {
+        let given = [&self.first, &self.middle]
+            .into_iter()
+            .flatten()
+            .map(|v| v.as_str())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let last = self.last.as_deref().unwrap_or("");
+        match given.is_empty() {
+            true => last.to_uppercase(),
+            false => format!("{}, {}", last, given).to_uppercase(),
+        }
+    }
+}
+
+// ex "Mr. Lawrence J Kellner Jr."
+impl fmt::Display for PersonName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts: Vec<&str> = [
+            &self.prefix,
+            &self.first,
+            &self.middle,
+            &self.last,
+            &self.suffix,
+        ]
+        .into_iter()
+        .flatten()
+        .map(|v| v.as_str())
+        .collect();
+        write!(f, "{}", parts.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{legacy_record, test_legacy_filing};
+
+    fn name(prefix: &str, first: &str, middle: &str, last: &str, suffix: &str) -> PersonName {
+        PersonName {
+            prefix: non_empty(prefix),
+            first: non_empty(first),
+            middle: non_empty(middle),
+            last: non_empty(last),
+            suffix: non_empty(suffix),
+        }
+    }
+
+    #[test]
+    fn person_names() {
+        assert_eq!(
+            PersonName::parse_delimited("Kellner^Lawrence", "^"),
+            name("", "Lawrence", "", "Kellner", "")
+        );
+        assert_eq!(
+            PersonName::parse_delimited("Kellner^Lawrence J^Mr.^Jr.", "^"),
+            name("Mr.", "Lawrence", "J", "Kellner", "Jr.")
+        );
+        assert_eq!(
+            PersonName::parse("KELLNER, LAWRENCE J"),
+            name("", "LAWRENCE", "J", "KELLNER", "")
+        );
+        assert_eq!(
+            PersonName::parse("Doe Jr., Dr. John"),
+            name("Dr.", "John", "", "Doe", "Jr.")
+        );
+        assert_eq!(
+            PersonName::parse("DOE, JR., JOHN"),
+            name("", "JOHN", "", "DOE", "JR.")
+        );
+        assert_eq!(
+            PersonName::parse("John Quincy Adams III"),
+            name("", "John", "Quincy", "Adams", "III")
+        );
+        assert_eq!(PersonName::parse("Cher"), name("", "", "", "Cher", ""));
+        assert_eq!(
+            PersonName::parse("SMITH, JANE V"),
+            name("", "JANE", "V", "SMITH", "")
+        );
+        assert_eq!(
+            PersonName::parse("SMITH, JANE, V"),
+            name("", "JANE", "", "SMITH", "V")
+        );
+        assert_eq!(PersonName::parse("JOHN DO"), name("", "JOHN", "", "DO", ""));
+        assert_eq!(
+            PersonName::parse("John Smith Jr"),
+            name("", "John", "", "Smith", "Jr")
+        );
+        assert_eq!(
+            PersonName::from_parts("DOE, JANE", "", "", "MS", ""),
+            name("MS", "JANE", "", "DOE", "")
+        );
+        assert_eq!(
+            PersonName::from_parts("Doe", "Jane", "", "", ""),
+            name("", "Jane", "", "Doe", "")
+        );
+        assert!(PersonName::parse("").is_empty());
+
+        let kellner = PersonName::parse_delimited("Kellner^Lawrence J", "^");
+        assert_eq!(kellner.normalized(), "KELLNER, LAWRENCE J");
+        assert_eq!(
+            kellner.normalized(),
+            PersonName::parse("Kellner, Lawrence J.")
+                .normalized()
+                .replace('.', "")
+        );
+        assert_eq!(
+            PersonName::parse_delimited("Kellner^Lawrence J^Mr^Jr", "^").to_string(),
+            "Mr Lawrence J Kellner Jr"
+        );
+    }
+
+    #[test]
+    fn person_name_from_row() {
+        let mut sa = vec![""; 45];
+        sa[0] = "SA11AI";
+        sa[5] = "IND";
+        sa[7] = "Doe";
+        sa[8] = "Jane";
+        let mut filing = crate::test_utils::test_filing(&[crate::test_utils::F3XN, &sa]);
+        let row = filing.next_row().unwrap().unwrap();
+        assert_eq!(
+            PersonName::from_row(&row, "contributor", None),
+            Some(name("", "Jane", "", "Doe", ""))
+        );
+        assert_eq!(PersonName::from_row(&row, "donor_candidate", None), None);
+
+        let f3x = legacy_record(
+            "F3XN",
+            &[
+                ("filer_committee_id_number", "C00000001"),
+                ("committee_name", "Test Committee"),
+            ],
+        );
+        let sa = legacy_record("SA11A1", &[("contributor_name", "Kellner|Lawrence J|Mr")]);
+        let mut filing = test_legacy_filing(&[&f3x, &sa]);
+        let row = filing.next_row().unwrap().unwrap();
+        let name_delim = filing.header.name_delim.as_deref();
+        assert_eq!(
+            PersonName::from_row(&row, "contributor", name_delim),
+            Some(name("Mr", "Lawrence", "J", "Kellner", ""))
+        );
+        // without the header's delimiter the name is read as free text
+        assert_ne!(
+            PersonName::from_row(&row, "contributor", None),
+            PersonName::from_row(&row, "contributor", name_delim)
+        );
+    }
+}
@@ -0,0 +1,446 @@
+use csv::StringRecord;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SummaryForm {
+    F3,
+    F3X,
+    F3P,
+}
+
+impl SummaryForm {
+    // ex 'F3XN', 'F3A', 'F3PT'. Other F3 variants (F3L, F3S, F3Z, F3PS...)
+    // don't have the same summary page, so they are not matched.
+    pub fn from_form_type(form_type: &str) -> Option<Self> {
+        let form_type = form_type.to_uppercase();
+        let (form, rest) = if let Some(rest) = form_type.strip_prefix("F3X") {
+            (SummaryForm::F3X, rest)
+        } else if let Some(rest) = form_type.strip_prefix("F3P") {
+            (SummaryForm::F3P, rest)
+        } else if let Some(rest) = form_type.strip_prefix("F3") {
+            (SummaryForm::F3, rest)
+        } else {
+            return None;
+        };
+        match rest {
+            "" | "N" | "A" | "T" => Some(form),
+            _ => None,
+        }
+    }
+}
+
+// A single line of the summary page, with the "Column A" amount for the
+// reporting period and the "Column B" amount for the calendar year/cycle.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SummaryAmount {
+    pub period: Option<f64>,
+    pub cycle_to_date: Option<f64>,
+}
+
+// Summary page totals from F3, F3X and F3P cover records. Lines that don't
+// exist on a given form (ex independent expenditures on F3P) are left empty.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FinancialSummary {
+    pub form: SummaryForm,
+    pub cash_on_hand_beginning: SummaryAmount,
+    pub total_receipts: SummaryAmount,
+    pub total_disbursements: SummaryAmount,
+    pub cash_on_hand_close: SummaryAmount,
+    pub debts_owed_to_committee: SummaryAmount,
+    pub debts_owed_by_committee: SummaryAmount,
+    pub individual_contributions_itemized: SummaryAmount,
+    pub individual_contributions_unitemized: SummaryAmount,
+    pub individual_contributions_total: SummaryAmount,
+    pub party_committee_contributions: SummaryAmount,
+    pub other_committee_contributions: SummaryAmount,
+    pub candidate_contributions: SummaryAmount,
+    pub total_contributions: SummaryAmount,
+    pub transfers_from_affiliated: SummaryAmount,
+    pub loans_received: SummaryAmount,
+    pub loan_repayments_received: SummaryAmount,
+    pub offsets_to_operating_expenditures: SummaryAmount,
+    pub other_receipts: SummaryAmount,
+    pub operating_expenditures: SummaryAmount,
+    pub other_federal_operating_expenditures: SummaryAmount,
+    pub transfers_to_affiliated: SummaryAmount,
+    pub contributions_to_candidates: SummaryAmount,
+    pub independent_expenditures: SummaryAmount,
+    pub coordinated_expenditures: SummaryAmount,
+    pub loan_repayments_made: SummaryAmount,
+    pub loans_made: SummaryAmount,
+    pub refunds_to_individuals: SummaryAmount,
+    pub refunds_to_party_committees: SummaryAmount,
+    pub refunds_to_other_committees: SummaryAmount,
+    pub total_contribution_refunds: SummaryAmount,
+    pub other_disbursements: SummaryAmount,
+    pub net_contributions: SummaryAmount,
+    pub net_operating_expenditures: SummaryAmount,
+}
+
+impl FinancialSummary {
+    pub fn from_record(
+        form_type: &str,
+        columns: &[String],
+        record: &StringRecord,
+    ) -> Option<FinancialSummary> {
+        let form = SummaryForm::from_form_type(form_type)?;
+        let amount = |prefix: &str, name: &str| -> Option<f64> {
+            if name.is_empty() {
+                return None;
+            }
+            let column = format!("{prefix}{name}");
+            let idx = columns.iter().position(|c| *c == column)?;
+            record.get(idx)?.trim().parse::<f64>().ok()
+        };
+        // column names without the 'col_a_'/'col_b_' prefix, empty if the
+        // line doesn't have that column
+        let line = |col_a: &str, col_b: &str| SummaryAmount {
+            period: amount("col_a_", col_a),
+            cycle_to_date: amount("col_b_", col_b),
+        };
+        let none = SummaryAmount::default();
+
+        Some(match form {
+            SummaryForm::F3X => FinancialSummary {
+                form,
+                cash_on_hand_beginning: line("cash_on_hand_beginning_period", "cash_on_hand_jan_1"),
+                total_receipts: line("total_receipts", "total_receipts"),
+                total_disbursements: line("total_disbursements", "total_disbursements"),
+                cash_on_hand_close: line(
+                    "cash_on_hand_close_of_period",
+                    "cash_on_hand_close_of_period",
+                ),
+                debts_owed_to_committee: line("debts_to", ""),
+                debts_owed_by_committee: line("debts_by", ""),
+                individual_contributions_itemized: line(
+                    "individuals_itemized",
+                    "individuals_itemized",
+                ),
+                individual_contributions_unitemized: line(
+                    "individuals_unitemized",
+                    "individuals_unitemized",
+                ),
+                individual_contributions_total: line(
+                    "individual_contribution_total",
+                    "individual_contribution_total",
+                ),
+                party_committee_contributions: line(
+                    "political_party_committees",
+                    "political_party_committees",
+                ),
+                other_committee_contributions: line(
+                    "other_political_committees_pacs",
+                    "other_political_committees_pacs",
+                ),
+                candidate_contributions: none,
+                total_contributions: line("total_contributions", "total_contributions"),
+                transfers_from_affiliated: line(
+                    "transfers_from_aff_other_party_cmttees",
+                    "transfers_from_aff_other_party_cmttees",
+                ),
+                loans_received: line("total_loans", "total_loans"),
+                loan_repayments_received: line(
+                    "total_loan_repayments_received",
+                    "total_loan_repayments_received",
+                ),
+                offsets_to_operating_expenditures: line(
+                    "offsets_to_expenditures",
+                    "offsets_to_expenditures",
+                ),
+                other_receipts: line("other_federal_receipts", "other_federal_receipts"),
+                operating_expenditures: line(
+                    "total_operating_expenditures",
+                    "total_operating_expenditures",
+                ),
+                other_federal_operating_expenditures: line(
+                    "other_federal_operating_expenditures",
+                    "other_federal_operating_expenditures",
+                ),
+                transfers_to_affiliated: line("transfers_to_affiliated", "transfers_to_affiliated"),
+                contributions_to_candidates: line(
+                    "contributions_to_candidates",
+                    "contributions_to_candidates",
+                ),
+                independent_expenditures: line(
+                    "independent_expenditures",
+                    "independent_expenditures",
+                ),
+                coordinated_expenditures: line(
+                    "coordinated_expenditures_by_party_committees",
+                    "coordinated_expenditures_by_party_committees",
+                ),
+                loan_repayments_made: line(
+                    "total_loan_repayments_made",
+                    "total_loan_repayments_made",
+                ),
+                loans_made: line("loans_made", "loans_made"),
+                refunds_to_individuals: line("refunds_to_individuals", "refunds_to_individuals"),
+                refunds_to_party_committees: line(
+                    "refunds_to_party_committees",
+                    "refunds_to_party_committees",
+                ),
+                refunds_to_other_committees: line(
+                    "refunds_to_other_committees",
+                    "refunds_to_other_committees",
+                ),
+                total_contribution_refunds: line("total_refunds", "total_refunds"),
+                other_disbursements: line("other_disbursements", "other_disbursements"),
+                net_contributions: line("net_contributions", "net_contributions"),
+                net_operating_expenditures: line(
+                    "net_operating_expenditures",
+                    "net_operating_expenditures",
+                ),
+            },
+            SummaryForm::F3 => FinancialSummary {
+                form,
+                cash_on_hand_beginning: line("cash_beginning_reporting_period", ""),
+                total_receipts: line("total_receipts", "total_receipts"),
+                total_disbursements: line("total_disbursements", "total_disbursements"),
+                cash_on_hand_close: line("cash_on_hand_close_of_period", ""),
+                debts_owed_to_committee: line("debts_to", ""),
+                debts_owed_by_committee: line("debts_by", ""),
+                individual_contributions_itemized: line(
+                    "individual_contributions_itemized",
+                    "individual_contributions_itemized",
+                ),
+                individual_contributions_unitemized: line(
+                    "individual_contributions_unitemized",
+                    "individual_contributions_unitemized",
+                ),
+                individual_contributions_total: line(
+                    "total_individual_contributions",
+                    "total_individual_contributions",
+                ),
+                party_committee_contributions: line(
+                    "political_party_contributions",
+                    "political_party_contributions",
+                ),
+                other_committee_contributions: line("pac_contributions", "pac_contributions"),
+                candidate_contributions: line("candidate_contributions", "candidate_contributions"),
+                total_contributions: line("total_contributions", "total_contributions"),
+                transfers_from_affiliated: line(
+                    "transfers_from_authorized",
+                    "transfers_from_authorized",
+                ),
+                loans_received: line("total_loans", "total_loans"),
+                loan_repayments_received: none,
+                offsets_to_operating_expenditures: line(
+                    "offset_to_operating_expenditures",
+                    "offset_to_operating_expenditures",
+                ),
+                other_receipts: line("other_receipts", "other_receipts"),
+                operating_expenditures: line("operating_expenditures", "operating_expenditures"),
+                other_federal_operating_expenditures: none,
+                transfers_to_affiliated: line("transfers_to_authorized", "transfers_to_authorized"),
+                contributions_to_candidates: none,
+                independent_expenditures: none,
+                coordinated_expenditures: none,
+                loan_repayments_made: line("total_loan_repayments", "total_loan_repayments"),
+                loans_made: none,
+                refunds_to_individuals: line("refunds_to_individuals", "refunds_to_individuals"),
+                refunds_to_party_committees: line(
+                    "refunds_to_party_committees",
+                    "refunds_to_party_committees",
+                ),
+                refunds_to_other_committees: line(
+                    "refunds_to_other_committees",
+                    "refunds_to_other_committees",
+                ),
+                total_contribution_refunds: line("total_refunds", "total_refunds"),
+                other_disbursements: line("other_disbursements", "other_disbursements"),
+                net_contributions: line("net_contributions", "net_contributions"),
+                net_operating_expenditures: line(
+                    "net_operating_expenditures",
+                    "net_operating_expenditures",
+                ),
+            },
+            SummaryForm::F3P => FinancialSummary {
+                form,
+                cash_on_hand_beginning: line("cash_on_hand_beginning_period", ""),
+                total_receipts: line("total_receipts", "total_receipts"),
+                total_disbursements: line("total_disbursements", "total_disbursements"),
+                cash_on_hand_close: line("cash_on_hand_close_of_period", ""),
+                debts_owed_to_committee: line("debts_to", ""),
+                debts_owed_by_committee: line("debts_by", ""),
+                individual_contributions_itemized: line(
+                    "individuals_itemized",
+                    "individuals_itemized",
+                ),
+                individual_contributions_unitemized: line(
+                    "individuals_unitemized",
+                    "individuals_unitemized",
+                ),
+                individual_contributions_total: line(
+                    "individual_contribution_total",
+                    "individual_contribution_total",
+                ),
+                party_committee_contributions: line(
+                    "political_party_committees_receipts",
+                    "political_party_committees_receipts",
+                ),
+                other_committee_contributions: line(
+                    "other_political_committees_pacs",
+                    "other_political_committees_pacs",
+                ),
+                candidate_contributions: line("the_candidate", "the_candidate"),
+                total_contributions: line(
+                    "total_contributions",
+                    "total_contributions_other_than_loans",
+                ),
+                transfers_from_affiliated: line(
+                    "transfers_from_aff_other_party_cmttees",
+                    "transfers_from_aff_other_party_cmttees",
+                ),
+                loans_received: line("total_loans", "total_loans"),
+                loan_repayments_received: none,
+                offsets_to_operating_expenditures: line(
+                    "total_offsets_to_expenditures",
+                    "total_offsets_to_operating_expenditures",
+                ),
+                other_receipts: line("other_receipts", "other_receipts"),
+                operating_expenditures: line("operating_expenditures", "operating_expenditures"),
+                other_federal_operating_expenditures: none,
+                transfers_to_affiliated: line(
+                    "transfers_to_other_authorized_committees",
+                    "transfers_to_other_authorized_committees",
+                ),
+                contributions_to_candidates: none,
+                independent_expenditures: none,
+                coordinated_expenditures: none,
+                loan_repayments_made: line(
+                    "total_loan_repayments_made",
+                    "total_loan_repayments_made",
+                ),
+                loans_made: none,
+                refunds_to_individuals: line("individuals", "individuals"),
+                refunds_to_party_committees: line(
+                    "political_party_committees_refunds",
+                    "political_party_committees_refunds",
+                ),
+                refunds_to_other_committees: line(
+                    "other_political_committees",
+                    "other_political_committees",
+                ),
+                total_contribution_refunds: line(
+                    "total_contributions_refunds",
+                    "total_contributions_refunds",
+                ),
+                other_disbursements: line("other_disbursements", "other_disbursements"),
+                net_contributions: line("net_contributions", ""),
+                net_operating_expenditures: line("net_operating_expenditures", ""),
+            },
+        })
+    }
+
+    // every line in summary page order, keyed by the field name
+    pub fn lines(&self) -> [(&'static str, SummaryAmount); 33] {
+        [
+            ("cash_on_hand_beginning", self.cash_on_hand_beginning),
+            ("total_receipts", self.total_receipts),
+            ("total_disbursements", self.total_disbursements),
+            ("cash_on_hand_close", self.cash_on_hand_close),
+            ("debts_owed_to_committee", self.debts_owed_to_committee),
+            ("debts_owed_by_committee", self.debts_owed_by_committee),
+            (
+                "individual_contributions_itemized",
+                self.individual_contributions_itemized,
+            ),
+            (
+                "individual_contributions_unitemized",
+                self.individual_contributions_unitemized,
+            ),
+            (
+                "individual_contributions_total",
+                self.individual_contributions_total,
+            ),
+            (
+                "party_committee_contributions",
+                self.party_committee_contributions,
+            ),
+            (
+                "other_committee_contributions",
+                self.other_committee_contributions,
+            ),
+            ("candidate_contributions", self.candidate_contributions),
+            ("total_contributions", self.total_contributions),
+            ("transfers_from_affiliated", self.transfers_from_affiliated),
+            ("loans_received", self.loans_received),
+            ("loan_repayments_received", self.loan_repayments_received),
+            (
+                "offsets_to_operating_expenditures",
+                self.offsets_to_operating_expenditures,
+            ),
+            ("other_receipts", self.other_receipts),
+            ("operating_expenditures", self.operating_expenditures),
+            (
+                "other_federal_operating_expenditures",
+                self.other_federal_operating_expenditures,
+            ),
+            ("transfers_to_affiliated", self.transfers_to_affiliated),
+            (
+                "contributions_to_candidates",
+                self.contributions_to_candidates,
+            ),
+            ("independent_expenditures", self.independent_expenditures),
+            ("coordinated_expenditures", self.coordinated_expenditures),
+            ("loan_repayments_made", self.loan_repayments_made),
+            ("loans_made", self.loans_made),
+            ("refunds_to_individuals", self.refunds_to_individuals),
+            (
+                "refunds_to_party_committees",
+                self.refunds_to_party_committees,
+            ),
+            (
+                "refunds_to_other_committees",
+                self.refunds_to_other_committees,
+            ),
+            (
+                "total_contribution_refunds",
+                self.total_contribution_refunds,
+            ),
+            ("other_disbursements", self.other_disbursements),
+            ("net_contributions", self.net_contributions),
+            (
+                "net_operating_expenditures",
+                self.net_operating_expenditures,
+            ),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{test_filing, F3XN};
+
+    #[test]
+    fn summary_form() {
+        assert_eq!(SummaryForm::from_form_type("F3XN"), Some(SummaryForm::F3X));
+        assert_eq!(SummaryForm::from_form_type("F3A"), Some(SummaryForm::F3));
+        assert_eq!(SummaryForm::from_form_type("F3PT"), Some(SummaryForm::F3P));
+        assert_eq!(SummaryForm::from_form_type("F3L"), None);
+        assert_eq!(SummaryForm::from_form_type("F3PS"), None);
+        assert_eq!(SummaryForm::from_form_type("F24N"), None);
+    }
+
+    #[test]
+    fn f3x_summary() {
+        let mut cover = F3XN.to_vec();
+        cover.resize(22, "");
+        // col_a_cash_on_hand_beginning_period .. col_a_debts_by
+        cover.extend(["100.00", "500.00", "600.00", "200.00", "400.00", "", "25"]);
+        let filing = test_filing(&[&cover]);
+        let summary = filing.cover.financial_summary().unwrap();
+
+        assert_eq!(summary.form, SummaryForm::F3X);
+        assert_eq!(summary.cash_on_hand_beginning.period, Some(100.0));
+        assert_eq!(summary.cash_on_hand_beginning.cycle_to_date, None);
+        assert_eq!(summary.total_receipts.period, Some(500.0));
+        assert_eq!(summary.cash_on_hand_close.period, Some(400.0));
+        assert_eq!(summary.debts_owed_to_committee.period, None);
+        assert_eq!(summary.debts_owed_by_committee.period, Some(25.0));
+        assert_eq!(
+            summary.lines()[3],
+            ("cash_on_hand_close", summary.cash_on_hand_close)
+        );
+    }
+}
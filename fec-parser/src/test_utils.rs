@@ -0,0 +1,60 @@
+use crate::{mappings::column_names_for_field, Filing};
+use std::io::Cursor;
+
+pub(crate) const HDR: &[&str] = &["HDR", "FEC", "8.4", "libfec", "0.1.0", "", "", ""];
+
+pub(crate) const F3XN: &[&str] = &[
+    "F3XN",
+    "C00000001",
+    "Test Committee",
+    "",
+    "1 Main St",
+    "",
+    "Washington",
+    "DC",
+    "20001",
+    "Q2",
+    "",
+    "",
+    "",
+    "20240401",
+    "20240630",
+];
+
+// builds an in-memory 8.4 filing, with the given records following the header
+pub(crate) fn test_filing(records: &[&[&str]]) -> Filing<Cursor<Vec<u8>>> {
+    let mut contents = HDR.join("\x1c");
+    contents.push('\n');
+    for record in records {
+        contents += &record.join("\x1c");
+        contents.push('\n');
+    }
+    Filing::from_reader(Cursor::new(contents.into_bytes()), "1".to_owned(), None).unwrap()
+}
+
+// a 5.3 header, comma separated with "|" as the name delimiter
+pub(crate) const LEGACY_HDR: &[&str] = &[
+    "HDR", "FEC", "5.3", "libfec", "0.1.0", "|", "FEC-1", "1", "",
+];
+
+// a 5.3 record of the given form type, with the named fields set
+pub(crate) fn legacy_record<'a>(form_type: &'a str, values: &[(&str, &'a str)]) -> Vec<&'a str> {
+    let columns = column_names_for_field(form_type, "5.3").unwrap();
+    let mut record = vec![""; columns.len()];
+    record[0] = form_type;
+    for (name, value) in values {
+        record[columns.iter().position(|v| v == name).unwrap()] = value;
+    }
+    record
+}
+
+// builds an in-memory 5.3 filing, with every field quoted
+pub(crate) fn test_legacy_filing(records: &[&[&str]]) -> Filing<Cursor<Vec<u8>>> {
+    let mut contents = String::new();
+    for record in std::iter::once(&LEGACY_HDR).chain(records) {
+        let fields: Vec<String> = record.iter().map(|v| format!("\"{v}\"")).collect();
+        contents += &fields.join(",");
+        contents.push('\n');
+    }
+    Filing::from_reader(Cursor::new(contents.into_bytes()), "1".to_owned(), None).unwrap()
+}
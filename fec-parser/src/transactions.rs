@@ -0,0 +1,305 @@
+use crate::{mappings::column_names_for_field, Filing, FilingRow, FilingRowReadError};
+use indexmap::IndexMap;
+use std::{collections::HashMap, io::Read};
+
+// the transaction ID column is named differently across schedules,
+// ex 'transaction_id' on SA vs 'transaction_id_number' on SB/SC/SC1
+pub const TRANSACTION_ID_COLUMNS: &[&str] = &["transaction_id", "transaction_id_number"];
+const BACK_REFERENCE_ID_COLUMNS: &[&str] = &["back_reference_tran_id_number"];
+const BACK_REFERENCE_SCHED_COLUMNS: &[&str] = &[
+    "back_reference_sched_name",
+    "back_reference_sched_form_name",
+];
+const TEXT_COLUMNS: &[&str] = &["text"];
+
+fn position(columns: &[String], candidates: &[&str]) -> Option<usize> {
+    columns
+        .iter()
+        .position(|column| candidates.contains(&column.as_str()))
+}
+
+fn non_empty(row: &FilingRow, idx: Option<usize>) -> Option<String> {
+    idx.and_then(|idx| row.record.get(idx))
+        .map(|v| v.trim())
+        .filter(|v| !v.is_empty())
+        .map(|v| v.to_owned())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransactionLink {
+    pub row_type: String,
+    pub transaction_id: String,
+    pub parent_transaction_id: Option<String>,
+    pub parent_sched_name: Option<String>,
+}
+
+// A TEXT record, which holds a memo narrative for the transaction it
+// references. Memos without a target transaction describe the whole report.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextMemo {
+    pub transaction_id: Option<String>,
+    pub target_transaction_id: Option<String>,
+    pub target_sched_name: Option<String>,
+    pub text: String,
+}
+
+// Indices of the transaction/back reference columns for a single row type,
+// so they can be resolved once and re-used for every row of that type.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TransactionColumns {
+    transaction_id: Option<usize>,
+    back_reference_tran_id: Option<usize>,
+    back_reference_sched_name: Option<usize>,
+    text: Option<usize>,
+}
+
+impl TransactionColumns {
+    pub fn new(columns: &[String]) -> Self {
+        Self {
+            transaction_id: position(columns, TRANSACTION_ID_COLUMNS),
+            back_reference_tran_id: position(columns, BACK_REFERENCE_ID_COLUMNS),
+            back_reference_sched_name: position(columns, BACK_REFERENCE_SCHED_COLUMNS),
+            text: position(columns, TEXT_COLUMNS),
+        }
+    }
+
+    pub fn transaction_id_column<'a>(&self, columns: &'a [String]) -> Option<&'a str> {
+        self.transaction_id
+            .and_then(|idx| columns.get(idx))
+            .map(|column| column.as_str())
+    }
+
+    pub fn for_field(row_type: &str, fec_version: &str) -> Self {
+        column_names_for_field(row_type, fec_version)
+            .map(|columns| Self::new(columns))
+            .unwrap_or_default()
+    }
+
+    pub fn has_transaction_id(&self) -> bool {
+        self.transaction_id.is_some() && !self.is_text_memo()
+    }
+
+    // TEXT records also have a back reference, but it points to the
+    // transaction being described rather than a parent transaction.
+    pub fn has_back_reference(&self) -> bool {
+        self.back_reference_tran_id.is_some() && !self.is_text_memo()
+    }
+
+    pub fn is_text_memo(&self) -> bool {
+        self.text.is_some()
+    }
+
+    pub fn link(&self, row: &FilingRow) -> Option<TransactionLink> {
+        if self.is_text_memo() {
+            return None;
+        }
+        let transaction_id = non_empty(row, self.transaction_id)?;
+        Some(TransactionLink {
+            row_type: row.row_type.clone(),
+            transaction_id,
+            parent_transaction_id: non_empty(row, self.back_reference_tran_id),
+            parent_sched_name: non_empty(row, self.back_reference_sched_name),
+        })
+    }
+
+    pub fn memo(&self, row: &FilingRow) -> Option<TextMemo> {
+        let text = row.record.get(self.text?)?.trim().to_owned();
+        Some(TextMemo {
+            transaction_id: non_empty(row, self.transaction_id),
+            target_transaction_id: non_empty(row, self.back_reference_tran_id),
+            target_sched_name: non_empty(row, self.back_reference_sched_name),
+            text,
+        })
+    }
+}
+
+// Parent/child graph of the transactions inside a single filing, built from
+// the back reference fields on each row. For example earmarked memo SA rows
+// point at their conduit SA row, or SC1 rows at their SC loan. TEXT records
+// are kept separately as memos on the transaction they describe.
+#[derive(Debug, Default)]
+pub struct TransactionGraph {
+    transactions: IndexMap<String, TransactionLink>,
+    children: HashMap<String, Vec<String>>,
+    memos: Vec<TextMemo>,
+}
+
+impl TransactionGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_filing<R: Read>(filing: &mut Filing<R>) -> Result<Self, FilingRowReadError> {
+        let mut graph = Self::new();
+        let mut columns_map: HashMap<String, TransactionColumns> = HashMap::new();
+        while let Some(row) = filing.next_row() {
+            let row = row?;
+            // lookup with the raw form type, since stripping the '/' from
+            // ex 'SC/10' would match the SC1 mappings instead
+            let columns = columns_map.entry(row.row_type.clone()).or_insert_with(|| {
+                TransactionColumns::for_field(
+                    row.record.get(0).unwrap_or(&row.row_type),
+                    &filing.header.fec_version,
+                )
+            });
+            if let Some(link) = columns.link(&row) {
+                graph.insert(link);
+            } else if let Some(memo) = columns.memo(&row) {
+                graph.insert_memo(memo);
+            }
+        }
+        Ok(graph)
+    }
+
+    pub fn insert_memo(&mut self, memo: TextMemo) {
+        self.memos.push(memo);
+    }
+
+    // Transaction IDs are unique within a filing, so only the first row with
+    // a given ID is kept.
+    pub fn insert(&mut self, link: TransactionLink) {
+        if self.transactions.contains_key(&link.transaction_id) {
+            return;
+        }
+        if let Some(ref parent) = link.parent_transaction_id {
+            self.children
+                .entry(parent.clone())
+                .or_default()
+                .push(link.transaction_id.clone());
+        }
+        self.transactions.insert(link.transaction_id.clone(), link);
+    }
+
+    pub fn len(&self) -> usize {
+        self.transactions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.transactions.is_empty()
+    }
+
+    pub fn get(&self, transaction_id: &str) -> Option<&TransactionLink> {
+        self.transactions.get(transaction_id)
+    }
+
+    pub fn transactions(&self) -> impl Iterator<Item = &TransactionLink> {
+        self.transactions.values()
+    }
+
+    pub fn parent(&self, transaction_id: &str) -> Option<&TransactionLink> {
+        self.get(transaction_id)?
+            .parent_transaction_id
+            .as_ref()
+            .and_then(|parent| self.get(parent))
+    }
+
+    pub fn children(&self, transaction_id: &str) -> impl Iterator<Item = &TransactionLink> {
+        self.children
+            .get(transaction_id)
+            .into_iter()
+            .flatten()
+            .filter_map(|child| self.get(child))
+    }
+
+    // Follows parent links up to the top-most transaction. Stops early on
+    // cycles or on back references to transactions not in this filing.
+    pub fn root(&self, transaction_id: &str) -> Option<&TransactionLink> {
+        let mut current = self.get(transaction_id)?;
+        for _ in 0..self.transactions.len() {
+            match self.parent(&current.transaction_id) {
+                Some(parent) => current = parent,
+                None => break,
+            }
+        }
+        Some(current)
+    }
+
+    pub fn memos(&self) -> impl Iterator<Item = &TextMemo> {
+        self.memos.iter()
+    }
+
+    pub fn memos_for<'a>(&'a self, transaction_id: &'a str) -> impl Iterator<Item = &'a TextMemo> {
+        self.memos
+            .iter()
+            .filter(move |memo| memo.target_transaction_id.as_deref() == Some(transaction_id))
+    }
+
+    // The transaction a TEXT memo describes, if it appears in this filing
+    pub fn memo_target(&self, memo: &TextMemo) -> Option<&TransactionLink> {
+        memo.target_transaction_id
+            .as_ref()
+            .and_then(|target| self.get(target))
+    }
+
+    // Transactions with a back reference, ie every child -> parent edge
+    pub fn links(&self) -> impl Iterator<Item = &TransactionLink> {
+        self.transactions
+            .values()
+            .filter(|link| link.parent_transaction_id.is_some())
+    }
+
+    // Transactions whose back reference points to a transaction ID that
+    // doesn't appear in this filing
+    pub fn orphans(&self) -> impl Iterator<Item = &TransactionLink> {
+        self.links().filter(|link| {
+            link.parent_transaction_id
+                .as_ref()
+                .is_some_and(|parent| !self.transactions.contains_key(parent))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{test_filing, F3XN};
+
+    #[test]
+    fn builds_graph() {
+        let mut filing = test_filing(&[
+            F3XN,
+            &["SA11AI", "C00000001", "SA.1", "", "", "IND"],
+            &["SA11AI", "C00000001", "SA.2", "SA.1", "SA11AI", "IND"],
+            &["SA11AI", "C00000001", "SA.3", "SA.1", "SA11AI", "IND"],
+            &["SC/10", "C00000001", "SC.1", "13", "ORG"],
+            &["SC1/10", "C00000001", "SC1.1", "SC.1", "Bank"],
+            &["SB21B", "C00000001", "SB.1", "SB.404", "SB21B", "ORG"],
+            &["TEXT", "C00000001", "TEXT.1", "SA.2", "SA11AI", "Earmarked"],
+            &["TEXT", "C00000001", "TEXT.2", "", "F3XN", "Amended report"],
+        ]);
+        let graph = TransactionGraph::from_filing(&mut filing).unwrap();
+
+        assert_eq!(graph.len(), 6);
+        assert_eq!(graph.parent("SA.2").unwrap().transaction_id, "SA.1");
+        assert_eq!(
+            graph
+                .children("SA.1")
+                .map(|link| link.transaction_id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["SA.2", "SA.3"]
+        );
+        assert!(graph.parent("SA.1").is_none());
+
+        let sc1 = graph.get("SC1.1").unwrap();
+        assert_eq!(sc1.row_type, "SC110");
+        assert_eq!(sc1.parent_transaction_id.as_deref(), Some("SC.1"));
+        assert_eq!(sc1.parent_sched_name, None);
+        assert_eq!(graph.root("SC1.1").unwrap().transaction_id, "SC.1");
+
+        assert_eq!(graph.links().count(), 4);
+        assert_eq!(
+            graph
+                .orphans()
+                .map(|link| link.transaction_id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["SB.1"]
+        );
+
+        assert!(graph.get("TEXT.1").is_none());
+        assert_eq!(graph.memos().count(), 2);
+        let memo = graph.memos_for("SA.2").next().unwrap();
+        assert_eq!(memo.text, "Earmarked");
+        assert_eq!(graph.memo_target(memo).unwrap().row_type, "SA11AI");
+        assert_eq!(graph.memos_for("SA.1").count(), 0);
+    }
+}
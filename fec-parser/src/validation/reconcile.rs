@@ -0,0 +1,445 @@
+use crate::{
+    mappings::column_names_for_field,
+    summary::{FinancialSummary, SummaryAmount, SummaryForm},
+    Filing, FilingRow, FilingRowReadError,
+};
+use std::{collections::HashMap, io::Read};
+
+// A summary page line that has matching itemizations. Row types that start
+// with `row_type_prefix` are summed together, ex SA11AI -> line 11(a)(i).
+pub struct ReconcileRule {
+    pub line: &'static str,
+    pub row_type_prefix: &'static str,
+    pub description: &'static str,
+    pub summary_line: fn(&FinancialSummary) -> SummaryAmount,
+}
+
+macro_rules! rule {
+    ($line:expr, $prefix:expr, $description:expr, $field:ident) => {
+        ReconcileRule {
+            line: $line,
+            row_type_prefix: $prefix,
+            description: $description,
+            summary_line: |summary| summary.$field,
+        }
+    };
+}
+
+pub static F3X_RULES: &[ReconcileRule] = &[
+    rule!(
+        "11(a)(i)",
+        "SA11AI",
+        "Contributions from individuals, itemized",
+        individual_contributions_itemized
+    ),
+    rule!(
+        "11(b)",
+        "SA11B",
+        "Contributions from political party committees",
+        party_committee_contributions
+    ),
+    rule!(
+        "11(c)",
+        "SA11C",
+        "Contributions from other political committees",
+        other_committee_contributions
+    ),
+    rule!(
+        "12",
+        "SA12",
+        "Transfers from affiliated/other party committees",
+        transfers_from_affiliated
+    ),
+    rule!("13", "SA13", "All loans received", loans_received),
+    rule!(
+        "14",
+        "SA14",
+        "Loan repayments received",
+        loan_repayments_received
+    ),
+    rule!(
+        "15",
+        "SA15",
+        "Offsets to operating expenditures",
+        offsets_to_operating_expenditures
+    ),
+    rule!("17", "SA17", "Other federal receipts", other_receipts),
+    rule!(
+        "21(b)",
+        "SB21B",
+        "Other federal operating expenditures",
+        other_federal_operating_expenditures
+    ),
+    rule!(
+        "22",
+        "SB22",
+        "Transfers to affiliated/other party committees",
+        transfers_to_affiliated
+    ),
+    rule!(
+        "23",
+        "SB23",
+        "Contributions to federal candidates/committees",
+        contributions_to_candidates
+    ),
+    rule!(
+        "24",
+        "SE",
+        "Independent expenditures",
+        independent_expenditures
+    ),
+    rule!(
+        "25",
+        "SF",
+        "Coordinated party expenditures",
+        coordinated_expenditures
+    ),
+    rule!("26", "SB26", "Loan repayments made", loan_repayments_made),
+    rule!("27", "SB27", "Loans made", loans_made),
+    rule!(
+        "28(a)",
+        "SB28A",
+        "Refunds to individuals",
+        refunds_to_individuals
+    ),
+    rule!(
+        "28(b)",
+        "SB28B",
+        "Refunds to political party committees",
+        refunds_to_party_committees
+    ),
+    rule!(
+        "28(c)",
+        "SB28C",
+        "Refunds to other political committees",
+        refunds_to_other_committees
+    ),
+    rule!("29", "SB29", "Other disbursements", other_disbursements),
+];
+
+pub static F3_RULES: &[ReconcileRule] = &[
+    rule!(
+        "11(a)(i)",
+        "SA11AI",
+        "Contributions from individuals, itemized",
+        individual_contributions_itemized
+    ),
+    rule!(
+        "11(b)",
+        "SA11B",
+        "Contributions from political party committees",
+        party_committee_contributions
+    ),
+    rule!(
+        "11(c)",
+        "SA11C",
+        "Contributions from other political committees",
+        other_committee_contributions
+    ),
+    rule!(
+        "11(d)",
+        "SA11D",
+        "Contributions from the candidate",
+        candidate_contributions
+    ),
+    rule!(
+        "12",
+        "SA12",
+        "Transfers from other authorized committees",
+        transfers_from_affiliated
+    ),
+    rule!("13", "SA13", "Loans", loans_received),
+    rule!(
+        "14",
+        "SA14",
+        "Offsets to operating expenditures",
+        offsets_to_operating_expenditures
+    ),
+    rule!("15", "SA15", "Other receipts", other_receipts),
+    rule!(
+        "17",
+        "SB17",
+        "Operating expenditures",
+        operating_expenditures
+    ),
+    rule!(
+        "18",
+        "SB18",
+        "Transfers to other authorized committees",
+        transfers_to_affiliated
+    ),
+    rule!("19", "SB19", "Loan repayments", loan_repayments_made),
+    rule!(
+        "20(a)",
+        "SB20A",
+        "Refunds to individuals",
+        refunds_to_individuals
+    ),
+    rule!(
+        "20(b)",
+        "SB20B",
+        "Refunds to political party committees",
+        refunds_to_party_committees
+    ),
+    rule!(
+        "20(c)",
+        "SB20C",
+        "Refunds to other political committees",
+        refunds_to_other_committees
+    ),
+    rule!("21", "SB21", "Other disbursements", other_disbursements),
+];
+
+pub static F3P_RULES: &[ReconcileRule] = &[
+    rule!(
+        "17(a)(i)",
+        "SA17A",
+        "Contributions from individuals, itemized",
+        individual_contributions_itemized
+    ),
+    rule!(
+        "17(b)",
+        "SA17B",
+        "Contributions from political party committees",
+        party_committee_contributions
+    ),
+    rule!(
+        "17(c)",
+        "SA17C",
+        "Contributions from other political committees",
+        other_committee_contributions
+    ),
+    rule!(
+        "17(d)",
+        "SA17D",
+        "Contributions from the candidate",
+        candidate_contributions
+    ),
+    rule!(
+        "18",
+        "SA18",
+        "Transfers from affiliated/other party committees",
+        transfers_from_affiliated
+    ),
+    rule!("19", "SA19", "Loans", loans_received),
+    rule!(
+        "20",
+        "SA20",
+        "Offsets to expenditures",
+        offsets_to_operating_expenditures
+    ),
+    rule!("21", "SA21", "Other receipts", other_receipts),
+    rule!(
+        "23",
+        "SB23",
+        "Operating expenditures",
+        operating_expenditures
+    ),
+    rule!(
+        "24",
+        "SB24",
+        "Transfers to other authorized committees",
+        transfers_to_affiliated
+    ),
+    rule!("27", "SB27", "Loan repayments made", loan_repayments_made),
+    rule!(
+        "28(a)",
+        "SB28A",
+        "Refunds to individuals",
+        refunds_to_individuals
+    ),
+    rule!(
+        "28(b)",
+        "SB28B",
+        "Refunds to political party committees",
+        refunds_to_party_committees
+    ),
+    rule!(
+        "28(c)",
+        "SB28C",
+        "Refunds to other political committees",
+        refunds_to_other_committees
+    ),
+    rule!("29", "SB29", "Other disbursements", other_disbursements),
+];
+
+pub fn rules_for_form(form: SummaryForm) -> &'static [ReconcileRule] {
+    match form {
+        SummaryForm::F3 => F3_RULES,
+        SummaryForm::F3X => F3X_RULES,
+        SummaryForm::F3P => F3P_RULES,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReconcileLine {
+    pub line: &'static str,
+    pub row_type_prefix: &'static str,
+    pub description: &'static str,
+    pub itemized_count: usize,
+    pub itemized_total: f64,
+    pub reported: Option<f64>,
+}
+
+impl ReconcileLine {
+    // reported total minus the sum of itemizations, rounded to cents
+    pub fn difference(&self) -> f64 {
+        let difference = self.reported.unwrap_or(0.0) - self.itemized_total;
+        (difference * 100.0).round() / 100.0
+    }
+
+    pub fn exceeds(&self, tolerance: f64) -> bool {
+        self.difference().abs() > tolerance
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct AmountColumns {
+    amount: Option<usize>,
+    memo_code: Option<usize>,
+}
+
+impl AmountColumns {
+    fn new(columns: &[String]) -> Self {
+        Self {
+            amount: columns
+                .iter()
+                .position(|c| c == "contribution_amount" || c == "expenditure_amount"),
+            memo_code: columns.iter().position(|c| c == "memo_code"),
+        }
+    }
+}
+
+// Sums itemizations per summary page line, to compare against the totals the
+// filer reported on the cover record. Memo entries are excluded, since they
+// are already included in another itemization's amount.
+pub struct Reconciliation {
+    pub form: SummaryForm,
+    pub lines: Vec<ReconcileLine>,
+    fec_version: String,
+    columns_map: HashMap<String, AmountColumns>,
+}
+
+impl Reconciliation {
+    pub fn new(summary: &FinancialSummary, fec_version: &str) -> Self {
+        let lines = rules_for_form(summary.form)
+            .iter()
+            .map(|rule| ReconcileLine {
+                line: rule.line,
+                row_type_prefix: rule.row_type_prefix,
+                description: rule.description,
+                itemized_count: 0,
+                itemized_total: 0.0,
+                reported: (rule.summary_line)(summary).period,
+            })
+            .collect();
+        Self {
+            form: summary.form,
+            lines,
+            fec_version: fec_version.to_owned(),
+            columns_map: HashMap::new(),
+        }
+    }
+
+    // None when the filing doesn't have a summary page (ex F24, F99)
+    pub fn from_filing<R: Read>(
+        filing: &mut Filing<R>,
+    ) -> Result<Option<Self>, FilingRowReadError> {
+        let summary = match filing.cover.financial_summary() {
+            Some(summary) => summary,
+            None => return Ok(None),
+        };
+        let mut reconciliation = Self::new(&summary, &filing.header.fec_version);
+        while let Some(row) = filing.next_row() {
+            reconciliation.add_row(&row?);
+        }
+        Ok(Some(reconciliation))
+    }
+
+    pub fn add_row(&mut self, row: &FilingRow) {
+        let line = match self
+            .lines
+            .iter_mut()
+            .find(|line| row.row_type.starts_with(line.row_type_prefix))
+        {
+            Some(line) => line,
+            None => return,
+        };
+        let fec_version = &self.fec_version;
+        let columns = self
+            .columns_map
+            .entry(row.row_type.clone())
+            .or_insert_with(|| {
+                column_names_for_field(row.record.get(0).unwrap_or(&row.row_type), fec_version)
+                    .map(|columns| AmountColumns::new(columns))
+                    .unwrap_or_default()
+            });
+
+        let is_memo = columns
+            .memo_code
+            .and_then(|idx| row.record.get(idx))
+            .is_some_and(|memo_code| memo_code.trim().eq_ignore_ascii_case("X"));
+        if is_memo {
+            return;
+        }
+        let amount = columns
+            .amount
+            .and_then(|idx| row.record.get(idx))
+            .and_then(|amount| amount.trim().parse::<f64>().ok());
+        if let Some(amount) = amount {
+            line.itemized_count += 1;
+            line.itemized_total += amount;
+        }
+    }
+
+    pub fn exceeding(&self, tolerance: f64) -> impl Iterator<Item = &ReconcileLine> {
+        self.lines
+            .iter()
+            .filter(move |line| line.exceeds(tolerance))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{test_filing, F3XN};
+
+    fn sa(transaction_id: &str, amount: &str, memo_code: &str) -> Vec<String> {
+        let mut row = vec![String::new(); 45];
+        row[0] = "SA11AI".to_owned();
+        row[2] = transaction_id.to_owned();
+        row[20] = amount.to_owned();
+        row[42] = memo_code.to_owned();
+        row
+    }
+
+    #[test]
+    fn reconciles_f3x() {
+        let mut cover = F3XN.to_vec();
+        cover.resize(29, "");
+        // col_a_individuals_itemized
+        cover.push("150.00");
+        let rows = [
+            sa("SA.1", "100.00", ""),
+            sa("SA.2", "100.00", "X"),
+            sa("SA.3", "25.50", ""),
+        ];
+        let rows: Vec<Vec<&str>> = rows
+            .iter()
+            .map(|row| row.iter().map(|v| v.as_str()).collect())
+            .collect();
+        let mut records: Vec<&[&str]> = vec![&cover];
+        records.extend(rows.iter().map(|row| row.as_slice()));
+        let mut filing = test_filing(&records);
+
+        let reconciliation = Reconciliation::from_filing(&mut filing).unwrap().unwrap();
+        let line = &reconciliation.lines[0];
+        assert_eq!(line.line, "11(a)(i)");
+        assert_eq!(line.itemized_count, 2);
+        assert_eq!(line.itemized_total, 125.5);
+        assert_eq!(line.reported, Some(150.0));
+        assert_eq!(line.difference(), 24.5);
+        assert_eq!(reconciliation.exceeding(0.01).count(), 1);
+        assert_eq!(reconciliation.exceeding(25.0).count(), 0);
+    }
+}
@@ -0,0 +1,429 @@
+use crate::{
+    mappings::{column_names_for_field, field_idx, DATE_COLUMNS, FLOAT_COLUMNS, FORM_TYPES},
+    FecDate, Filing, FilingRowReadError,
+};
+use csv::StringRecord;
+use fec_parser_macros::{gen_spec_codes, gen_spec_columns, gen_spec_fields};
+use regex::Regex;
+use std::{collections::HashMap, io::Read};
+
+// code lists, ex "entity_type" -> ["CAN", "CCM", ...]
+pub static SPEC_CODES: &[(&str, &[&str])] = &gen_spec_codes!("");
+// column name -> format, either a code list name from SPEC_CODES or one of
+// "committee_id", "candidate_id", "election_code"
+pub static SPEC_COLUMNS: &[(&str, &str)] = &gen_spec_columns!("");
+// (column, required, max length)
+pub type FieldSpec = (&'static str, bool, usize);
+// (form type regex from mappings2.json, fec version regex, fields)
+pub static SPEC_FIELDS: &[(&str, &str, &[FieldSpec])] = &gen_spec_fields!("");
+
+// the FEC was created in 1975, so earlier dates are most likely typos
+const MIN_YEAR: u32 = 1975;
+
+lazy_static::lazy_static! {
+  static ref COMMITTEE_ID: Regex = Regex::new(r"^C\d{8}$").unwrap();
+  // office, election year digit, state (or "00" for president), sequence number
+  static ref CANDIDATE_ID: Regex = Regex::new(r"^[HSP]\d[0-9A-Z]{2}\d{5}$").unwrap();
+  // P primary, G general, R runoff, S special, C convention, E recount, O other
+  static ref ELECTION_CODE: Regex = Regex::new(r"^[PGRSCEO]\d{4}$").unwrap();
+}
+
+pub fn codes(name: &str) -> Option<&'static [&'static str]> {
+    SPEC_CODES
+        .iter()
+        .find(|(code_name, _)| *code_name == name)
+        .map(|(_, codes)| *codes)
+}
+
+pub fn spec_fields(form_type: &str, fec_version: &str) -> Option<&'static [FieldSpec]> {
+    let form_key = FORM_TYPES.get(field_idx(form_type)?)?;
+    SPEC_FIELDS
+        .iter()
+        .filter(|(key, _, _)| key == form_key)
+        .find(|(_, version, _)| {
+            Regex::new(version)
+                .map(|version| version.is_match(fec_version))
+                .unwrap_or(false)
+        })
+        .map(|(_, _, fields)| *fields)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rule {
+    UnknownRowType,
+    NoSpec,
+    Required,
+    MaxLength,
+    Code,
+    CommitteeId,
+    CandidateId,
+    ElectionCode,
+    Date,
+    DateRange,
+    Amount,
+}
+
+impl Rule {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Rule::UnknownRowType => "unknown_row_type",
+            Rule::NoSpec => "no_spec",
+            Rule::Required => "required",
+            Rule::MaxLength => "max_length",
+            Rule::Code => "code",
+            Rule::CommitteeId => "committee_id",
+            Rule::CandidateId => "candidate_id",
+            Rule::ElectionCode => "election_code",
+            Rule::Date => "date",
+            Rule::DateRange => "date_range",
+            Rule::Amount => "amount",
+        }
+    }
+
+    // values that are too long or have implausible dates are still
+    // readable, everything else likely means the value is wrong
+    pub fn severity(&self) -> Severity {
+        match self {
+            Rule::UnknownRowType | Rule::NoSpec | Rule::MaxLength | Rule::DateRange => {
+                Severity::Warning
+            }
+            _ => Severity::Error,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Finding {
+    pub line: Option<u64>,
+    pub row_type: String,
+    pub column: Option<String>,
+    pub value: Option<String>,
+    pub rule: Rule,
+    pub message: String,
+}
+
+impl Finding {
+    pub fn severity(&self) -> Severity {
+        self.rule.severity()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Format {
+    Codes(&'static [&'static str]),
+    CommitteeId,
+    CandidateId,
+    ElectionCode,
+    Date,
+    Amount,
+}
+
+#[derive(Debug, Clone)]
+struct ColumnSpec {
+    name: &'static str,
+    required: bool,
+    max_length: Option<usize>,
+    format: Option<Format>,
+}
+
+fn column_format(column: &str) -> Option<Format> {
+    if let Some((_, format)) = SPEC_COLUMNS.iter().find(|(name, _)| *name == column) {
+        return match *format {
+            "committee_id" => Some(Format::CommitteeId),
+            "candidate_id" => Some(Format::CandidateId),
+            "election_code" => Some(Format::ElectionCode),
+            format => codes(format).map(Format::Codes),
+        };
+    }
+    if DATE_COLUMNS.contains(column) {
+        Some(Format::Date)
+    } else if FLOAT_COLUMNS.contains(column) {
+        Some(Format::Amount)
+    } else {
+        None
+    }
+}
+
+fn column_specs(form_type: &str, fec_version: &str) -> Option<Vec<ColumnSpec>> {
+    let columns = column_names_for_field(form_type, fec_version)?;
+    let fields = spec_fields(form_type, fec_version).unwrap_or_default();
+    Some(
+        columns
+            .iter()
+            .map(|column| {
+                let field = fields.iter().find(|(name, _, _)| name == column);
+                ColumnSpec {
+                    name: column.as_str(),
+                    required: field.is_some_and(|(_, required, _)| *required),
+                    max_length: field
+                        .map(|(_, _, max_length)| *max_length)
+                        .filter(|max_length| *max_length > 0),
+                    format: column_format(column),
+                }
+            })
+            .collect(),
+    )
+}
+
+// Checks records against the per-form spec table in spec.json: required
+// fields, max lengths, code lists and ID/date/amount formats.
+pub struct SpecValidator {
+    fec_version: String,
+    max_year: u32,
+    specs: HashMap<String, Option<Vec<ColumnSpec>>>,
+}
+
+impl SpecValidator {
+    pub fn new(fec_version: &str) -> Self {
+        Self {
+            fec_version: fec_version.to_owned(),
+            max_year: FecDate::today().year + 1,
+            specs: HashMap::new(),
+        }
+    }
+
+    // dates after this year are reported as out of range
+    pub fn with_max_year(mut self, max_year: u32) -> Self {
+        self.max_year = max_year;
+        self
+    }
+
+    pub fn validate_filing<R: Read>(
+        filing: &mut Filing<R>,
+    ) -> Result<Vec<Finding>, FilingRowReadError> {
+        let mut validator = Self::new(&filing.header.fec_version);
+        let mut findings = validator.validate_record(Some(1), &filing.header.header_record);
+        findings.extend(validator.validate_record(Some(2), filing.cover.record()));
+        while let Some(row) = filing.next_row() {
+            let row = row?;
+            let line = row.record.position().map(|position| position.line());
+            findings.extend(validator.validate_record(line, &row.record));
+        }
+        Ok(findings)
+    }
+
+    pub fn validate_record(&mut self, line: Option<u64>, record: &StringRecord) -> Vec<Finding> {
+        let form_type = record.get(0).unwrap_or_default();
+        let mut findings = vec![];
+        let finding =
+            |column: Option<&str>, value: Option<&str>, rule: Rule, message: String| Finding {
+                line,
+                row_type: form_type.to_owned(),
+                column: column.map(|c| c.to_owned()),
+                value: value.map(|v| v.to_owned()),
+                rule,
+                message,
+            };
+
+        let fec_version = &self.fec_version;
+        // only the columns' formats can be checked without a spec for the
+        // version, reported once per row type
+        if !self.specs.contains_key(form_type)
+            && column_names_for_field(form_type, fec_version).is_some()
+            && spec_fields(form_type, fec_version).is_none()
+        {
+            findings.push(finding(
+                None,
+                None,
+                Rule::NoSpec,
+                format!("no spec for row type '{form_type}' in FEC version {fec_version}"),
+            ));
+        }
+        let specs = self
+            .specs
+            .entry(form_type.to_owned())
+            .or_insert_with(|| column_specs(form_type, fec_version));
+        let specs = match specs {
+            Some(specs) => specs,
+            None => {
+                findings.push(finding(
+                    None,
+                    None,
+                    Rule::UnknownRowType,
+                    format!("unknown row type '{form_type}' for FEC version {fec_version}"),
+                ));
+                return findings;
+            }
+        };
+
+        for (idx, spec) in specs.iter().enumerate() {
+            let value = record.get(idx).unwrap_or_default().trim();
+            let column = Some(spec.name);
+            if value.is_empty() {
+                if spec.required {
+                    findings.push(finding(
+                        column,
+                        None,
+                        Rule::Required,
+                        format!("{} is required", spec.name),
+                    ));
+                }
+                continue;
+            }
+            if let Some(max_length) = spec.max_length {
+                let length = value.chars().count();
+                if length > max_length {
+                    findings.push(finding(
+                        column,
+                        Some(value),
+                        Rule::MaxLength,
+                        format!(
+                            "{} is {length} characters, longer than the maximum of {max_length}",
+                            spec.name
+                        ),
+                    ));
+                }
+            }
+            let (rule, message) = match spec.format {
+                Some(Format::Codes(codes)) if !codes.contains(&value) => (
+                    Rule::Code,
+                    format!(
+                        "'{value}' is not a valid {}, expected one of {}",
+                        spec.name,
+                        codes.join(", ")
+                    ),
+                ),
+                Some(Format::CommitteeId) if !COMMITTEE_ID.is_match(value) => (
+                    Rule::CommitteeId,
+                    format!("'{value}' is not a valid committee ID"),
+                ),
+                Some(Format::CandidateId) if !CANDIDATE_ID.is_match(value) => (
+                    Rule::CandidateId,
+                    format!("'{value}' is not a valid candidate ID"),
+                ),
+                Some(Format::ElectionCode) if !ELECTION_CODE.is_match(value) => (
+                    Rule::ElectionCode,
+                    format!("'{value}' is not a valid election code"),
+                ),
+                Some(Format::Date) => match FecDate::parse(value) {
+                    None => (
+                        Rule::Date,
+                        format!("'{value}' is not a valid YYYYMMDD date"),
+                    ),
+                    Some(date) if date.year < MIN_YEAR || date.year > self.max_year => (
+                        Rule::DateRange,
+                        format!("'{value}' is outside of {MIN_YEAR}-{}", self.max_year),
+                    ),
+                    Some(_) => continue,
+                },
+                Some(Format::Amount) if value.parse::<f64>().is_err() => {
+                    (Rule::Amount, format!("'{value}' is not a valid amount"))
+                }
+                _ => continue,
+            };
+            findings.push(finding(column, Some(value), rule, message));
+        }
+        findings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{test_filing, F3XN};
+
+    #[test]
+    fn spec_table() {
+        assert_eq!(codes("memo_code"), Some(&["X"][..]));
+        let fields = spec_fields("SA11AI", "8.4").unwrap();
+        assert!(fields.contains(&("transaction_id", true, 20)));
+        assert!(spec_fields("SA11AI", "5.3").is_none());
+        assert_eq!(
+            FecDate::parse("20240229"),
+            Some(FecDate {
+                year: 2024,
+                month: 2,
+                day: 29
+            })
+        );
+        assert_eq!(FecDate::parse("20230229"), None);
+        assert_eq!(FecDate::parse("2024-01-01"), None);
+        assert_eq!(
+            spec_fields("SA11AI", "6.4").map(|fields| fields.len()),
+            spec_fields("SA11AI", "8.4").map(|fields| fields.len())
+        );
+        for date in ["19700101", "19691231", "20000229", "20240301", "21001231"] {
+            let date = FecDate::parse(date).unwrap();
+            assert_eq!(
+                FecDate::from_days_since_epoch(date.days_since_epoch()),
+                date
+            );
+        }
+    }
+
+    #[test]
+    fn reports_missing_spec() {
+        let mut validator = SpecValidator::new("5.3").with_max_year(2025);
+        let mut sa = vec![""; 30];
+        sa[0] = "SA11AI";
+        sa[14] = "20260101";
+        let record = StringRecord::from(sa);
+        let findings: Vec<_> = (0..2)
+            .flat_map(|line| validator.validate_record(Some(line), &record))
+            .map(|f| (f.line, f.column, f.rule))
+            .collect();
+        assert_eq!(findings.iter().filter(|f| f.2 == Rule::NoSpec).count(), 1);
+        assert!(findings.contains(&(Some(0), None, Rule::NoSpec)));
+        assert!(findings.contains(&(
+            Some(1),
+            Some("contribution_date".to_owned()),
+            Rule::DateRange
+        )));
+    }
+
+    #[test]
+    fn validates_filing() {
+        let mut sa = vec![""; 45];
+        sa[0] = "SA11AI";
+        sa[1] = "C0000001";
+        sa[2] = "SA.1";
+        sa[5] = "XYZ";
+        sa[19] = "20241301";
+        sa[20] = "1o0.00";
+        sa[21] = "100.00";
+        sa[15] = "CAL";
+        sa[42] = "X";
+        let mut filing = test_filing(&[F3XN, &sa, &["SZ", "C00000001"]]);
+        let findings = SpecValidator::validate_filing(&mut filing).unwrap();
+        let rules: Vec<_> = findings
+            .iter()
+            .map(|f| (f.line, f.column.as_deref(), f.rule))
+            .collect();
+
+        assert!(rules.contains(&(
+            Some(3),
+            Some("filer_committee_id_number"),
+            Rule::CommitteeId
+        )));
+        assert!(rules.contains(&(Some(3), Some("entity_type"), Rule::Code)));
+        assert!(rules.contains(&(Some(3), Some("contributor_state"), Rule::MaxLength)));
+        assert!(rules.contains(&(Some(3), Some("contribution_date"), Rule::Date)));
+        assert!(rules.contains(&(Some(3), Some("contribution_amount"), Rule::Amount)));
+        assert!(rules.contains(&(Some(4), None, Rule::UnknownRowType)));
+        assert!(!rules
+            .iter()
+            .any(|(_, column, _)| *column == Some("memo_code")));
+        assert_eq!(
+            findings
+                .iter()
+                .filter(|f| f.severity() == Severity::Warning)
+                .count(),
+            2
+        );
+    }
+}
@@ -0,0 +1,273 @@
+use arrow::{
+    pyarrow::{PyArrowType, ToPyArrow},
+    record_batch::RecordBatch,
+};
+use fec_parser::{
+    arrow::{schema, ArrowBatchError, RecordBatchBuilder},
+    mappings::{column_names_for_field, field_idx},
+    Filing as FecFiling, FilingRow,
+};
+use pyo3::{
+    create_exception,
+    exceptions::PyException,
+    prelude::*,
+    types::{PyBytes, PyDict},
+};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{Cursor, Read},
+    path::Path,
+    sync::Arc,
+};
+
+create_exception!(libfec, FecError, PyException);
+
+// A parsed .fec filing. Rows are read lazily, so iterating over the filing
+// consumes them, and each row can only be read once. to_pandas/to_arrow read
+// the rest of the filing, and keep the rows of the other form types for
+// later calls.
+#[pyclass(unsendable, module = "libfec")]
+struct Filing {
+    filing: FecFiling<Box<dyn Read>>,
+    // rows read by to_arrow that weren't of the requested form type, by
+    // form type index
+    pending: HashMap<usize, (RecordBatchBuilder, Vec<RecordBatch>)>,
+}
+
+fn to_py_err(e: impl std::fmt::Display) -> PyErr {
+    FecError::new_err(e.to_string())
+}
+
+fn row_dict<'py>(py: Python<'py>, row: &FilingRow) -> PyResult<Bound<'py, PyDict>> {
+    let dict = PyDict::new(py);
+    dict.set_item("row_type", &row.row_type)?;
+    for (column, value) in row.iter_named() {
+        dict.set_item(column, value)?;
+    }
+    Ok(dict)
+}
+
+impl Filing {
+    fn open(rdr: Box<dyn Read>, filing_id: String, source_length: Option<usize>) -> PyResult<Self> {
+        let filing = FecFiling::from_reader(rdr, filing_id, source_length).map_err(to_py_err)?;
+        Ok(Self {
+            filing,
+            pending: HashMap::new(),
+        })
+    }
+
+    fn next_row(&mut self) -> PyResult<Option<FilingRow>> {
+        self.filing.next_row().transpose().map_err(to_py_err)
+    }
+
+    // the remaining rows of a form type as record batches, ex "SA" for
+    // every Schedule A row type, which all share the same columns. Rows of
+    // other form types are kept as batches for the next calls.
+    fn batches(&mut self, form_type: &str) -> PyResult<Vec<RecordBatch>> {
+        let form_type = field_idx(form_type)
+            .ok_or_else(|| to_py_err(ArrowBatchError::UnknownFormType(form_type.to_owned())))?;
+        while let Some(row) = self.next_row()? {
+            let Some(idx) = row.record.get(0).and_then(field_idx) else {
+                continue;
+            };
+            let (builder, batches) = self
+                .pending
+                .entry(idx)
+                .or_insert_with(|| (RecordBatchBuilder::new(row.columns()), vec![]));
+            batches.extend(
+                builder
+                    .push(&self.filing.filing_id, &row)
+                    .map_err(to_py_err)?,
+            );
+        }
+        let Some((mut builder, mut batches)) = self.pending.remove(&form_type) else {
+            return Ok(vec![]);
+        };
+        batches.extend(builder.finish().map_err(to_py_err)?);
+        Ok(batches)
+    }
+}
+
+// to_pandas/to_arrow follow the Python naming, and consume the rows
+#[allow(clippy::wrong_self_convention)]
+#[pymethods]
+impl Filing {
+    // from a .fec file, the filing ID is the file name without extension
+    #[staticmethod]
+    fn from_path(path: &str) -> PyResult<Self> {
+        let path = Path::new(path);
+        let file = File::open(path).map_err(to_py_err)?;
+        let filing_id = path
+            .file_stem()
+            .map(|v| v.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let source_length = file.metadata().map(|v| v.len() as usize).ok();
+        Self::open(Box::new(file), filing_id, source_length)
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (data, filing_id = ""))]
+    fn from_bytes(data: &Bound<'_, PyBytes>, filing_id: &str) -> PyResult<Self> {
+        let data = data.as_bytes().to_vec();
+        let len = data.len();
+        Self::open(Box::new(Cursor::new(data)), filing_id.to_owned(), Some(len))
+    }
+
+    #[getter]
+    fn filing_id(&self) -> &str {
+        &self.filing.filing_id
+    }
+
+    #[getter]
+    fn form_type(&self) -> &str {
+        &self.filing.cover.form_type
+    }
+
+    #[getter]
+    fn header<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let header = &self.filing.header;
+        let dict = PyDict::new(py);
+        dict.set_item("record_type", &header.record_type)?;
+        dict.set_item("ef_type", &header.ef_type)?;
+        dict.set_item("fec_version", &header.fec_version)?;
+        dict.set_item("soft_name", &header.soft_name)?;
+        dict.set_item("soft_ver", &header.soft_ver)?;
+        dict.set_item("report_id", &header.report_id)?;
+        dict.set_item("report_number", &header.report_number)?;
+        dict.set_item("comment", &header.comment)?;
+        Ok(dict)
+    }
+
+    #[getter]
+    fn cover<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+        for (column, value) in self.filing.cover.iter_named() {
+            dict.set_item(column, value)?;
+        }
+        Ok(dict)
+    }
+
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__<'py>(&mut self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyDict>>> {
+        self.next_row()?.map(|row| row_dict(py, &row)).transpose()
+    }
+
+    // pyarrow.Table of the remaining rows of a form type, ex "SA". Dates are
+    // date32 and amounts decimal128(14, 2), see fec_parser::arrow.
+    fn to_arrow<'py>(&mut self, py: Python<'py>, form_type: &str) -> PyResult<Bound<'py, PyAny>> {
+        let batches = self.batches(form_type)?;
+        // without any rows, the columns of the filing's version
+        let schema = match batches.first() {
+            Some(batch) => batch.schema(),
+            None => Arc::new(schema(
+                column_names_for_field(form_type, &self.filing.header.fec_version)
+                    .map(Vec::as_slice)
+                    .unwrap_or_default(),
+            )),
+        };
+        let batches = batches
+            .iter()
+            .map(|batch| batch.to_pyarrow(py))
+            .collect::<PyResult<Vec<_>>>()?;
+        let schema = PyArrowType(schema.as_ref().clone()).into_pyobject(py)?;
+        py.import("pyarrow")?
+            .getattr("Table")?
+            .call_method1("from_batches", (batches, schema))
+    }
+
+    // pandas.DataFrame of the remaining rows of a form type, ex "SA"
+    fn to_pandas<'py>(&mut self, py: Python<'py>, form_type: &str) -> PyResult<Bound<'py, PyAny>> {
+        self.to_arrow(py, form_type)?.call_method0("to_pandas")
+    }
+}
+
+#[pymodule]
+fn libfec(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Filing>()?;
+    m.add("FecError", m.py().get_type::<FecError>())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::AsArray;
+
+    #[test]
+    fn reads_rows() {
+        let contents = [
+            "HDR\u{1c}FEC\u{1c}8.4\u{1c}libfec\u{1c}0.1\u{1c}\u{1c}\u{1c}",
+            "F3XN\u{1c}C00000001\u{1c}Test PAC",
+            "SA11AI\u{1c}C00000001\u{1c}SA.1\u{1c}\u{1c}\u{1c}IND\u{1c}\u{1c}Doe\u{1c}Jane",
+            "SB21B\u{1c}C00000001\u{1c}SB.1",
+        ]
+        .join("\n");
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let data = PyBytes::new(py, contents.as_bytes());
+            let mut filing = Filing::from_bytes(&data, "1").unwrap();
+            assert_eq!(filing.form_type(), "F3XN");
+            let row = filing.__next__(py).unwrap().unwrap();
+            let last_name: String = row
+                .get_item("contributor_last_name")
+                .unwrap()
+                .unwrap()
+                .extract()
+                .unwrap();
+            assert_eq!(last_name, "Doe");
+
+            let batches = filing.batches("SB").unwrap();
+            assert_eq!(batches.len(), 1);
+            let ids = batches[0]
+                .column_by_name("transaction_id_number")
+                .unwrap()
+                .as_string::<i32>();
+            assert_eq!(ids.value(0), "SB.1");
+            assert!(filing.__next__(py).unwrap().is_none());
+            assert!(filing.batches("SB").unwrap().is_empty());
+        });
+    }
+
+    #[test]
+    fn keeps_other_form_types() {
+        let contents = [
+            "HDR\u{1c}FEC\u{1c}8.4\u{1c}libfec\u{1c}0.1\u{1c}\u{1c}\u{1c}",
+            "F3XN\u{1c}C00000001\u{1c}Test PAC",
+            "SB21B\u{1c}C00000001\u{1c}SB.1",
+            "SA11AI\u{1c}C00000001\u{1c}SA.1",
+            "SB21B\u{1c}C00000001\u{1c}SB.2",
+            "SA11B\u{1c}C00000001\u{1c}SA.2",
+        ]
+        .join("\n");
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let data = PyBytes::new(py, contents.as_bytes());
+            let mut filing = Filing::from_bytes(&data, "1").unwrap();
+            let ids = |batches: Vec<RecordBatch>, column: &str| -> Vec<String> {
+                batches
+                    .iter()
+                    .flat_map(|batch| {
+                        let ids = batch.column_by_name(column).unwrap().as_string::<i32>();
+                        ids.iter()
+                            .map(|id| id.unwrap_or_default().to_owned())
+                            .collect::<Vec<_>>()
+                    })
+                    .collect()
+            };
+            assert_eq!(
+                ids(filing.batches("SA").unwrap(), "transaction_id"),
+                ["SA.1", "SA.2"]
+            );
+            assert_eq!(
+                ids(filing.batches("SB").unwrap(), "transaction_id_number"),
+                ["SB.1", "SB.2"]
+            );
+            assert!(filing.batches("SA").unwrap().is_empty());
+            assert!(filing.batches("ZZ").is_err());
+        });
+    }
+}
@@ -0,0 +1,238 @@
+use fec_parser::{mappings::field_idx, Filing as FecFiling, FilingRow, FilingRowReadError};
+use js_sys::{Array, Object, Reflect, Uint8Array};
+use std::io::Cursor;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+
+#[wasm_bindgen]
+extern "C" {
+    pub type ReadableStream;
+    #[wasm_bindgen(method, js_name = getReader)]
+    fn get_reader(this: &ReadableStream) -> ReadableStreamDefaultReader;
+
+    type ReadableStreamDefaultReader;
+    #[wasm_bindgen(method)]
+    fn read(this: &ReadableStreamDefaultReader) -> js_sys::Promise;
+}
+
+// Rows read so far, so that pages can be requested in any order. Rows are
+// only parsed up to the last page that was asked for.
+struct Rows {
+    filing: FecFiling<Cursor<Vec<u8>>>,
+    rows: Vec<FilingRow>,
+    done: bool,
+}
+
+impl Rows {
+    // reads until `matches` rows matching the filter are cached, or the end
+    // of the filing
+    fn fill(&mut self, matches: usize, filter: Option<usize>) -> Result<(), FilingRowReadError> {
+        let mut count = self
+            .rows
+            .iter()
+            .filter(|row| row_matches(row, filter))
+            .count();
+        while count < matches && !self.done {
+            match self.filing.next_row() {
+                Some(row) => {
+                    let row = row?;
+                    if row_matches(&row, filter) {
+                        count += 1;
+                    }
+                    self.rows.push(row);
+                }
+                None => self.done = true,
+            }
+        }
+        Ok(())
+    }
+
+    fn page(
+        &mut self,
+        offset: usize,
+        limit: usize,
+        filter: Option<usize>,
+    ) -> Result<Vec<&FilingRow>, FilingRowReadError> {
+        self.fill(offset + limit, filter)?;
+        Ok(self
+            .rows
+            .iter()
+            .filter(|row| row_matches(row, filter))
+            .skip(offset)
+            .take(limit)
+            .collect())
+    }
+}
+
+// ex "SA" matches every Schedule A row type
+fn row_matches(row: &FilingRow, filter: Option<usize>) -> bool {
+    filter.is_none() || row.record.get(0).and_then(field_idx) == filter
+}
+
+fn set(object: &Object, key: &str, value: impl Into<JsValue>) -> Result<(), JsError> {
+    Reflect::set(object, &key.into(), &value.into())
+        .map(|_| ())
+        .map_err(|_| JsError::new(&format!("Could not set {key}")))
+}
+
+fn row_object(row: &FilingRow) -> Result<Object, JsError> {
+    let object = Object::new();
+    set(&object, "row_type", row.row_type.as_str())?;
+    for (column, value) in row.iter_named() {
+        set(&object, column, value)?;
+    }
+    Ok(object)
+}
+
+// A .fec filing parsed entirely in the browser, without any network or
+// filesystem access
+#[wasm_bindgen]
+pub struct Filing {
+    rows: Rows,
+}
+
+#[wasm_bindgen]
+impl Filing {
+    #[wasm_bindgen(constructor)]
+    pub fn new(data: Vec<u8>, filing_id: Option<String>) -> Result<Filing, JsError> {
+        let len = data.len();
+        let filing =
+            FecFiling::from_reader(Cursor::new(data), filing_id.unwrap_or_default(), Some(len))
+                .map_err(|e| JsError::new(&e.to_string()))?;
+        Ok(Self {
+            rows: Rows {
+                filing,
+                rows: vec![],
+                done: false,
+            },
+        })
+    }
+
+    // Reads a ReadableStream, ex `file.stream()`, to the end. The filing
+    // has to fit in memory either way, this saves copying it into a
+    // Uint8Array first.
+    #[wasm_bindgen(js_name = fromStream)]
+    pub async fn from_stream(
+        stream: ReadableStream,
+        filing_id: Option<String>,
+    ) -> Result<Filing, JsError> {
+        let reader = stream.get_reader();
+        let mut data = vec![];
+        loop {
+            let chunk = JsFuture::from(reader.read())
+                .await
+                .map_err(|_| JsError::new("Error reading stream"))?;
+            let done = Reflect::get(&chunk, &"done".into())
+                .map_err(|_| JsError::new("Invalid stream chunk"))?;
+            if done.is_truthy() {
+                break;
+            }
+            let value = Reflect::get(&chunk, &"value".into())
+                .map_err(|_| JsError::new("Invalid stream chunk"))?;
+            data.extend(Uint8Array::new(&value).to_vec());
+        }
+        Self::new(data, filing_id)
+    }
+
+    #[wasm_bindgen(getter, js_name = filingId)]
+    pub fn filing_id(&self) -> String {
+        self.rows.filing.filing_id.clone()
+    }
+
+    #[wasm_bindgen(getter, js_name = formType)]
+    pub fn form_type(&self) -> String {
+        self.rows.filing.cover.form_type.clone()
+    }
+
+    pub fn header(&self) -> Result<Object, JsError> {
+        let header = &self.rows.filing.header;
+        let object = Object::new();
+        set(&object, "record_type", header.record_type.as_str())?;
+        set(&object, "ef_type", header.ef_type.as_str())?;
+        set(&object, "fec_version", header.fec_version.as_str())?;
+        set(&object, "soft_name", header.soft_name.as_str())?;
+        set(&object, "soft_ver", header.soft_ver.as_str())?;
+        set(&object, "report_id", header.report_id.clone())?;
+        set(&object, "report_number", header.report_number.clone())?;
+        set(&object, "comment", header.comment.clone())?;
+        Ok(object)
+    }
+
+    // the cover record, which includes the summary page of F3 reports
+    pub fn cover(&self) -> Result<Object, JsError> {
+        let object = Object::new();
+        for (column, value) in self.rows.filing.cover.iter_named() {
+            set(&object, column, value)?;
+        }
+        Ok(object)
+    }
+
+    // `{rows, done}`, where `done` is true once there are no rows after this
+    // page. `formType` limits the rows to one form type, ex "SA".
+    pub fn rows(
+        &mut self,
+        offset: usize,
+        limit: usize,
+        #[wasm_bindgen(js_name = formType)] form_type: Option<String>,
+    ) -> Result<Object, JsError> {
+        let filter = match form_type {
+            Some(form_type) => Some(
+                field_idx(&form_type)
+                    .ok_or_else(|| JsError::new(&format!("Unknown form type {form_type}")))?,
+            ),
+            None => None,
+        };
+        let page = self
+            .rows
+            .page(offset, limit + 1, filter)
+            .map_err(|e| JsError::new(&e.to_string()))?;
+        let done = page.len() <= limit;
+        let rows = page
+            .into_iter()
+            .take(limit)
+            .map(row_object)
+            .collect::<Result<Array, _>>()?;
+        let object = Object::new();
+        set(&object, "rows", rows)?;
+        set(&object, "done", done)?;
+        Ok(object)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pages_rows() {
+        let mut contents = ["HDR", "FEC", "8.4", "libfec", "0.1", "", "", ""].join("\x1c");
+        contents += "\nF3XN\x1cC00000001\x1cTest PAC\n";
+        for i in 0..5 {
+            contents += &format!("SA11AI\x1cC00000001\x1cSA.{i}\n");
+            contents += &format!("SB21B\x1cC00000001\x1cSB.{i}\n");
+        }
+        let filing =
+            FecFiling::from_reader(Cursor::new(contents.into_bytes()), "1".to_owned(), None)
+                .unwrap();
+        let mut rows = Rows {
+            filing,
+            rows: vec![],
+            done: false,
+        };
+        let ids = |page: Vec<&FilingRow>| -> Vec<String> {
+            page.iter()
+                .map(|row| row.record.get(2).unwrap().to_owned())
+                .collect()
+        };
+        assert_eq!(
+            ids(rows.page(0, 3, None).unwrap()),
+            vec!["SA.0", "SB.0", "SA.1"]
+        );
+        // only as far as needed
+        assert_eq!(rows.rows.len(), 3);
+        let sb = field_idx("SB");
+        assert_eq!(ids(rows.page(3, 5, sb).unwrap()), vec!["SB.3", "SB.4"]);
+        assert!(rows.done);
+        assert_eq!(ids(rows.page(1, 1, None).unwrap()), vec!["SB.0"]);
+    }
+}